@@ -0,0 +1,85 @@
+// aabb.rs
+use raylib::prelude::Vector3;
+
+/// Caja alineada con los ejes que envuelve una primitiva (para estructuras de aceleración).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3, max: Vector3) -> Self {
+        Aabb { min, max }
+    }
+
+    /// Caja vacía: al unirla con cualquier otra da la otra.
+    pub fn empty() -> Self {
+        Aabb {
+            min: Vector3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Vector3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub fn from_points(points: &[Vector3]) -> Self {
+        points.iter().fold(Aabb::empty(), |aabb, &point| aabb.grow(point))
+    }
+
+    /// Caja de un disco de radio `radius` con centro `center` y normal `normal` (unitaria).
+    pub fn from_disk(center: Vector3, normal: Vector3, radius: f32) -> Self {
+        let extent = |n: f32| radius * (1.0 - n * n).max(0.0).sqrt();
+        let half = Vector3::new(extent(normal.x), extent(normal.y), extent(normal.z));
+        Aabb { min: center - half, max: center + half }
+    }
+
+    pub fn grow(&self, point: Vector3) -> Self {
+        Aabb { min: self.min.min(point), max: self.max.max(point) }
+    }
+
+    pub fn union(&self, other: &Aabb) -> Self {
+        Aabb { min: self.min.min(other.min), max: self.max.max(other.max) }
+    }
+
+    pub fn centroid(&self) -> Vector3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn extent(&self) -> Vector3 {
+        self.max - self.min
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let e = self.extent();
+        if e.x < 0.0 || e.y < 0.0 || e.z < 0.0 {
+            return 0.0;
+        }
+        2.0 * (e.x * e.y + e.y * e.z + e.z * e.x)
+    }
+
+    /// Eje (0 = X, 1 = Y, 2 = Z) en el que la caja es más larga.
+    pub fn longest_axis(&self) -> usize {
+        let e = self.extent();
+        if e.x >= e.y && e.x >= e.z { 0 } else if e.y >= e.z { 1 } else { 2 }
+    }
+
+    /// Test de "slabs": distancia de entrada del rayo a la caja si la cruza antes de `max_distance`.
+    /// `inv_direction` es `1 / dirección` por componentes, precalculado por quien recorre muchas cajas.
+    pub fn hit(&self, origin: &Vector3, inv_direction: &Vector3, max_distance: f32) -> Option<f32> {
+        let t1 = (self.min - *origin) * *inv_direction;
+        let t2 = (self.max - *origin) * *inv_direction;
+        let t_near = t1.min(t2);
+        let t_far = t1.max(t2);
+        let enter = t_near.x.max(t_near.y).max(t_near.z).max(0.0);
+        let exit = t_far.x.min(t_far.y).min(t_far.z).min(max_distance);
+        (enter <= exit).then_some(enter)
+    }
+}
+
+/// Componente de un vector por índice de eje.
+pub fn axis(v: Vector3, axis: usize) -> f32 {
+    match axis {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
@@ -0,0 +1,337 @@
+// animation.rs
+use raylib::prelude::Vector3;
+use std::ops::{Add, Mul, Sub};
+use std::sync::Arc;
+
+use crate::camera::Camera;
+use crate::material::Material;
+use crate::motion::MotionTransform;
+use crate::scene_graph::{NodeId, SceneGraph};
+use crate::transform::Transform;
+
+/// Claves por fotograma del movimiento de los nodos animados con desenfoque de movimiento.
+const MOTION_KEYS: usize = 4;
+
+/// Cómo se pasa de un fotograma clave al siguiente.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Mantiene el valor hasta la siguiente clave.
+    Step,
+    Linear,
+    /// Arranca y frena suavemente (smoothstep).
+    EaseInOut,
+    /// Curva de Catmull-Rom que pasa por las claves sin cambios bruscos de velocidad.
+    CatmullRom,
+}
+
+/// Valores que se pueden interpolar: escalares y vectores.
+pub trait Animatable: Copy + Add<Output = Self> + Sub<Output = Self> + Mul<f32, Output = Self> {}
+
+impl<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T>> Animatable for T {}
+
+/// Valor fijado en un instante; `interpolation` dice cómo se llega desde él a la clave siguiente.
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    pub interpolation: Interpolation,
+}
+
+/// Secuencia de fotogramas clave de una propiedad, ordenada por tiempo (en segundos).
+/// Antes de la primera clave y después de la última se mantiene el valor del extremo.
+#[derive(Debug, Clone)]
+pub struct Track<T> {
+    keys: Vec<Keyframe<T>>,
+}
+
+impl<T: Animatable> Default for Track<T> {
+    fn default() -> Self {
+        Track::new()
+    }
+}
+
+impl<T: Animatable> Track<T> {
+    pub fn new() -> Self {
+        Track { keys: Vec::new() }
+    }
+
+    /// Añade una clave; una clave en el mismo instante que otra la sustituye.
+    pub fn key(mut self, time: f32, value: T, interpolation: Interpolation) -> Self {
+        let keyframe = Keyframe { time, value, interpolation };
+        match self.keys.binary_search_by(|key| key.time.total_cmp(&time)) {
+            Ok(index) => self.keys[index] = keyframe,
+            Err(index) => self.keys.insert(index, keyframe),
+        }
+        self
+    }
+
+    pub fn keys(&self) -> &[Keyframe<T>] {
+        &self.keys
+    }
+
+    /// Instante de la última clave.
+    pub fn end(&self) -> f32 {
+        self.keys.last().map(|key| key.time).unwrap_or(0.0)
+    }
+
+    /// Valor en `time`; `None` si la pista está vacía.
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let first = self.keys.first()?;
+        let last = self.keys.last()?;
+        if time <= first.time {
+            return Some(first.value);
+        }
+        if time >= last.time {
+            return Some(last.value);
+        }
+        // Primera clave posterior a `time` (existe porque `time` < última)
+        let next = self.keys.partition_point(|key| key.time <= time);
+        let (a, b) = (&self.keys[next - 1], &self.keys[next]);
+        let t = (time - a.time) / (b.time - a.time);
+        Some(match a.interpolation {
+            Interpolation::Step => a.value,
+            Interpolation::Linear => lerp(a.value, b.value, t),
+            Interpolation::EaseInOut => lerp(a.value, b.value, t * t * (3.0 - 2.0 * t)),
+            Interpolation::CatmullRom => {
+                // En los extremos se repite la clave para no salirse de la pista
+                let before = self.keys[next.saturating_sub(2)].value;
+                let after = self.keys.get(next + 1).map(|key| key.value).unwrap_or(b.value);
+                catmull_rom(before, a.value, b.value, after, t)
+            }
+        })
+    }
+}
+
+fn lerp<T: Animatable>(a: T, b: T, t: f32) -> T {
+    a + (b - a) * t
+}
+
+/// Catmull-Rom uniforme entre `p1` y `p2`, con `p0` y `p3` como vecinos.
+fn catmull_rom<T: Animatable>(p0: T, p1: T, p2: T, p3: T, t: f32) -> T {
+    let (t2, t3) = (t * t, t * t * t);
+    let m1 = (p2 - p0) * 0.5;
+    let m2 = (p3 - p1) * 0.5;
+    p1 * (2.0 * t3 - 3.0 * t2 + 1.0) + m1 * (t3 - 2.0 * t2 + t) + p2 * (3.0 * t2 - 2.0 * t3) + m2 * (t3 - t2)
+}
+
+/// Pistas de la cámara. Mover `eye` y `center` por separado permite travellings y paneos.
+#[derive(Debug, Clone, Default)]
+pub struct CameraAnimation {
+    pub eye: Option<Track<Vector3>>,
+    pub center: Option<Track<Vector3>>,
+    /// Campo de visión vertical en radianes.
+    pub fov: Option<Track<f32>>,
+}
+
+/// Pistas de la transformación de un nodo del grafo: escala, giro (ángulos en radianes alrededor
+/// de X, Y y Z, en ese orden) y traslación. Reemplazan la transformación del nodo; los canales sin
+/// pista toman su valor neutro, así que un nodo que no está en el origen necesita su traslación.
+#[derive(Debug, Clone, Default)]
+pub struct NodeAnimation {
+    pub node: String,
+    pub translation: Option<Track<Vector3>>,
+    pub rotation: Option<Track<Vector3>>,
+    pub scale: Option<Track<Vector3>>,
+}
+
+/// Pistas de las luces de un nodo del grafo (la posición es relativa al nodo).
+#[derive(Debug, Clone, Default)]
+pub struct LightAnimation {
+    pub node: String,
+    pub position: Option<Track<Vector3>>,
+    pub color: Option<Track<Vector3>>,
+    pub intensity: Option<Track<f32>>,
+}
+
+/// Pistas de parámetros del material de un nodo. Se animan sobre el material que sustituye a la
+/// geometría en ese nodo o en el antepasado más cercano que lo tenga; sin él no hay efecto.
+#[derive(Debug, Clone, Default)]
+pub struct MaterialAnimation {
+    pub node: String,
+    pub diffuse: Option<Track<Vector3>>,
+    pub emission: Option<Track<Vector3>>,
+    pub roughness: Option<Track<f32>>,
+    pub reflectivity: Option<Track<f32>>,
+    pub transparency: Option<Track<f32>>,
+}
+
+/// Animación por fotogramas clave de una cámara y de los nodos de un grafo de escena.
+/// Los nodos se buscan por nombre, o por ruta si contiene `/` (como en `SceneGraph::find_path`).
+#[derive(Debug, Clone, Default)]
+pub struct Animation {
+    pub camera: CameraAnimation,
+    pub nodes: Vec<NodeAnimation>,
+    pub lights: Vec<LightAnimation>,
+    pub materials: Vec<MaterialAnimation>,
+}
+
+impl Animation {
+    pub fn new() -> Self {
+        Animation::default()
+    }
+
+    /// Instante de la última clave de todas las pistas.
+    pub fn duration(&self) -> f32 {
+        let camera = [track_end(&self.camera.eye), track_end(&self.camera.center), track_end(&self.camera.fov)];
+        let nodes = self.nodes.iter().flat_map(|node| [track_end(&node.translation), track_end(&node.rotation), track_end(&node.scale)]);
+        let lights = self.lights.iter().flat_map(|light| [track_end(&light.position), track_end(&light.color), track_end(&light.intensity)]);
+        let materials = self.materials.iter().flat_map(|material| {
+            [
+                track_end(&material.diffuse),
+                track_end(&material.emission),
+                track_end(&material.roughness),
+                track_end(&material.reflectivity),
+                track_end(&material.transparency),
+            ]
+        });
+        camera.into_iter().chain(nodes).chain(lights).chain(materials).fold(0.0, f32::max)
+    }
+
+    /// Solo modifica la cámara; sirve para escenas que no son un grafo.
+    pub fn apply_camera(&self, time: f32, camera: &mut Camera) {
+        camera.motion = None;
+        let eye = sample(&self.camera.eye, time);
+        let center = sample(&self.camera.center, time);
+        let fov = sample(&self.camera.fov, time);
+        if eye.is_none() && center.is_none() && fov.is_none() {
+            return;
+        }
+        camera.eye = eye.unwrap_or(camera.eye);
+        camera.center = center.unwrap_or(camera.center);
+        camera.fov = fov.unwrap_or(camera.fov);
+        camera.update_basis();
+    }
+
+    /// Como `apply_camera`, y además guarda dónde está la cámara al final de un fotograma
+    /// de `frame_duration` segundos para el desenfoque de movimiento.
+    pub fn apply_camera_motion(&self, time: f32, frame_duration: f32, camera: &mut Camera) {
+        self.apply_camera(time, camera);
+        if self.camera.eye.is_none() && self.camera.center.is_none() {
+            return;
+        }
+        let end = time + frame_duration;
+        let eye = sample(&self.camera.eye, end).unwrap_or(camera.eye);
+        let center = sample(&self.camera.center, end).unwrap_or(camera.center);
+        camera.motion = Some((eye, center));
+    }
+
+    /// Deja la cámara y el grafo como están en `time`. Hay que volver a aplanar el grafo después.
+    pub fn apply(&self, time: f32, camera: &mut Camera, graph: &mut SceneGraph) {
+        self.apply_camera(time, camera);
+
+        for animation in &self.nodes {
+            let Some(id) = find_node(graph, &animation.node) else { continue };
+            if let Some(transform) = animation.transform(time) {
+                graph.set_transform(id, transform);
+                graph.node_mut(id).motion = None;
+            }
+        }
+
+        for animation in &self.lights {
+            let Some(id) = find_node(graph, &animation.node) else { continue };
+            for light in &mut graph.node_mut(id).lights {
+                light.position = sample(&animation.position, time).unwrap_or(light.position);
+                light.color = sample(&animation.color, time).unwrap_or(light.color);
+                light.intensity = sample(&animation.intensity, time).unwrap_or(light.intensity);
+            }
+        }
+
+        for animation in &self.materials {
+            let Some(id) = find_node(graph, &animation.node) else { continue };
+            let Some(base) = inherited_material(graph, id) else { continue };
+            let mut material = Material::clone(&base);
+            if let Some(diffuse) = sample(&animation.diffuse, time) {
+                material.diffuse = diffuse;
+                if let Some(pbr) = material.pbr.as_mut() {
+                    pbr.base_color = diffuse;
+                }
+            }
+            if let Some(roughness) = sample(&animation.roughness, time) {
+                material.roughness = roughness;
+                if let Some(pbr) = material.pbr.as_mut() {
+                    pbr.roughness = roughness;
+                }
+            }
+            material.emission = sample(&animation.emission, time).unwrap_or(material.emission);
+            material.reflectivity = sample(&animation.reflectivity, time).unwrap_or(material.reflectivity);
+            material.transparency = sample(&animation.transparency, time).unwrap_or(material.transparency);
+            graph.node_mut(id).material = Some(Arc::new(material));
+        }
+    }
+
+    /// Como `apply`, y además da a los nodos animados su movimiento durante un fotograma de
+    /// `frame_duration` segundos (y a la cámara) para el desenfoque de movimiento.
+    pub fn apply_motion(&self, time: f32, frame_duration: f32, camera: &mut Camera, graph: &mut SceneGraph) {
+        self.apply(time, camera, graph);
+        self.apply_camera_motion(time, frame_duration, camera);
+        for animation in &self.nodes {
+            let Some(id) = find_node(graph, &animation.node) else { continue };
+            let keys: Vec<(f32, Transform)> = (0..MOTION_KEYS)
+                .filter_map(|i| {
+                    let fraction = i as f32 / (MOTION_KEYS - 1) as f32;
+                    animation.transform(time + frame_duration * fraction).map(|transform| (fraction, transform))
+                })
+                .collect();
+            if !keys.is_empty() {
+                graph.node_mut(id).motion = Some(MotionTransform::new(keys));
+            }
+        }
+    }
+}
+
+impl NodeAnimation {
+    /// Transformación del nodo en `time`; `None` si no tiene pistas.
+    pub fn transform(&self, time: f32) -> Option<Transform> {
+        if self.translation.is_none() && self.rotation.is_none() && self.scale.is_none() {
+            return None;
+        }
+        let scale = sample(&self.scale, time).unwrap_or(Vector3::new(1.0, 1.0, 1.0));
+        let rotation = sample(&self.rotation, time).unwrap_or(Vector3::zero());
+        let translation = sample(&self.translation, time).unwrap_or(Vector3::zero());
+        Some(
+            Transform::scale(scale)
+                .rotated(Vector3::new(1.0, 0.0, 0.0), rotation.x)
+                .rotated(Vector3::new(0.0, 1.0, 0.0), rotation.y)
+                .rotated(Vector3::new(0.0, 0.0, 1.0), rotation.z)
+                .translated(translation),
+        )
+    }
+}
+
+fn sample<T: Animatable>(track: &Option<Track<T>>, time: f32) -> Option<T> {
+    track.as_ref().and_then(|track| track.sample(time))
+}
+
+fn track_end<T: Animatable>(track: &Option<Track<T>>) -> f32 {
+    track.as_ref().map(Track::end).unwrap_or(0.0)
+}
+
+fn find_node(graph: &SceneGraph, name: &str) -> Option<NodeId> {
+    if name.contains('/') { graph.find_path(name) } else { graph.find(name) }
+}
+
+/// Material del nodo o, si no tiene, del antepasado más cercano que lo tenga.
+fn inherited_material(graph: &SceneGraph, id: NodeId) -> Option<Arc<Material>> {
+    let mut current = Some(id);
+    while let Some(node) = current {
+        if let Some(material) = &graph.node(node).material {
+            return Some(material.clone());
+        }
+        current = graph.parent(node);
+    }
+    None
+}
+
+/// Nombre de archivo del fotograma `frame`: el grupo de `#` del patrón se sustituye por el número
+/// rellenado con ceros hasta ese ancho (`"frames/frame_####.png"` → `"frames/frame_0012.png"`).
+/// Sin `#` el número se añade antes de la extensión.
+pub fn frame_path(pattern: &str, frame: u32) -> String {
+    let Some(start) = pattern.find('#') else {
+        return match pattern.rfind('.') {
+            Some(dot) => format!("{}_{:04}{}", &pattern[..dot], frame, &pattern[dot..]),
+            None => format!("{}_{:04}", pattern, frame),
+        };
+    };
+    let width = pattern[start..].chars().take_while(|&c| c == '#').count();
+    format!("{}{:0width$}{}", &pattern[..start], frame, &pattern[start + width..], width = width)
+}
@@ -0,0 +1,170 @@
+// area_light.rs
+use raylib::prelude::*;
+use std::f32::consts::PI;
+
+use crate::cube::{Cube, CubeFace};
+use crate::light::Light;
+use crate::quad::Quad;
+use crate::sampling::random_f32;
+use crate::transform::Transform;
+use crate::triangle::Triangle;
+
+/// Las muestras se separan un poco de la superficie emisora para que el rayo de sombra
+/// no choque con la propia cara que emite.
+const SURFACE_OFFSET: f32 = 1e-3;
+
+/// Rectángulo emisor: `corner + edge_u * s + edge_v * t` con s, t en [0, 1]; si es un
+/// triángulo, solo la mitad con s + t ≤ 1.
+#[derive(Debug, Clone, Copy)]
+pub struct EmissivePatch {
+    pub corner: Vector3,
+    pub edge_u: Vector3,
+    pub edge_v: Vector3,
+    pub normal: Vector3,   // Hacia fuera, hacia donde emite
+    pub emission: Vector3, // Radiancia emitida
+    pub area: f32,
+    pub triangle: bool,
+}
+
+impl EmissivePatch {
+    pub fn new(corner: Vector3, edge_u: Vector3, edge_v: Vector3, normal: Vector3, emission: Vector3) -> Self {
+        EmissivePatch { corner, edge_u, edge_v, normal, emission, area: edge_u.cross(edge_v).length(), triangle: false }
+    }
+
+    /// Triángulo que emite por su cara delantera (vértices en sentido antihorario).
+    pub fn triangle(vertices: [Vector3; 3], emission: Vector3) -> Self {
+        let [v0, v1, v2] = vertices;
+        let (edge_u, edge_v) = (v1 - v0, v2 - v0);
+        let normal = edge_u.cross(edge_v).normalized();
+        EmissivePatch { corner: v0, edge_u, edge_v, normal, emission, area: edge_u.cross(edge_v).length() * 0.5, triangle: true }
+    }
+
+    /// El mismo emisor colocado con `transform` (la radiancia no cambia, el área sí).
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        let edge_u = transform.direction(self.edge_u);
+        let edge_v = transform.direction(self.edge_v);
+        let area = edge_u.cross(edge_v).length() * if self.triangle { 0.5 } else { 1.0 };
+        EmissivePatch {
+            corner: transform.point(self.corner),
+            edge_u,
+            edge_v,
+            normal: transform.normal(self.normal),
+            area,
+            ..*self
+        }
+    }
+
+    /// Punto uniforme sobre la superficie.
+    fn sample_point(&self) -> Vector3 {
+        let (mut s, mut t) = (random_f32(), random_f32());
+        if self.triangle && s + t > 1.0 {
+            (s, t) = (1.0 - s, 1.0 - t);
+        }
+        self.corner + self.edge_u * s + self.edge_v * t
+    }
+}
+
+/// Geometría emisiva muestreada sobre su superficie: cada muestra es una luz puntual
+/// con el peso de un estimador de Monte Carlo (coseno en el emisor, distancia al cuadrado, área).
+#[derive(Debug, Clone)]
+pub struct AreaLight {
+    pub patches: Vec<EmissivePatch>,
+}
+
+impl AreaLight {
+    /// Luz de área con los emisores dados, o `None` si no hay ninguno.
+    pub fn from_patches(patches: Vec<EmissivePatch>) -> Option<Self> {
+        (!patches.is_empty()).then_some(AreaLight { patches })
+    }
+
+    /// Caras emisivas de un cubo (cada cara usa su propio material).
+    /// Devuelve `None` si ninguna cara emite luz.
+    pub fn from_cube(cube: &Cube) -> Option<Self> {
+        let min = cube.min_bounds;
+        let size = cube.max_bounds - cube.min_bounds;
+        let x = Vector3::new(size.x, 0.0, 0.0);
+        let y = Vector3::new(0.0, size.y, 0.0);
+        let z = Vector3::new(0.0, 0.0, size.z);
+        let max = cube.max_bounds;
+
+        let patches: Vec<EmissivePatch> = CubeFace::ALL
+            .iter()
+            .filter_map(|&face| {
+                let (corner, edge_u, edge_v, normal) = match face {
+                    CubeFace::Right => (Vector3::new(max.x, min.y, min.z), y, z, Vector3::new(1.0, 0.0, 0.0)),
+                    CubeFace::Left => (min, y, z, Vector3::new(-1.0, 0.0, 0.0)),
+                    CubeFace::Top => (Vector3::new(min.x, max.y, min.z), x, z, Vector3::new(0.0, 1.0, 0.0)),
+                    CubeFace::Bottom => (min, x, z, Vector3::new(0.0, -1.0, 0.0)),
+                    CubeFace::Front => (Vector3::new(min.x, min.y, max.z), x, y, Vector3::new(0.0, 0.0, 1.0)),
+                    CubeFace::Back => (min, x, y, Vector3::new(0.0, 0.0, -1.0)),
+                };
+                let emission = cube.material_for(&normal).emission;
+                (emission.dot(emission) > 0.0).then(|| EmissivePatch::new(corner, edge_u, edge_v, normal, emission))
+            })
+            .collect();
+
+        (!patches.is_empty()).then_some(AreaLight { patches })
+    }
+
+    /// Un quad emisivo ilumina por su cara delantera (`edge_u × edge_v`).
+    pub fn from_quad(quad: &Quad) -> Option<Self> {
+        let emission = quad.material.emission;
+        (emission.dot(emission) > 0.0).then(|| AreaLight {
+            patches: vec![EmissivePatch::new(quad.corner, quad.edge_u, quad.edge_v, quad.normal(), emission)],
+        })
+    }
+
+    /// Un triángulo emisivo ilumina por su cara delantera (la de la normal geométrica).
+    pub fn from_triangle(triangle: &Triangle) -> Option<Self> {
+        let emission = triangle.material.emission;
+        (emission.dot(emission) > 0.0).then(|| AreaLight {
+            patches: vec![EmissivePatch::triangle(triangle.vertices, emission)],
+        })
+    }
+
+    /// Añade a `lights` `samples` luces puntuales repartidas sobre las caras que ven `point`.
+    /// La intensidad de cada una es L · cos θ_luz · A / (π · r² · muestras), de forma que la suma
+    /// aproxima la irradiancia de la superficie emisora con la misma convención que `Light`
+    /// (una superficie blanca mate devuelve `intensity · cos θ`).
+    pub fn sample_lights(&self, point: Vector3, samples: u32, lights: &mut Vec<Light>) {
+        let visible: Vec<&EmissivePatch> = self
+            .patches
+            .iter()
+            .filter(|patch| patch.normal.dot(point - patch.corner) > 0.0)
+            .collect();
+        let total_area: f32 = visible.iter().map(|patch| patch.area).sum();
+        if total_area <= 0.0 {
+            return;
+        }
+
+        let samples = samples.max(1);
+        for _ in 0..samples {
+            // Elegir una cara en proporción a su área y un punto uniforme sobre ella
+            let mut target = random_f32() * total_area;
+            let patch = visible
+                .iter()
+                .find(|patch| {
+                    target -= patch.area;
+                    target <= 0.0
+                })
+                .unwrap_or(&visible[visible.len() - 1]);
+            let position = patch.sample_point();
+
+            let to_point = point - position;
+            let distance_squared = to_point.dot(to_point);
+            if distance_squared <= 1e-8 {
+                continue;
+            }
+            let cos_light = patch.normal.dot(to_point) / distance_squared.sqrt();
+            if cos_light <= 0.0 {
+                continue;
+            }
+            let weight = cos_light * total_area / (PI * distance_squared * samples as f32);
+            lights.push(Light::new(
+                position + patch.normal * SURFACE_OFFSET,
+                patch.emission.normalized(),
+                patch.emission.length() * weight,
+            ));
+        }
+    }
+}
@@ -0,0 +1,148 @@
+// bvh.rs
+use raylib::prelude::Vector3;
+
+use crate::aabb::{Aabb, axis};
+
+/// Número máximo de elementos en una hoja.
+const MAX_LEAF_SIZE: usize = 4;
+/// Cubetas para evaluar la heurística de área de superficie (SAH) en cada división.
+const SAH_BUCKETS: usize = 12;
+
+#[derive(Debug, Clone)]
+struct BvhNode {
+    bounds: Aabb,
+    /// Hoja: primer índice en `Bvh::indices`. Nodo interior: índice del hijo derecho
+    /// (el izquierdo va justo después del padre).
+    offset: usize,
+    /// Elementos de la hoja; 0 en los nodos interiores.
+    count: usize,
+}
+
+/// Jerarquía de volúmenes envolventes sobre una lista de elementos con caja conocida.
+/// No guarda los elementos: quien la usa los prueba con los índices que devuelve el recorrido.
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    indices: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn build(bounds: &[Aabb]) -> Self {
+        let mut bvh = Bvh { nodes: Vec::new(), indices: (0..bounds.len()).collect() };
+        if !bounds.is_empty() {
+            let centroids: Vec<Vector3> = bounds.iter().map(|b| b.centroid()).collect();
+            bvh.build_node(bounds, &centroids, 0, bounds.len());
+        }
+        bvh
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.nodes.first().map(|node| node.bounds).unwrap_or_else(Aabb::empty)
+    }
+
+    fn build_node(&mut self, bounds: &[Aabb], centroids: &[Vector3], start: usize, end: usize) -> usize {
+        let node_bounds = self.indices[start..end].iter().fold(Aabb::empty(), |acc, &i| acc.union(&bounds[i]));
+        let node_index = self.nodes.len();
+        self.nodes.push(BvhNode { bounds: node_bounds, offset: start, count: end - start });
+        if end - start <= MAX_LEAF_SIZE {
+            return node_index;
+        }
+
+        let centroid_bounds = self.indices[start..end].iter().fold(Aabb::empty(), |acc, &i| acc.grow(centroids[i]));
+        let split_axis = centroid_bounds.longest_axis();
+        let axis_min = axis(centroid_bounds.min, split_axis);
+        let axis_extent = axis(centroid_bounds.extent(), split_axis);
+        if axis_extent <= 0.0 {
+            return node_index; // Todos los centroides coinciden: no se puede dividir
+        }
+
+        // SAH con cubetas: se elige el plano que minimiza área × número de elementos a cada lado
+        let bucket_of = |i: usize| {
+            let t = (axis(centroids[i], split_axis) - axis_min) / axis_extent;
+            ((t * SAH_BUCKETS as f32) as usize).min(SAH_BUCKETS - 1)
+        };
+        let mut bucket_bounds = [Aabb::empty(); SAH_BUCKETS];
+        let mut bucket_counts = [0usize; SAH_BUCKETS];
+        for &i in &self.indices[start..end] {
+            let b = bucket_of(i);
+            bucket_counts[b] += 1;
+            bucket_bounds[b] = bucket_bounds[b].union(&bounds[i]);
+        }
+        let mut best = (f32::INFINITY, 0);
+        for split in 1..SAH_BUCKETS {
+            let (left, left_count) = (0..split).fold((Aabb::empty(), 0), |(a, c), b| (a.union(&bucket_bounds[b]), c + bucket_counts[b]));
+            let (right, right_count) = (split..SAH_BUCKETS).fold((Aabb::empty(), 0), |(a, c), b| (a.union(&bucket_bounds[b]), c + bucket_counts[b]));
+            if left_count == 0 || right_count == 0 {
+                continue;
+            }
+            let cost = left.surface_area() * left_count as f32 + right.surface_area() * right_count as f32;
+            if cost < best.0 {
+                best = (cost, split);
+            }
+        }
+
+        let mid = if best.0.is_finite() {
+            let split = best.1;
+            partition(&mut self.indices[start..end], |&i| bucket_of(i) < split) + start
+        } else {
+            // Reparto degenerado: se corta por la mediana
+            self.indices[start..end].sort_by(|&a, &b| axis(centroids[a], split_axis).total_cmp(&axis(centroids[b], split_axis)));
+            (start + end) / 2
+        };
+
+        self.nodes[node_index].count = 0;
+        self.build_node(bounds, centroids, start, mid);
+        let right = self.build_node(bounds, centroids, mid, end);
+        self.nodes[node_index].offset = right;
+        node_index
+    }
+
+    /// Recorre los nodos que cruza el rayo, del más cercano al más lejano, y llama a `test`
+    /// con cada elemento candidato y la distancia máxima actual. `test` devuelve la distancia
+    /// de un impacto más cercano, si lo hay, para podar el resto del recorrido.
+    pub fn traverse(&self, origin: &Vector3, direction: &Vector3, max_distance: f32, mut test: impl FnMut(usize, f32) -> Option<f32>) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let inv_direction = Vector3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+        let mut closest = max_distance;
+        let mut stack = vec![0usize];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if node.bounds.hit(origin, &inv_direction, closest).is_none() {
+                continue;
+            }
+            if node.count > 0 {
+                for &item in &self.indices[node.offset..node.offset + node.count] {
+                    if let Some(distance) = test(item, closest) {
+                        closest = closest.min(distance);
+                    }
+                }
+                continue;
+            }
+            // Visitar primero el hijo más cercano
+            let (left, right) = (node_index + 1, node.offset);
+            let left_hit = self.nodes[left].bounds.hit(origin, &inv_direction, closest);
+            let right_hit = self.nodes[right].bounds.hit(origin, &inv_direction, closest);
+            match (left_hit, right_hit) {
+                (Some(l), Some(r)) if l <= r => stack.extend([right, left]),
+                (Some(_), Some(_)) => stack.extend([left, right]),
+                (Some(_), None) => stack.push(left),
+                (None, Some(_)) => stack.push(right),
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+/// Reordena `items` dejando primero los que cumplen `predicate`; devuelve cuántos son.
+fn partition<T>(items: &mut [T], predicate: impl Fn(&T) -> bool) -> usize {
+    let mut first = 0;
+    for i in 0..items.len() {
+        if predicate(&items[i]) {
+            items.swap(first, i);
+            first += 1;
+        }
+    }
+    first
+}
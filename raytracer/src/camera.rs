@@ -1,12 +1,86 @@
 // camera.rs
 use raylib::prelude::*;
+use std::f32::consts::PI;
 
+use crate::ray_intersect::RayIntersect;
+use crate::sampling::{orthonormal_basis, uniform_sample_disk, uniform_sample_polygon};
+
+/// Alto del sensor para convertir número f en apertura: el de una cámara de 35 mm, con la escena
+/// en metros (un bloque mide 1).
+const SENSOR_HEIGHT: f32 = 0.024;
+
+/// Forma del diafragma; es la forma que toman los puntos de luz desenfocados (bokeh).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ApertureShape {
+    Circular,
+    /// Diafragma de `blades` láminas, girado `rotation` radianes.
+    Polygon { blades: u32, rotation: f32 },
+}
+
+/// Inclinación máxima de la vista en modo vuelo, para no pasar por la vertical.
+const MAX_PITCH: f32 = 89.0 * PI / 180.0;
+
+/// Cómo responde la cámara a los controles: girando alrededor de `center` o volando libremente
+/// en primera persona.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    Orbit,
+    Fly,
+}
+
+/// Cómo se convierte cada píxel en un rayo.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    /// Perspectiva clásica con el campo de visión vertical `Camera::fov`.
+    Perspective,
+    /// Rayos paralelos a `forward` desde un rectángulo de `height` unidades de alto centrado en el ojo
+    /// (sin perspectiva, para planos isométricos).
+    Orthographic { height: f32 },
+    /// Ojo de pez equidistante: el ángulo con `forward` crece en proporción a la distancia al centro
+    /// hasta `fov / 2` en el borde del círculo inscrito; fuera del círculo no hay imagen.
+    Fisheye { fov: f32 },
+    /// Panorama de 360° × 180° (longitud en horizontal y latitud en vertical) centrado en `forward`.
+    Equirectangular,
+    /// Las seis caras de 90° de un cubo alrededor del ojo en una rejilla de 3×2 (ver `cube_map_direction`).
+    CubeMap,
+}
+
+impl Projection {
+    /// Si los rayos de la proyección pasan por un plano de enfoque (las demás enfocan a una distancia fija).
+    fn is_planar(&self) -> bool {
+        matches!(self, Projection::Perspective | Projection::Orthographic { .. })
+    }
+}
+
+/// Dirección (en el espacio de la cámara, mirando a -Z) del punto (a, b) ∈ [-1, 1]² de una cara del
+/// cubo, con `a` hacia la derecha y `b` hacia arriba de la imagen. Las caras van en el orden de la
+/// rejilla: arriba derecha, izquierda y cielo; abajo suelo, delante y detrás. Las laterales tienen
+/// el cielo arriba; la del cielo toca a la de delante por su borde inferior y la del suelo por el superior.
+fn cube_map_direction(face: usize, a: f32, b: f32) -> Vector3 {
+    let direction = match face {
+        0 => Vector3::new(1.0, b, a),   // Derecha (+X)
+        1 => Vector3::new(-1.0, b, -a), // Izquierda (-X)
+        2 => Vector3::new(a, 1.0, b),   // Cielo (+Y)
+        3 => Vector3::new(a, -1.0, -b), // Suelo (-Y)
+        4 => Vector3::new(a, b, -1.0),  // Delante (-Z)
+        _ => Vector3::new(-a, b, 1.0),  // Detrás (+Z)
+    };
+    direction.normalized()
+}
+
+#[derive(Clone)]
 pub struct Camera {
     pub eye: Vector3,     // donde esta la camara en el mundo, ejemplo (7,100,10)
     pub center: Vector3,  // que mira la camara (mario), ejemplo (7,100,5)
     pub up: Vector3,      // donde esta arriba
     pub forward: Vector3,
     pub right: Vector3,
+    pub fov: f32,         // campo de visión vertical en radianes
+    pub motion: Option<(Vector3, Vector3)>, // eye y center al final del fotograma, para el desenfoque de movimiento
+    pub aperture_radius: f32, // radio de la lente; 0.0 es una cámara estenopeica (todo enfocado)
+    pub focus_distance: f32,  // distancia (a lo largo de forward) del plano que queda nítido
+    pub aperture_shape: ApertureShape,
+    pub projection: Projection,
     changed: bool,        // para optimizar actualizaciones
 }
 
@@ -18,6 +92,12 @@ impl Camera {
             up,
             forward: Vector3::zero(),
             right: Vector3::zero(),
+            fov: PI / 3.0,
+            motion: None,
+            aperture_radius: 0.0,
+            focus_distance: (center - eye).length(),
+            aperture_shape: ApertureShape::Circular,
+            projection: Projection::Perspective,
             changed: true,
         };
 
@@ -25,6 +105,22 @@ impl Camera {
         camera
     }
 
+    /// Vista ortográfica isométrica de `center` (las tres caras de un bloque igual de inclinadas)
+    /// con `height` unidades de escena en vertical
+    pub fn set_isometric(&mut self, height: f32) {
+        // Lo bastante lejos para no cortar la escena; en ortográfica la distancia no cambia la imagen
+        self.eye = self.center + Vector3::new(1.0, 1.0, 1.0).normalized() * (height * 4.0).max(50.0);
+        self.up = Vector3::new(0.0, 1.0, 0.0);
+        self.projection = Projection::Orthographic { height };
+        self.update_basis();
+    }
+
+    /// Alto en unidades de escena que abarca la vista en el punto `center` (para pasar de
+    /// perspectiva a ortográfica sin que cambie el encuadre)
+    pub fn view_height(&self) -> f32 {
+        2.0 * (self.center - self.eye).length() * (self.fov * 0.5).tan()
+    }
+
     pub fn update_basis(&mut self) {
         self.forward = (self.center - self.eye).normalized();
         self.right = self.forward.cross(self.up).normalized();
@@ -69,6 +165,172 @@ impl Camera {
         self.update_basis();
     }
 
+    /// La cámara en el instante `time` del fotograma (0 = inicio, 1 = final) si se está moviendo
+    pub fn at(&self, time: f32) -> Camera {
+        let mut camera = self.clone();
+        if let Some((eye, center)) = self.motion {
+            camera.eye = self.eye + (eye - self.eye) * time;
+            camera.center = self.center + (center - self.center) * time;
+            camera.update_basis();
+        }
+        camera
+    }
+
+    /// Distancia focal equivalente al campo de visión con un sensor de 35 mm
+    pub fn focal_length(&self) -> f32 {
+        SENSOR_HEIGHT * 0.5 / (self.fov * 0.5).tan()
+    }
+
+    /// Abre el diafragma al número f dado (f/2 deja poca profundidad de campo, f/16 casi todo nítido)
+    pub fn set_f_stop(&mut self, f_stop: f32) {
+        self.aperture_radius = self.focal_length() / (2.0 * f_stop.max(0.1));
+        self.changed = true;
+    }
+
+    pub fn f_stop(&self) -> f32 {
+        self.focal_length() / (2.0 * self.aperture_radius)
+    }
+
+    /// Rayo (origen y dirección normalizada) que pasa por el píxel (x, y) de una imagen de
+    /// width × height según la proyección; `None` si el píxel queda fuera de ella (ojo de pez)
+    pub fn primary_ray(&self, x: i32, y: i32, width: i32, height: i32) -> Option<(Vector3, Vector3)> {
+        let aspect_ratio = width as f32 / height as f32;
+        let screen_x = (2.0 * x as f32) / width as f32 - 1.0;
+        let screen_y = -(2.0 * y as f32) / height as f32 + 1.0;
+
+        match self.projection {
+            Projection::Perspective => {
+                let perspective_scale = (self.fov * 0.5).tan();
+                let screen_x = screen_x * aspect_ratio * perspective_scale;
+                let screen_y = screen_y * perspective_scale;
+                let ray_direction = Vector3::new(screen_x, screen_y, -1.0).normalized();
+                Some((self.eye, self.basis_change(&ray_direction)))
+            }
+            Projection::Orthographic { height: view_height } => {
+                let half_height = view_height * 0.5;
+                let origin = self.eye + self.right * (screen_x * aspect_ratio * half_height) + self.up * (screen_y * half_height);
+                Some((origin, self.forward))
+            }
+            Projection::Fisheye { fov } => {
+                // Coordenadas respecto al lado corto para que el círculo quepa en la imagen
+                let (px, py) = if aspect_ratio >= 1.0 { (screen_x * aspect_ratio, screen_y) } else { (screen_x, screen_y / aspect_ratio) };
+                let radius = (px * px + py * py).sqrt();
+                if radius > 1.0 {
+                    return None;
+                }
+                let theta = radius * fov * 0.5;
+                let phi = py.atan2(px);
+                let ray_direction = Vector3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), -theta.cos());
+                Some((self.eye, self.basis_change(&ray_direction)))
+            }
+            Projection::Equirectangular => {
+                let longitude = screen_x * PI;
+                let latitude = screen_y * PI * 0.5;
+                let ray_direction = Vector3::new(
+                    longitude.sin() * latitude.cos(),
+                    latitude.sin(),
+                    -longitude.cos() * latitude.cos(),
+                );
+                Some((self.eye, self.basis_change(&ray_direction)))
+            }
+            Projection::CubeMap => {
+                // Celda de la rejilla y posición del centro del píxel dentro de ella, en [-1, 1]
+                let cell_x = (x as f32 + 0.5) * 3.0 / width as f32;
+                let cell_y = (y as f32 + 0.5) * 2.0 / height as f32;
+                let column = cell_x.floor().min(2.0);
+                let row = cell_y.floor().min(1.0);
+                let a = 2.0 * (cell_x - column) - 1.0;
+                let b = 1.0 - 2.0 * (cell_y - row);
+                let ray_direction = cube_map_direction(row as usize * 3 + column as usize, a, b);
+                Some((self.eye, self.basis_change(&ray_direction)))
+            }
+        }
+    }
+
+    /// Rayo de la lente delgada a partir del de la cámara estenopeica: sale de un punto al azar del
+    /// diafragma y pasa por el punto enfocado del rayo original (en el plano de enfoque, o a
+    /// `focus_distance` en las proyecciones que no son planas)
+    pub fn lens_ray(&self, origin: Vector3, direction: Vector3) -> (Vector3, Vector3) {
+        if self.aperture_radius <= 0.0 {
+            return (origin, direction);
+        }
+        let (u, v) = match self.aperture_shape {
+            ApertureShape::Circular => uniform_sample_disk(),
+            ApertureShape::Polygon { blades, rotation } => uniform_sample_polygon(blades, rotation),
+        };
+        // En las panorámicas la lente se orienta hacia cada rayo
+        let (lens_u, lens_v, focus) = if self.projection.is_planar() {
+            (self.right, self.up, self.focus_distance / direction.dot(self.forward))
+        } else {
+            let (tangent, bitangent) = orthonormal_basis(direction);
+            (tangent, bitangent, self.focus_distance)
+        };
+        let focus_point = origin + direction * focus;
+        let origin = origin + lens_u * (u * self.aperture_radius) + lens_v * (v * self.aperture_radius);
+        (origin, (focus_point - origin).normalized())
+    }
+
+    /// Enfoca lo que hay en el centro de la pantalla en el instante `time` del fotograma; si no hay
+    /// nada, deja el enfoque como estaba. Devuelve la nueva distancia de enfoque
+    pub fn auto_focus(&mut self, objects: &[Box<dyn RayIntersect>], time: f32) -> Option<f32> {
+        let distance = objects
+            .iter()
+            .map(|object| object.ray_intersect(&self.eye, &self.forward, time))
+            .filter(|hit| hit.is_intersecting)
+            .map(|hit| hit.distance)
+            .fold(f32::INFINITY, f32::min);
+        if !distance.is_finite() {
+            return None;
+        }
+        self.focus_distance = distance;
+        self.changed = true;
+        Some(distance)
+    }
+
+    /// Desplaza el ojo y el punto de mira juntos `right` unidades a la derecha de la vista,
+    /// `up` en la vertical del mundo y `forward` hacia donde se mira (modo vuelo)
+    pub fn fly(&mut self, right: f32, up: f32, forward: f32) {
+        let offset = self.right * right + Vector3::new(0.0, up, 0.0) + self.forward * forward;
+        self.eye += offset;
+        self.center += offset;
+        self.update_basis();
+    }
+
+    /// Gira la vista desde el ojo: `yaw` alrededor de la vertical del mundo (positivo a la izquierda)
+    /// y `pitch` alrededor de right (positivo hacia arriba), sin pasar de la vertical.
+    /// El punto de mira se mantiene a la misma distancia para poder volver a orbitar
+    pub fn look(&mut self, yaw: f32, pitch: f32) {
+        let world_up = Vector3::new(0.0, 1.0, 0.0);
+        let distance = (self.center - self.eye).length();
+
+        let turn = Matrix::rotate(world_up, yaw);
+        let mut forward = self.forward.transform_with(turn);
+        let mut up = self.up.transform_with(turn);
+
+        let current_pitch = forward.y.clamp(-1.0, 1.0).asin();
+        let new_pitch = (current_pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+        let right = forward.cross(up).normalized();
+        let tilt = Matrix::rotate(right, new_pitch - current_pitch);
+        forward = forward.transform_with(tilt);
+        up = up.transform_with(tilt);
+
+        self.center = self.eye + forward.normalized() * distance;
+        self.up = up;
+        self.update_basis();
+    }
+
+    /// Inclina la cámara `angle` radianes alrededor de la dirección de vista (positivo a la derecha)
+    pub fn roll(&mut self, angle: f32) {
+        self.up = self.up.transform_with(Matrix::rotate(self.forward, angle));
+        self.update_basis();
+    }
+
+    /// Quita la inclinación lateral y vuelve a poner arriba la vertical del mundo
+    pub fn reset_roll(&mut self) {
+        self.up = Vector3::new(0.0, 1.0, 0.0);
+        self.update_basis();
+    }
+
     /// Verifica si la cámara ha cambiado desde la última vez que se llamó esta función
     pub fn is_changed(&mut self) -> bool {
         let changed = self.changed;
@@ -0,0 +1,118 @@
+// capsule.rs
+use raylib::prelude::Vector3;
+use std::f32::consts::PI;
+
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect, Span, spans_from_hits, wrap_uv};
+use crate::sampling::orthonormal_basis;
+
+/// Cápsula: todos los puntos a distancia `radius` del segmento `start`–`end`
+/// (un cilindro rematado por dos semiesferas). Es un sólido con normales hacia fuera.
+/// UV: u alrededor del eje y v a lo largo de toda la cápsula, de polo a polo.
+pub struct Capsule {
+    pub start: Vector3,
+    pub end: Vector3,
+    pub radius: f32,
+    pub material: Material,
+}
+
+impl Capsule {
+    pub fn new(start: Vector3, end: Vector3, radius: f32, material: Material) -> Self {
+        Capsule { start, end, radius, material }
+    }
+
+    /// Eje unitario y longitud del segmento (si los extremos coinciden es una esfera y el eje es Y).
+    fn axis(&self) -> (Vector3, f32) {
+        let segment = self.end - self.start;
+        let length = segment.length();
+        if length > 1e-6 { (segment / length, length) } else { (Vector3::new(0.0, 1.0, 0.0), 0.0) }
+    }
+
+    /// Distancias (ordenadas, de cualquier signo) a las que el rayo cruza la superficie.
+    pub fn hits(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Vec<f32> {
+        let (axis, length) = self.axis();
+        let o = *ray_origin - self.start;
+        let oy = o.dot(axis);
+        let dy = ray_direction.dot(axis);
+        let mut hits = Vec::with_capacity(4);
+        let mut solve = |a: f32, b: f32, c: f32, keep: &dyn Fn(f32) -> bool| {
+            let discriminant = b * b - 4.0 * a * c;
+            if a > 1e-12 && discriminant >= 0.0 {
+                let root = discriminant.sqrt();
+                for t in [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)] {
+                    if keep(oy + dy * t) {
+                        hits.push(t);
+                    }
+                }
+            }
+        };
+
+        // Tramo cilíndrico: la parte perpendicular al eje mide `radius`
+        let o_perp = o - axis * oy;
+        let d_perp = *ray_direction - axis * dy;
+        let r2 = self.radius * self.radius;
+        solve(d_perp.dot(d_perp), 2.0 * o_perp.dot(d_perp), o_perp.dot(o_perp) - r2, &|y| y > 0.0 && y < length);
+
+        // Semiesferas: cada esfera solo cuenta por fuera del tramo cilíndrico
+        let d2 = ray_direction.dot(*ray_direction);
+        solve(d2, 2.0 * o.dot(*ray_direction), o.dot(o) - r2, &|y| y <= 0.0);
+        // Con los extremos coincidentes las dos semiesferas forman una sola esfera
+        if length > 0.0 {
+            let o_end = *ray_origin - self.end;
+            solve(d2, 2.0 * o_end.dot(*ray_direction), o_end.dot(o_end) - r2, &|y| y >= length);
+        } else {
+            solve(d2, 2.0 * o.dot(*ray_direction), o.dot(o) - r2, &|y| y > 0.0);
+        }
+
+        hits.sort_by(|a, b| a.total_cmp(b));
+        hits
+    }
+
+    /// Normal, UV y base tangente en un punto de la superficie.
+    fn surface(&self, point: Vector3) -> (Vector3, f32, f32, Vector3, Vector3) {
+        let (axis, length) = self.axis();
+        let y = (point - self.start).dot(axis);
+        let closest = self.start + axis * y.clamp(0.0, length);
+        let normal = (point - closest).normalized();
+
+        let (axis_x, axis_z) = orthonormal_basis(axis);
+        let radial_vector = normal - axis * normal.dot(axis);
+        let radial_distance = radial_vector.length();
+        let radial = if radial_distance > 1e-6 { radial_vector / radial_distance } else { axis_x };
+        let u = wrap_uv(radial.dot(axis_z).atan2(radial.dot(axis_x)) / (2.0 * PI));
+        let total_length = length + 2.0 * self.radius;
+        let v = ((y + self.radius) / total_length).clamp(0.0, 1.0);
+
+        // dP/dv sigue el meridiano (paralelo al eje en el tramo recto, curvado en las semiesferas)
+        let around = axis.cross(radial);
+        let meridian = normal.cross(around).normalized();
+        let tangent = around * (2.0 * PI * (self.radius * radial_distance).max(1e-3));
+        (normal, u, v, tangent, meridian * total_length)
+    }
+
+    /// Intersección en el punto del rayo a `distance`, que está sobre la superficie.
+    fn hit_at(&self, ray_origin: &Vector3, ray_direction: &Vector3, distance: f32) -> Intersect {
+        let point = *ray_origin + *ray_direction * distance;
+        let (normal, u, v, tangent, bitangent) = self.surface(point);
+        Intersect::new(self.material.clone(), distance, normal, point, u, v).with_tangent_frame(tangent, bitangent)
+    }
+}
+
+impl RayIntersect for Capsule {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Intersect {
+        match self.hits(ray_origin, ray_direction).into_iter().find(|t| *t > 0.001) {
+            Some(distance) => self.hit_at(ray_origin, ray_direction, distance),
+            None => Intersect::empty(),
+        }
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Vec<Span> {
+        spans_from_hits(&self.hits(ray_origin, ray_direction), |t| self.hit_at(ray_origin, ray_direction, t))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = Vector3::new(self.radius, self.radius, self.radius);
+        Some(Aabb::new(self.start.min(self.end) - r, self.start.max(self.end) + r))
+    }
+}
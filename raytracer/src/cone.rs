@@ -0,0 +1,142 @@
+// cone.rs
+use raylib::prelude::Vector3;
+use std::f32::consts::PI;
+
+use crate::aabb::Aabb;
+use crate::disk::disk_surface;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect, Span, spans_from_hits, wrap_uv};
+use crate::sampling::orthonormal_basis;
+
+/// Cono con base circular de radio `radius` centrada en `base` y vértice a `height` sobre ella
+/// a lo largo de `axis`. Con `capped` la base está cerrada y es un sólido.
+/// UV del lateral: u alrededor del eje, v de la base (0) al vértice (1); la base usa UV polares.
+pub struct Cone {
+    pub base: Vector3,
+    pub axis: Vector3,
+    pub radius: f32,
+    pub height: f32,
+    pub capped: bool,
+    pub material: Material,
+}
+
+impl Cone {
+    pub fn new(base: Vector3, axis: Vector3, radius: f32, height: f32, material: Material) -> Self {
+        Cone {
+            base,
+            axis: axis.normalized(),
+            radius,
+            height,
+            capped: true,
+            material,
+        }
+    }
+
+    /// Cono abierto por la base, que se ve también por dentro.
+    pub fn open(mut self) -> Self {
+        self.capped = false;
+        self
+    }
+
+    pub fn apex(&self) -> Vector3 {
+        self.base + self.axis * self.height
+    }
+
+    /// Distancias (ordenadas, de cualquier signo) a las que el rayo cruza la superficie.
+    pub fn hits(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Vec<f32> {
+        let (axis_x, axis_z) = orthonormal_basis(self.axis);
+        let o = *ray_origin - self.base;
+        let (ox, oy, oz) = (o.dot(axis_x), o.dot(self.axis), o.dot(axis_z));
+        let (dx, dy, dz) = (ray_direction.dot(axis_x), ray_direction.dot(self.axis), ray_direction.dot(axis_z));
+        let mut hits = Vec::with_capacity(3);
+
+        // Lateral: x² + z² = (k · (h - y))² con k = r / h y 0 ≤ y ≤ h
+        let k = self.radius / self.height;
+        let k2 = k * k;
+        let w = self.height - oy;
+        let a = dx * dx + dz * dz - k2 * dy * dy;
+        let b = 2.0 * (ox * dx + oz * dz + k2 * w * dy);
+        let c = ox * ox + oz * oz - k2 * w * w;
+        let roots: Vec<f32> = if a.abs() < 1e-9 {
+            // Rayo paralelo a una generatriz: un único corte
+            if b.abs() > 1e-12 { vec![-c / b] } else { Vec::new() }
+        } else {
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                Vec::new()
+            } else {
+                let root = discriminant.sqrt();
+                vec![(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+            }
+        };
+        // La ecuación también describe el cono reflejado sobre el vértice; se descarta
+        hits.extend(roots.into_iter().filter(|t| (0.0..=self.height).contains(&(oy + dy * t))));
+
+        if self.capped && dy.abs() > 1e-12 {
+            let t = -oy / dy;
+            let (x, z) = (ox + dx * t, oz + dz * t);
+            if x * x + z * z <= self.radius * self.radius {
+                hits.push(t);
+            }
+        }
+        hits.sort_by(|a, b| a.total_cmp(b));
+        hits
+    }
+
+    /// Normal hacia fuera, UV y base tangente en un punto de la superficie.
+    fn surface(&self, point: Vector3) -> (Vector3, f32, f32, Vector3, Vector3) {
+        let local = point - self.base;
+        let y = local.dot(self.axis);
+        let radial_vector = local - self.axis * y;
+        let radial_distance = radial_vector.length();
+        let k = self.radius / self.height;
+
+        // Cerca del plano de la base y dentro del borde: es la tapa
+        let side_gap = (radial_distance - k * (self.height - y)).abs();
+        if self.capped && y.abs() < side_gap {
+            let normal = -self.axis;
+            let (u, v, tangent, bitangent) = disk_surface(self.base, normal, self.radius, point);
+            return (normal, u, v, tangent, bitangent);
+        }
+
+        let (axis_x, axis_z) = orthonormal_basis(self.axis);
+        let radial = if radial_distance > 1e-6 { radial_vector / radial_distance } else { axis_x };
+        let u = wrap_uv(radial.dot(axis_z).atan2(radial.dot(axis_x)) / (2.0 * PI));
+        let v = (y / self.height).clamp(0.0, 1.0);
+        // El gradiente de x² + z² - k²(h - y)² es proporcional a radial + k · eje
+        let normal = (radial + self.axis * k).normalized();
+        let around = self.axis.cross(radial);
+        (normal, u, v, around * (2.0 * PI * radial_distance.max(1e-3)), self.axis * self.height - radial * self.radius)
+    }
+
+    /// Intersección en el punto del rayo a `distance`, que está sobre la superficie.
+    fn hit_at(&self, ray_origin: &Vector3, ray_direction: &Vector3, distance: f32) -> Intersect {
+        let point = *ray_origin + *ray_direction * distance;
+        let (mut normal, u, v, tangent, bitangent) = self.surface(point);
+        if !self.capped && normal.dot(*ray_direction) > 0.0 {
+            normal = -normal;
+        }
+        Intersect::new(self.material.clone(), distance, normal, point, u, v).with_tangent_frame(tangent, bitangent)
+    }
+}
+
+impl RayIntersect for Cone {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Intersect {
+        match self.hits(ray_origin, ray_direction).into_iter().find(|t| *t > 0.001) {
+            Some(distance) => self.hit_at(ray_origin, ray_direction, distance),
+            None => Intersect::empty(),
+        }
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Vec<Span> {
+        // Sin tapas no encierra ningún volumen
+        if !self.capped {
+            return Vec::new();
+        }
+        spans_from_hits(&self.hits(ray_origin, ray_direction), |t| self.hit_at(ray_origin, ray_direction, t))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_disk(self.base, self.axis, self.radius).grow(self.apex()))
+    }
+}
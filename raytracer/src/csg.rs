@@ -0,0 +1,173 @@
+// csg.rs
+use raylib::prelude::Vector3;
+
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect, Span};
+
+/// Cruces a menos de esta distancia se consideran simultáneos (caras coplanarias).
+const COINCIDENT_EPSILON: f32 = 1e-4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+/// Geometría constructiva de sólidos: combina dos objetos cualesquiera como volúmenes.
+/// Con `Difference` el objeto derecho se resta del izquierdo y sus caras, vueltas hacia dentro,
+/// forman las paredes del hueco. Los operandos deben ser sólidos cerrados (o un `Plane`, que
+/// cuenta como semiespacio) y pueden ser a su vez otros `Csg`.
+pub struct Csg {
+    pub operation: CsgOperation,
+    pub left: Box<dyn RayIntersect>,
+    pub right: Box<dyn RayIntersect>,
+    /// Material para las caras que aporta el objeto derecho en una diferencia o intersección
+    /// (la piedra "cortada"); si es `None` se conserva el material del objeto derecho.
+    pub cut_material: Option<Material>,
+}
+
+/// Un cruce de la frontera de uno de los dos operandos.
+struct Crossing {
+    from_right: bool,
+    entering: bool,
+    hit: Intersect,
+}
+
+impl Csg {
+    fn new(operation: CsgOperation, left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg {
+            operation,
+            left: Box::new(left),
+            right: Box::new(right),
+            cut_material: None,
+        }
+    }
+
+    pub fn union(left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection(left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg::new(CsgOperation::Intersection, left, right)
+    }
+
+    /// `left` menos `right`.
+    pub fn difference(left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg::new(CsgOperation::Difference, left, right)
+    }
+
+    pub fn with_cut_material(mut self, material: Material) -> Self {
+        self.cut_material = Some(material);
+        self
+    }
+
+    /// Si un punto está dentro del resultado sabiendo si está dentro de cada operando.
+    fn contains(&self, in_left: bool, in_right: bool) -> bool {
+        match self.operation {
+            CsgOperation::Union => in_left || in_right,
+            CsgOperation::Intersection => in_left && in_right,
+            CsgOperation::Difference => in_left && !in_right,
+        }
+    }
+
+    /// Superficie del resultado en un cruce de un operando.
+    fn boundary(&self, crossing: &Crossing) -> Intersect {
+        let mut hit = crossing.hit.clone();
+        if crossing.from_right && self.operation != CsgOperation::Union {
+            if let Some(material) = &self.cut_material {
+                hit.material = material.clone();
+            }
+            // El hueco de una diferencia es el interior del objeto derecho visto desde dentro.
+            // Invertir la tangente mantiene tangente × bitangente = normal.
+            if self.operation == CsgOperation::Difference {
+                hit.normal = -hit.normal;
+                hit.tangent = -hit.tangent;
+            }
+        }
+        hit
+    }
+}
+
+impl RayIntersect for Csg {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Intersect {
+        self.ray_intervals(ray_origin, ray_direction, time)
+            .into_iter()
+            .flat_map(|span| [span.enter, span.exit])
+            .find(|hit| hit.is_intersecting && hit.distance > 0.001)
+            .unwrap_or_else(Intersect::empty)
+    }
+
+    /// Recorre en orden los cruces de ambos operandos y se queda con los que cambian
+    /// el estado dentro/fuera del resultado.
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Vec<Span> {
+        let mut crossings = Vec::new();
+        for (from_right, object) in [(false, &self.left), (true, &self.right)] {
+            for span in object.ray_intervals(ray_origin, ray_direction, time) {
+                crossings.push(Crossing { from_right, entering: true, hit: span.enter });
+                crossings.push(Crossing { from_right, entering: false, hit: span.exit });
+            }
+        }
+        crossings.sort_by(|a, b| a.hit.distance.total_cmp(&b.hit.distance));
+
+        let mut spans = Vec::new();
+        let mut enter: Option<Intersect> = None;
+        let (mut in_left, mut in_right) = (false, false);
+        let mut inside = false;
+        let mut start = 0;
+        while start < crossings.len() {
+            // Los cruces simultáneos se aplican juntos para no abrir huecos de grosor cero
+            let first = crossings[start].hit.distance;
+            let mut end = start;
+            while end < crossings.len()
+                && (crossings[end].hit.distance == first || crossings[end].hit.distance - first < COINCIDENT_EPSILON)
+            {
+                end += 1;
+            }
+            let group = &crossings[start..end];
+            start = end;
+
+            let before = (in_left, in_right);
+            for crossing in group {
+                if crossing.from_right { in_right = crossing.entering } else { in_left = crossing.entering }
+            }
+            let now = self.contains(in_left, in_right);
+            if now == inside {
+                continue;
+            }
+
+            // La superficie es la del cruce que por sí solo provoca el cambio
+            let crossing = group
+                .iter()
+                .find(|crossing| {
+                    let (mut left, mut right) = before;
+                    if crossing.from_right { right = crossing.entering } else { left = crossing.entering }
+                    self.contains(left, right) == now
+                })
+                .unwrap_or(&group[0]);
+            let boundary = self.boundary(crossing);
+            if now {
+                enter = Some(boundary);
+            } else {
+                let enter = enter.take().unwrap_or_else(|| Intersect::at_infinity(f32::NEG_INFINITY));
+                spans.push(Span { enter, exit: boundary });
+            }
+            inside = now;
+        }
+        spans
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let left = self.left.bounding_box();
+        let right = self.right.bounding_box();
+        match self.operation {
+            CsgOperation::Union => Some(left?.union(&right?)),
+            CsgOperation::Intersection => match (left, right) {
+                (Some(a), Some(b)) => Some(Aabb::new(a.min.max(b.min), a.max.min(b.max))),
+                (a, b) => a.or(b),
+            },
+            CsgOperation::Difference => left,
+        }
+    }
+}
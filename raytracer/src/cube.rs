@@ -1,12 +1,58 @@
 // src/cube.rs
 use raylib::prelude::Vector3;
-use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::ray_intersect::{Intersect, RayIntersect, Span};
 use crate::material::Material;
+use crate::aabb::Aabb;
+use crate::area_light::{AreaLight, EmissivePatch};
+
+/// Cara de un cubo alineado con los ejes, identificada por la dirección de su normal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    Right,  // +X
+    Left,   // -X
+    Top,    // +Y
+    Bottom, // -Y
+    Front,  // +Z
+    Back,   // -Z
+}
+
+impl CubeFace {
+    pub const ALL: [CubeFace; 6] = [CubeFace::Right, CubeFace::Left, CubeFace::Top, CubeFace::Bottom, CubeFace::Front, CubeFace::Back];
+
+    pub fn from_normal(normal: &Vector3) -> Self {
+        if normal.x.abs() > 0.5 {
+            if normal.x > 0.0 { CubeFace::Right } else { CubeFace::Left }
+        } else if normal.y.abs() > 0.5 {
+            if normal.y > 0.0 { CubeFace::Top } else { CubeFace::Bottom }
+        } else if normal.z > 0.0 {
+            CubeFace::Front
+        } else {
+            CubeFace::Back
+        }
+    }
+
+    /// Nombre usado en las bibliotecas de materiales (`[grass.top]`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            CubeFace::Right => "right",
+            CubeFace::Left => "left",
+            CubeFace::Top => "top",
+            CubeFace::Bottom => "bottom",
+            CubeFace::Front => "front",
+            CubeFace::Back => "back",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        CubeFace::ALL.into_iter().find(|face| face.name() == name)
+    }
+}
 
 pub struct Cube {
     pub min_bounds: Vector3,
     pub max_bounds: Vector3,
     pub material: Material,
+    pub face_materials: Vec<(CubeFace, Material)>, // Materiales que sustituyen a `material` en caras concretas
 }
 
 impl Cube {
@@ -17,9 +63,37 @@ impl Cube {
             min_bounds: center - half_size,
             max_bounds: center + half_size,
             material,
+            face_materials: Vec::new(),
+        }
+    }
+
+    /// Crea una caja (no necesariamente cúbica) entre dos esquinas opuestas.
+    pub fn from_bounds(min_bounds: Vector3, max_bounds: Vector3, material: Material) -> Self {
+        Self {
+            min_bounds: min_bounds.min(max_bounds),
+            max_bounds: min_bounds.max(max_bounds),
+            material,
+            face_materials: Vec::new(),
         }
     }
 
+    /// Usa otro material en una cara (p. ej. la parte superior de un bloque de pasto).
+    pub fn with_face_material(mut self, face: CubeFace, material: Material) -> Self {
+        self.face_materials.retain(|(f, _)| *f != face);
+        self.face_materials.push((face, material));
+        self
+    }
+
+    /// Material de la cara con esa normal.
+    pub fn material_for(&self, normal: &Vector3) -> &Material {
+        let face = CubeFace::from_normal(normal);
+        self.face_materials
+            .iter()
+            .find(|(f, _)| *f == face)
+            .map(|(_, material)| material)
+            .unwrap_or(&self.material)
+    }
+
     /// Calcula las coordenadas UV para texturizar, basándose en el punto de intersección y la normal de la cara.
     fn get_uv(&self, point: &Vector3, normal: &Vector3) -> (f32, f32) {
         let size = self.max_bounds - self.min_bounds;
@@ -38,11 +112,22 @@ impl Cube {
         }
         (u, v)
     }
+
+    /// Devuelve (tangente, bitangente) de la cara: las direcciones en las que crecen u y v según `get_uv`.
+    fn get_tangent_frame(&self, normal: &Vector3) -> (Vector3, Vector3) {
+        if normal.x.abs() > 0.5 { // u sigue a Z, v sigue a Y
+            (Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 1.0, 0.0))
+        } else if normal.y.abs() > 0.5 { // u sigue a X, v sigue a Z
+            (Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0))
+        } else { // u sigue a X, v sigue a Y
+            (Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0))
+        }
+    }
 }
 
-impl RayIntersect for Cube {
-    /// Implementa el test de intersección rayo-cubo usando el método "Slab".
-    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+impl Cube {
+    /// Test "slab": distancias (de cualquier signo) de entrada y salida del rayo en el cubo.
+    fn slab(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<(f32, f32)> {
         let inv_dir = Vector3::new(1.0 / ray_direction.x, 1.0 / ray_direction.y, 1.0 / ray_direction.z);
 
         let mut tmin = (self.min_bounds.x - ray_origin.x) * inv_dir.x;
@@ -56,7 +141,7 @@ impl RayIntersect for Cube {
         if tymin > tymax { std::mem::swap(&mut tymin, &mut tymax); }
 
         if (tmin > tymax) || (tymin > tmax) {
-            return Intersect::empty();
+            return None;
         }
 
         if tymin > tmin { tmin = tymin; }
@@ -68,20 +153,17 @@ impl RayIntersect for Cube {
         if tzmin > tzmax { std::mem::swap(&mut tzmin, &mut tzmax); }
 
         if (tmin > tzmax) || (tzmin > tmax) {
-            return Intersect::empty();
+            return None;
         }
 
         if tzmin > tmin { tmin = tzmin; }
         if tzmax < tmax { tmax = tzmax; }
 
-        // Si tmin es negativo, el rayo empieza dentro del cubo, usamos tmax.
-        let distance = if tmin > 0.001 { tmin } else { tmax };
-
-        // Si la distancia es demasiado pequeña o negativa, no hay intersección visible.
-        if distance < 0.001 {
-            return Intersect::empty();
-        }
+        Some((tmin, tmax))
+    }
 
+    /// Intersección en el punto del rayo a `distance`, que está sobre la superficie del cubo.
+    fn hit_at(&self, ray_origin: &Vector3, ray_direction: &Vector3, distance: f32) -> Intersect {
         let point = *ray_origin + *ray_direction * distance;
         
         // Se determina la normal de la cara intersectada comparando la posición del punto
@@ -97,14 +179,52 @@ impl RayIntersect for Cube {
         else if (point.z - self.max_bounds.z).abs() < epsilon { normal.z = 1.0; }
 
         let (u, v) = self.get_uv(&point, &normal);
+        let (tangent, bitangent) = self.get_tangent_frame(&normal);
 
         Intersect::new(
-            self.material.clone(),
+            self.material_for(&normal).clone(),
             distance,
             normal,
             point,
             u,
             v,
-        )
+        ).with_tangent_frame(tangent, bitangent)
+    }
+}
+
+impl RayIntersect for Cube {
+    /// Implementa el test de intersección rayo-cubo usando el método "Slab".
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Intersect {
+        let Some((tmin, tmax)) = self.slab(ray_origin, ray_direction) else {
+            return Intersect::empty();
+        };
+
+        // Si tmin es negativo, el rayo empieza dentro del cubo, usamos tmax.
+        let distance = if tmin > 0.001 { tmin } else { tmax };
+
+        // Si la distancia es demasiado pequeña o negativa, no hay intersección visible.
+        if distance < 0.001 {
+            return Intersect::empty();
+        }
+
+        self.hit_at(ray_origin, ray_direction, distance)
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Vec<Span> {
+        match self.slab(ray_origin, ray_direction) {
+            Some((tmin, tmax)) => vec![Span {
+                enter: self.hit_at(ray_origin, ray_direction, tmin),
+                exit: self.hit_at(ray_origin, ray_direction, tmax),
+            }],
+            None => Vec::new(),
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.min_bounds, self.max_bounds))
+    }
+
+    fn emissive_patches(&self) -> Vec<EmissivePatch> {
+        AreaLight::from_cube(self).map(|light| light.patches).unwrap_or_default()
     }
 }
\ No newline at end of file
@@ -0,0 +1,137 @@
+// cylinder.rs
+use raylib::prelude::Vector3;
+use std::f32::consts::PI;
+
+use crate::aabb::Aabb;
+use crate::disk::disk_surface;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect, Span, spans_from_hits, wrap_uv};
+use crate::sampling::orthonormal_basis;
+
+/// Cilindro de radio `radius` que sube `height` desde el centro de su base a lo largo de `axis`.
+/// Con `capped` se cierra con dos tapas y es un sólido (normales hacia fuera); sin tapas es un
+/// tubo que se ve por dentro y por fuera. UV del lateral: u alrededor del eje, v a lo largo de él;
+/// las tapas usan UV polares como `Disk`.
+pub struct Cylinder {
+    pub base: Vector3,
+    pub axis: Vector3,
+    pub radius: f32,
+    pub height: f32,
+    pub capped: bool,
+    pub material: Material,
+}
+
+impl Cylinder {
+    pub fn new(base: Vector3, axis: Vector3, radius: f32, height: f32, material: Material) -> Self {
+        Cylinder {
+            base,
+            axis: axis.normalized(),
+            radius,
+            height,
+            capped: true,
+            material,
+        }
+    }
+
+    /// Tubo abierto (sin tapas), como una tubería.
+    pub fn open(mut self) -> Self {
+        self.capped = false;
+        self
+    }
+
+    pub fn top(&self) -> Vector3 {
+        self.base + self.axis * self.height
+    }
+
+    /// Distancias (ordenadas, de cualquier signo) a las que el rayo cruza la superficie.
+    pub fn hits(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Vec<f32> {
+        let (axis_x, axis_z) = orthonormal_basis(self.axis);
+        let o = *ray_origin - self.base;
+        let (ox, oy, oz) = (o.dot(axis_x), o.dot(self.axis), o.dot(axis_z));
+        let (dx, dy, dz) = (ray_direction.dot(axis_x), ray_direction.dot(self.axis), ray_direction.dot(axis_z));
+        let mut hits = Vec::with_capacity(4);
+
+        // Lateral: x² + z² = r² con 0 ≤ y ≤ altura
+        let a = dx * dx + dz * dz;
+        let b = 2.0 * (ox * dx + oz * dz);
+        let c = ox * ox + oz * oz - self.radius * self.radius;
+        let discriminant = b * b - 4.0 * a * c;
+        if a > 1e-12 && discriminant >= 0.0 {
+            let root = discriminant.sqrt();
+            for t in [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)] {
+                let y = oy + dy * t;
+                if (0.0..=self.height).contains(&y) {
+                    hits.push(t);
+                }
+            }
+        }
+
+        // Tapas: planos y = 0 e y = altura dentro del radio
+        if self.capped && dy.abs() > 1e-12 {
+            for cap in [0.0, self.height] {
+                let t = (cap - oy) / dy;
+                let (x, z) = (ox + dx * t, oz + dz * t);
+                if x * x + z * z <= self.radius * self.radius {
+                    hits.push(t);
+                }
+            }
+        }
+        hits.sort_by(|a, b| a.total_cmp(b));
+        hits
+    }
+
+    /// Normal hacia fuera, UV y base tangente en un punto de la superficie.
+    fn surface(&self, point: Vector3) -> (Vector3, f32, f32, Vector3, Vector3) {
+        let local = point - self.base;
+        let y = local.dot(self.axis);
+        let radial_vector = local - self.axis * y;
+        let radial_distance = radial_vector.length();
+
+        // Si está más cerca de un plano de tapa que del lateral, es la tapa
+        let side_gap = (radial_distance - self.radius).abs();
+        if self.capped && y.abs().min((y - self.height).abs()) < side_gap {
+            let (center, normal) = if y < self.height * 0.5 { (self.base, -self.axis) } else { (self.top(), self.axis) };
+            let (u, v, tangent, bitangent) = disk_surface(center, normal, self.radius, point);
+            return (normal, u, v, tangent, bitangent);
+        }
+
+        let (axis_x, axis_z) = orthonormal_basis(self.axis);
+        let radial = if radial_distance > 1e-6 { radial_vector / radial_distance } else { axis_x };
+        let u = wrap_uv(radial.dot(axis_z).atan2(radial.dot(axis_x)) / (2.0 * PI));
+        let v = (y / self.height).clamp(0.0, 1.0);
+        let around = self.axis.cross(radial);
+        (radial, u, v, around * (2.0 * PI * self.radius), self.axis * self.height)
+    }
+
+    /// Intersección en el punto del rayo a `distance`, que está sobre la superficie.
+    fn hit_at(&self, ray_origin: &Vector3, ray_direction: &Vector3, distance: f32) -> Intersect {
+        let point = *ray_origin + *ray_direction * distance;
+        let (mut normal, u, v, tangent, bitangent) = self.surface(point);
+        // Un tubo abierto se ve también por dentro
+        if !self.capped && normal.dot(*ray_direction) > 0.0 {
+            normal = -normal;
+        }
+        Intersect::new(self.material.clone(), distance, normal, point, u, v).with_tangent_frame(tangent, bitangent)
+    }
+}
+
+impl RayIntersect for Cylinder {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Intersect {
+        match self.hits(ray_origin, ray_direction).into_iter().find(|t| *t > 0.001) {
+            Some(distance) => self.hit_at(ray_origin, ray_direction, distance),
+            None => Intersect::empty(),
+        }
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Vec<Span> {
+        // Sin tapas no encierra ningún volumen
+        if !self.capped {
+            return Vec::new();
+        }
+        spans_from_hits(&self.hits(ray_origin, ray_direction), |t| self.hit_at(ray_origin, ray_direction, t))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_disk(self.base, self.axis, self.radius).union(&Aabb::from_disk(self.top(), self.axis, self.radius)))
+    }
+}
@@ -0,0 +1,68 @@
+// disk.rs
+use raylib::prelude::Vector3;
+use std::f32::consts::PI;
+
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect, wrap_uv};
+use crate::sampling::orthonormal_basis;
+
+/// Disco plano de radio `radius`. Se ve por las dos caras.
+/// UV polares: u es el ángulo alrededor de la normal y v la distancia al centro (0 en el centro, 1 en el borde).
+pub struct Disk {
+    pub center: Vector3,
+    pub normal: Vector3,
+    pub radius: f32,
+    pub material: Material,
+}
+
+impl Disk {
+    pub fn new(center: Vector3, normal: Vector3, radius: f32, material: Material) -> Self {
+        Disk {
+            center,
+            normal: normal.normalized(),
+            radius,
+            material,
+        }
+    }
+}
+
+impl RayIntersect for Disk {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Intersect {
+        let denominator = self.normal.dot(*ray_direction);
+        if denominator.abs() < 1e-8 {
+            return Intersect::empty();
+        }
+        let distance = self.normal.dot(self.center - *ray_origin) / denominator;
+        if distance < 0.001 {
+            return Intersect::empty();
+        }
+        let point = *ray_origin + *ray_direction * distance;
+        if (point - self.center).length() > self.radius {
+            return Intersect::empty();
+        }
+
+        let (u, v, tangent, bitangent) = disk_surface(self.center, self.normal, self.radius, point);
+        let normal = if denominator > 0.0 { -self.normal } else { self.normal };
+        Intersect::new(self.material.clone(), distance, normal, point, u, v).with_tangent_frame(tangent, bitangent)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_disk(self.center, self.normal, self.radius))
+    }
+}
+
+/// UV polares de un punto sobre un disco (también las tapas de cilindros y conos) y su base
+/// tangente: v crece del centro al borde y u da la vuelta de forma que tangente × bitangente = normal.
+pub fn disk_surface(center: Vector3, normal: Vector3, radius: f32, point: Vector3) -> (f32, f32, Vector3, Vector3) {
+    let (axis_x, axis_z) = orthonormal_basis(normal);
+    let local = point - center;
+    let radial_distance = local.length();
+    let angle = (-local.dot(axis_z)).atan2(local.dot(axis_x));
+    let u = wrap_uv(angle / (2.0 * PI));
+    let v = (radial_distance / radius).min(1.0);
+
+    let radial = if radial_distance > 1e-6 { local / radial_distance } else { axis_x };
+    let around = radial.cross(normal);
+    (u, v, around * (2.0 * PI * radial_distance.max(1e-3)), radial * radius)
+}
@@ -0,0 +1,354 @@
+// environment.rs
+use raylib::prelude::*;
+use std::f32::consts::PI;
+use std::fs;
+
+use crate::light::Light;
+
+/// Distancia a la que se coloca la luz sintetizada a partir del sol del HDRI.
+/// Lo bastante lejos para que se comporte como una luz direccional.
+const SUN_DISTANCE: f32 = 1.0e4;
+
+/// Mapa de entorno equirectangular (lat-long) con valores HDR en coma flotante.
+pub struct EnvironmentMap {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Vector3>, // Radiancia lineal RGB, sin limitar a [0, 1]
+    pub rotation: f32,    // Rotación alrededor del eje Y en radianes
+    pub intensity: f32,   // Multiplicador de la radiancia
+    sun: Option<(Vector3, Vector3)>, // (dirección sin rotar, irradiancia RGB) de la zona más brillante
+    sun_threshold: f32,              // Luminancia a partir de la cual un texel forma parte del sol
+}
+
+impl EnvironmentMap {
+    /// Carga una imagen Radiance `.hdr` (RGBE, con o sin compresión RLE).
+    pub fn load_hdr(path: &str, rotation: f32, intensity: f32) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("No se pudo leer {}: {}", path, e))?;
+        let (width, height, pixels) = decode_hdr(&bytes).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Self::from_pixels(width, height, pixels, rotation, intensity))
+    }
+
+    /// Crea un mapa de entorno a partir de píxeles lineales ya decodificados (fila 0 = arriba).
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Vector3>, rotation: f32, intensity: f32) -> Self {
+        let mut environment = EnvironmentMap {
+            width,
+            height,
+            pixels,
+            rotation,
+            intensity,
+            sun: None,
+            sun_threshold: f32::INFINITY,
+        };
+        if let Some((sun, threshold)) = environment.find_sun() {
+            environment.sun = Some(sun);
+            environment.sun_threshold = threshold;
+        }
+        environment
+    }
+
+    /// Mapa lat-long auxiliar (sin rotación ni detección de sol), usado por la iluminación IBL.
+    pub fn latlong(width: usize, height: usize, pixels: Vec<Vector3>) -> Self {
+        EnvironmentMap {
+            width,
+            height,
+            pixels,
+            rotation: 0.0,
+            intensity: 1.0,
+            sun: None,
+            sun_threshold: f32::INFINITY,
+        }
+    }
+
+    /// Convierte una dirección del mundo a coordenadas (u, v) del mapa, aplicando la rotación.
+    pub fn direction_to_uv(&self, direction: Vector3) -> (f32, f32) {
+        let (u, v) = equirect_uv(direction);
+        ((u - self.rotation / (2.0 * PI)).rem_euclid(1.0), v)
+    }
+
+    /// Inversa de `direction_to_uv`: dirección del mundo para unas coordenadas del mapa.
+    pub fn uv_to_direction(&self, u: f32, v: f32) -> Vector3 {
+        equirect_direction(u + self.rotation / (2.0 * PI), v)
+    }
+
+    /// Devuelve la radiancia en la dirección dada usando filtrado bilineal.
+    pub fn sample(&self, direction: Vector3) -> Vector3 {
+        let (u, v) = self.direction_to_uv(direction);
+        self.sample_uv(u, v, |x, y| self.texel(x, y)) * self.intensity
+    }
+
+    /// Radiancia sin la zona del sol, que ya ilumina como luz directa con `sun_light`.
+    /// Es la que deben usar la iluminación del entorno y sus reflejos para no contarlo dos veces.
+    pub fn sample_without_sun(&self, direction: Vector3) -> Vector3 {
+        let (u, v) = self.direction_to_uv(direction);
+        self.sample_uv(u, v, |x, y| self.texel_without_sun(x, y)) * self.intensity
+    }
+
+    /// Como `sample_without_sun`, pero en el marco del propio mapa: sin rotación ni intensidad.
+    /// La IBL se precalcula con esta radiancia y aplica las dos al consultarse.
+    pub fn sample_unrotated_without_sun(&self, direction: Vector3) -> Vector3 {
+        let (u, v) = equirect_uv(direction);
+        self.sample_uv(u, v, |x, y| self.texel_without_sun(x, y))
+    }
+
+    /// Muestreo bilineal en coordenadas (u, v); u se repite horizontalmente y v se limita.
+    fn sample_uv(&self, u: f32, v: f32, texel: impl Fn(usize, usize) -> Vector3) -> Vector3 {
+        let x = u * self.width as f32 - 0.5;
+        let y = (v * self.height as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+
+        let wrap_x = |x: i64| x.rem_euclid(self.width as i64) as usize;
+        let xa = wrap_x(x0 as i64);
+        let xb = wrap_x(x0 as i64 + 1);
+        let ya = y0 as usize;
+        let yb = (ya + 1).min(self.height - 1);
+
+        let top = texel(xa, ya) * (1.0 - fx) + texel(xb, ya) * fx;
+        let bottom = texel(xa, yb) * (1.0 - fx) + texel(xb, yb) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    pub fn texel(&self, x: usize, y: usize) -> Vector3 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Texel con los del sol a cero: son exactamente los que integra `find_sun`.
+    fn texel_without_sun(&self, x: usize, y: usize) -> Vector3 {
+        let radiance = self.texel(x, y);
+        if luminance(radiance) >= self.sun_threshold { Vector3::zero() } else { radiance }
+    }
+
+    /// Ángulo sólido que cubre el texel de la fila `y`.
+    pub fn texel_solid_angle(&self, y: usize) -> f32 {
+        let theta = (y as f32 + 0.5) / self.height as f32 * PI;
+        (2.0 * PI / self.width as f32) * (PI / self.height as f32) * theta.sin()
+    }
+
+    /// Localiza la zona más brillante del mapa (normalmente el sol) e integra su irradiancia.
+    /// Devuelve también la luminancia mínima de los texels que forman parte de ella.
+    fn find_sun(&self) -> Option<((Vector3, Vector3), f32)> {
+        let max_luminance = self.pixels.iter().map(|c| luminance(*c)).fold(0.0, f32::max);
+        // Un cielo sin un punto claramente dominante no aporta una luz direccional útil.
+        if max_luminance <= 1.0 {
+            return None;
+        }
+
+        let threshold = max_luminance * 0.5;
+        let mut irradiance = Vector3::zero();
+        let mut weighted_direction = Vector3::zero();
+        for y in 0..self.height {
+            let solid_angle = self.texel_solid_angle(y);
+            for x in 0..self.width {
+                let radiance = self.texel(x, y);
+                let lum = luminance(radiance);
+                if lum < threshold {
+                    continue;
+                }
+                // Dirección sin rotar: se rota al consultarla para que la rotación sea barata.
+                let direction = equirect_direction(
+                    (x as f32 + 0.5) / self.width as f32,
+                    (y as f32 + 0.5) / self.height as f32,
+                );
+                irradiance += radiance * solid_angle;
+                weighted_direction += direction * (lum * solid_angle);
+            }
+        }
+
+        if weighted_direction.length() == 0.0 {
+            return None;
+        }
+        Some(((weighted_direction.normalized(), irradiance), threshold))
+    }
+
+    /// Luz direccional equivalente a la zona más brillante del entorno, vista desde `point`.
+    pub fn sun_light(&self, point: Vector3) -> Option<Light> {
+        let (direction, irradiance) = self.sun?;
+        let rotated = rotate_y(direction, self.rotation);
+        let energy = irradiance * self.intensity;
+        let strength = energy.x.max(energy.y).max(energy.z);
+        if strength <= 0.0 {
+            return None;
+        }
+        Some(Light::new(point + rotated * SUN_DISTANCE, energy / strength, strength))
+    }
+}
+
+/// Luminancia relativa (Rec. 709) de un color RGB lineal.
+pub fn luminance(color: Vector3) -> f32 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+/// Gira una dirección del marco del mapa al del mundo, consistente con `uv_to_direction`.
+/// Con `-angle` hace el giro inverso.
+pub fn rotate_y(direction: Vector3, angle: f32) -> Vector3 {
+    let (sin, cos) = angle.sin_cos();
+    Vector3::new(
+        direction.x * cos - direction.z * sin,
+        direction.y,
+        direction.x * sin + direction.z * cos,
+    )
+}
+
+/// Coordenadas (u, v) en proyección equirectangular sin rotación: u recorre el
+/// horizonte empezando en -Z y v va de +Y (0) a -Y (1).
+pub fn equirect_uv(direction: Vector3) -> (f32, f32) {
+    let d = direction.normalized();
+    let u = (0.5 + d.x.atan2(-d.z) / (2.0 * PI)).rem_euclid(1.0);
+    let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+    (u, v)
+}
+
+/// Inversa de `equirect_uv`.
+pub fn equirect_direction(u: f32, v: f32) -> Vector3 {
+    let phi = (u - 0.5) * 2.0 * PI;
+    let theta = v * PI;
+    Vector3::new(theta.sin() * phi.sin(), theta.cos(), -theta.sin() * phi.cos())
+}
+
+/// Decodifica un archivo Radiance HDR. Devuelve (ancho, alto, píxeles con la fila 0 arriba).
+fn decode_hdr(bytes: &[u8]) -> Result<(usize, usize, Vec<Vector3>), String> {
+    let mut pos = 0;
+    let read_line = |pos: &mut usize| -> Option<String> {
+        if *pos >= bytes.len() {
+            return None;
+        }
+        let start = *pos;
+        while *pos < bytes.len() && bytes[*pos] != b'\n' {
+            *pos += 1;
+        }
+        let line = String::from_utf8_lossy(&bytes[start..*pos]).trim_end().to_string();
+        *pos += 1;
+        Some(line)
+    };
+
+    let magic = read_line(&mut pos).ok_or("archivo vacío")?;
+    if !magic.starts_with("#?") {
+        return Err("no es un archivo Radiance HDR".to_string());
+    }
+
+    // Cabecera: termina con una línea vacía.
+    loop {
+        let line = read_line(&mut pos).ok_or("cabecera incompleta")?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=")
+            && format != "32-bit_rle_rgbe"
+        {
+            return Err(format!("formato no soportado: {}", format));
+        }
+    }
+
+    let resolution = read_line(&mut pos).ok_or("falta la resolución")?;
+    let parts: Vec<&str> = resolution.split_whitespace().collect();
+    if parts.len() != 4 || parts[2] != "+X" {
+        return Err(format!("orientación no soportada: {}", resolution));
+    }
+    let flip_y = match parts[0] {
+        "-Y" => false,
+        "+Y" => true,
+        _ => return Err(format!("orientación no soportada: {}", resolution)),
+    };
+    let height: usize = parts[1].parse().map_err(|_| "alto inválido")?;
+    let width: usize = parts[3].parse().map_err(|_| "ancho inválido")?;
+    if width == 0 || height == 0 {
+        return Err("imagen vacía".to_string());
+    }
+
+    let mut pixels = vec![Vector3::zero(); width * height];
+    let mut scanline = vec![[0u8; 4]; width];
+    for row in 0..height {
+        read_scanline(bytes, &mut pos, &mut scanline)?;
+        let y = if flip_y { height - 1 - row } else { row };
+        for (x, rgbe) in scanline.iter().enumerate() {
+            pixels[y * width + x] = rgbe_to_vector3(*rgbe);
+        }
+    }
+    Ok((width, height, pixels))
+}
+
+fn read_scanline(bytes: &[u8], pos: &mut usize, scanline: &mut [[u8; 4]]) -> Result<(), String> {
+    let width = scanline.len();
+    let next = |pos: &mut usize| -> Result<u8, String> {
+        let byte = *bytes.get(*pos).ok_or("datos de imagen truncados")?;
+        *pos += 1;
+        Ok(byte)
+    };
+
+    let is_new_rle = (8..0x8000).contains(&width)
+        && bytes.len() >= *pos + 4
+        && bytes[*pos] == 2
+        && bytes[*pos + 1] == 2
+        && bytes[*pos + 2] & 0x80 == 0;
+
+    if !is_new_rle {
+        // Formato plano (o RLE antiguo con píxeles 1,1,1,n que repiten el anterior).
+        let mut x = 0;
+        let mut shift = 0;
+        while x < width {
+            let rgbe = [next(pos)?, next(pos)?, next(pos)?, next(pos)?];
+            if rgbe[0] == 1 && rgbe[1] == 1 && rgbe[2] == 1 && x > 0 {
+                let count = (rgbe[3] as usize) << shift;
+                let previous = scanline[x - 1];
+                for _ in 0..count.min(width - x) {
+                    scanline[x] = previous;
+                    x += 1;
+                }
+                shift += 8;
+            } else {
+                scanline[x] = rgbe;
+                x += 1;
+                shift = 0;
+            }
+        }
+        return Ok(());
+    }
+
+    let encoded_width = ((bytes[*pos + 2] as usize) << 8) | bytes[*pos + 3] as usize;
+    if encoded_width != width {
+        return Err("ancho de línea inconsistente".to_string());
+    }
+    *pos += 4;
+
+    // RLE nuevo: cada canal se codifica por separado.
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = next(pos)? as usize;
+            if count > 128 {
+                let run = count - 128;
+                let value = next(pos)?;
+                if x + run > width {
+                    return Err("secuencia RLE fuera de la línea".to_string());
+                }
+                for pixel in &mut scanline[x..x + run] {
+                    pixel[channel] = value;
+                }
+                x += run;
+            } else {
+                if count == 0 || x + count > width {
+                    return Err("secuencia RLE inválida".to_string());
+                }
+                for pixel in &mut scanline[x..x + count] {
+                    pixel[channel] = next(pos)?;
+                }
+                x += count;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn rgbe_to_vector3(rgbe: [u8; 4]) -> Vector3 {
+    if rgbe[3] == 0 {
+        return Vector3::zero();
+    }
+    let f = 2f32.powi(rgbe[3] as i32 - (128 + 8));
+    Vector3::new(
+        (rgbe[0] as f32 + 0.5) * f,
+        (rgbe[1] as f32 + 0.5) * f,
+        (rgbe[2] as f32 + 0.5) * f,
+    )
+}
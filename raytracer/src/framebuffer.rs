@@ -40,5 +40,15 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: Color) {
         self.current_color = color;
     }
+    /// Copia una imagen completa (fila a fila, como la devuelve `render`).
+    pub fn set_pixels(&mut self, pixels: &[Color]) {
+        for (i, color) in pixels.iter().enumerate().take((self.width * self.height) as usize) {
+            self.color_buffer.draw_pixel(i as i32 % self.width, i as i32 / self.width, *color);
+        }
+    }
+    /// Guarda el contenido en un archivo de imagen (el formato lo indica la extensión).
+    pub fn export(&self, path: &str) {
+        self.color_buffer.export_image(path);
+    }
     // La función `swap_buffers` se ha eliminado. La lógica de dibujado ahora está en el bucle principal de `main.rs` para permitir dibujar el texto de los FPS encima de la imagen renderizada.
 }
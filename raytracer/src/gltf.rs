@@ -0,0 +1,828 @@
+// gltf.rs
+use raylib::prelude::*;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::aabb::Aabb;
+use crate::camera::{Camera, Projection};
+use crate::light::{Falloff, Light};
+use crate::material::Material;
+use crate::mesh::{Mesh, MeshFace};
+use crate::pbr::PbrMaterial;
+use crate::ray_intersect::RayIntersect;
+use crate::transform::transform_direction;
+
+/// Las luces direccionales se colocan así de lejos en sentido contrario a su dirección,
+/// para que los rayos de sombra lleguen prácticamente paralelos.
+const DIRECTIONAL_LIGHT_DISTANCE: f32 = 1.0e4;
+
+const GLB_MAGIC: u32 = 0x4654_6C67; // "glTF"
+const GLB_CHUNK_JSON: u32 = 0x4E4F_534A; // "JSON"
+const GLB_CHUNK_BIN: u32 = 0x004E_4942; // "BIN\0"
+
+/// Proyección de una cámara de glTF.
+#[derive(Debug, Clone, Copy)]
+pub enum GltfProjection {
+    Perspective { yfov: f32 },
+    Orthographic { xmag: f32, ymag: f32 },
+}
+
+/// Cámara de glTF ya colocada en el mundo (mira hacia su -Z local).
+#[derive(Debug, Clone)]
+pub struct GltfCamera {
+    pub name: String,
+    pub eye: Vector3,
+    pub forward: Vector3,
+    pub up: Vector3,
+    pub projection: GltfProjection,
+}
+
+impl GltfCamera {
+    /// Cámara del raytracer que orbita alrededor del punto a `focus_distance` delante del ojo.
+    pub fn to_camera(&self, focus_distance: f32) -> Camera {
+        let mut camera = Camera::new(self.eye, self.eye + self.forward * focus_distance.max(1e-3), self.up);
+        match self.projection {
+            GltfProjection::Perspective { yfov } => camera.fov = yfov,
+            // `ymag` es la mitad del alto visible
+            GltfProjection::Orthographic { ymag, .. } => camera.projection = Projection::Orthographic { height: 2.0 * ymag },
+        }
+        camera
+    }
+}
+
+/// Imagen incrustada en el archivo (GLB o URI `data:`); se carga desde memoria con `key` como ruta.
+#[derive(Debug, Clone)]
+pub struct EmbeddedImage {
+    pub key: String,
+    pub file_type: String, // Extensión con punto, como la espera raylib: ".png"
+    pub bytes: Vec<u8>,
+}
+
+/// Escena glTF 2.0 convertida a las estructuras del raytracer: una malla por nodo con malla
+/// (en coordenadas del mundo), luces KHR_lights_punctual, cámaras y las texturas que usan.
+#[derive(Default)]
+pub struct GltfScene {
+    pub meshes: Vec<Mesh>,
+    pub lights: Vec<Light>,
+    pub cameras: Vec<GltfCamera>,
+    pub texture_paths: Vec<String>,
+    pub embedded_images: Vec<EmbeddedImage>,
+}
+
+impl GltfScene {
+    /// Caja que envuelve todas las mallas.
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.meshes
+            .iter()
+            .filter_map(|mesh| mesh.bounding_box())
+            .reduce(|a, b| a.union(&b))
+    }
+
+    /// EV100 para las luces del archivo, que vienen en unidades físicas (candelas y lux): el que
+    /// deja en blanco una superficie blanca mate en el centro de las mallas con todas las luces.
+    /// `None` si no hay luces o no llegan al centro.
+    pub fn suggested_ev100(&self) -> Option<f32> {
+        let center = self.bounds().map_or(Vector3::zero(), |bounds| bounds.centroid());
+        let luminance: f32 = self.lights.iter().map(|light| light.intensity_at(center)).sum();
+        (luminance > 0.0).then(|| (luminance / 1.2).log2())
+    }
+
+    /// Cámara a partir de la primera cámara del archivo, orbitando sobre el centro del modelo.
+    pub fn default_camera(&self) -> Option<Camera> {
+        let camera = self.cameras.first()?;
+        let focus_distance = self
+            .bounds()
+            .map(|bounds| (bounds.centroid() - camera.eye).dot(camera.forward))
+            .filter(|distance| *distance > 0.0)
+            .unwrap_or(5.0);
+        Some(camera.to_camera(focus_distance))
+    }
+}
+
+/// Documento cargado: el JSON y el contenido de sus buffers.
+struct Document {
+    json: Value,
+    buffers: Vec<Vec<u8>>,
+    path: String,
+    directory: PathBuf,
+}
+
+/// Carga un `.gltf` (JSON con buffers externos o `data:`) o un `.glb` (binario) desde disco,
+/// sin acceder a la red. Las mallas compartidas por varios nodos se duplican con la
+/// transformación de cada uno.
+pub fn load_gltf(path: &str) -> Result<GltfScene, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("No se pudo leer {}: {}", path, e))?;
+    let error = |message: String| format!("{}: {}", path, message);
+    let (json, binary_chunk) = parse_container(&bytes).map_err(error)?;
+    let directory = Path::new(path).parent().unwrap_or(Path::new("")).to_path_buf();
+
+    let mut document = Document { json, buffers: Vec::new(), path: path.to_string(), directory };
+    document.buffers = load_buffers(&document, binary_chunk).map_err(error)?;
+
+    let mut scene = GltfScene::default();
+    let image_keys = load_images(&document, &mut scene).map_err(error)?;
+    let materials = load_materials(&document, &image_keys);
+
+    // Nodos raíz de la escena por defecto; sin escenas, todos los que no son hijos de otro
+    let nodes = array(&document.json, "nodes");
+    let roots: Vec<usize> = match document.json.get("scenes").and_then(Value::as_array) {
+        Some(scenes) if !scenes.is_empty() => {
+            let index = document.json.get("scene").and_then(Value::as_u64).unwrap_or(0) as usize;
+            let scene_json = scenes.get(index).ok_or_else(|| error(format!("la escena {} no existe", index)))?;
+            array(scene_json, "nodes").iter().filter_map(Value::as_u64).map(|i| i as usize).collect()
+        }
+        _ => {
+            let children: Vec<u64> = nodes.iter().flat_map(|node| array(node, "children")).filter_map(Value::as_u64).collect();
+            (0..nodes.len()).filter(|i| !children.contains(&(*i as u64))).collect()
+        }
+    };
+    for root in roots {
+        visit_node(&document, root, Matrix::identity(), &materials, &mut scene, 0).map_err(error)?;
+    }
+    Ok(scene)
+}
+
+/// Separa el JSON y el bloque binario de un GLB; un `.gltf` es solo JSON.
+fn parse_container(bytes: &[u8]) -> Result<(Value, Option<Vec<u8>>), String> {
+    let read_u32 = |offset: usize| -> Result<u32, String> {
+        bytes
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| "GLB truncado".to_string())
+    };
+    if bytes.len() < 12 || read_u32(0)? != GLB_MAGIC {
+        let json = serde_json::from_slice(bytes).map_err(|e| format!("JSON no válido: {}", e))?;
+        return Ok((json, None));
+    }
+
+    let version = read_u32(4)?;
+    if version != 2 {
+        return Err(format!("versión de GLB {} no soportada", version));
+    }
+    let total_length = (read_u32(8)? as usize).min(bytes.len());
+    let mut offset = 12;
+    let mut json = None;
+    let mut binary = None;
+    while offset + 8 <= total_length {
+        let chunk_length = read_u32(offset)? as usize;
+        let chunk_type = read_u32(offset + 4)?;
+        let data = bytes.get(offset + 8..offset + 8 + chunk_length).ok_or("bloque de GLB truncado")?;
+        match chunk_type {
+            GLB_CHUNK_JSON => json = Some(serde_json::from_slice(data).map_err(|e| format!("JSON no válido: {}", e))?),
+            GLB_CHUNK_BIN if binary.is_none() => binary = Some(data.to_vec()),
+            _ => {} // Los bloques desconocidos se ignoran
+        }
+        offset += 8 + chunk_length;
+    }
+    Ok((json.ok_or("el GLB no tiene bloque JSON")?, binary))
+}
+
+fn load_buffers(document: &Document, mut binary_chunk: Option<Vec<u8>>) -> Result<Vec<Vec<u8>>, String> {
+    array(&document.json, "buffers")
+        .iter()
+        .enumerate()
+        .map(|(index, buffer)| match buffer.get("uri").and_then(Value::as_str) {
+            Some(uri) => load_uri(document, uri).map(|(bytes, _)| bytes),
+            None => binary_chunk.take().ok_or_else(|| format!("el buffer {} no tiene datos", index)),
+        })
+        .collect()
+}
+
+/// Contenido de una URI: `data:` en base64 o un archivo relativo al documento.
+/// Devuelve también el tipo MIME si la URI lo indica.
+fn load_uri(document: &Document, uri: &str) -> Result<(Vec<u8>, Option<String>), String> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (header, payload) = data.split_once(',').ok_or("URI data: sin datos")?;
+        if !header.ends_with(";base64") {
+            return Err("solo se admiten URI data: en base64".to_string());
+        }
+        let mime_type = header.trim_end_matches(";base64").to_string();
+        return Ok((decode_base64(payload)?, Some(mime_type)));
+    }
+    if uri.contains("://") {
+        return Err(format!("'{}' es remoto; solo se cargan archivos locales", uri));
+    }
+    let file = document.directory.join(percent_decode(uri));
+    std::fs::read(&file)
+        .map(|bytes| (bytes, None))
+        .map_err(|e| format!("No se pudo leer {}: {}", file.display(), e))
+}
+
+/// Ruta con la que se registra cada imagen en el `TextureManager` (`None` si no se pudo usar).
+/// Las externas se cargan por su ruta; las incrustadas se guardan para cargarlas desde memoria.
+fn load_images(document: &Document, scene: &mut GltfScene) -> Result<Vec<Option<String>>, String> {
+    let mut keys = Vec::new();
+    for (index, image) in array(&document.json, "images").iter().enumerate() {
+        let mime_type = image.get("mimeType").and_then(Value::as_str).map(str::to_string);
+        let uri = image.get("uri").and_then(Value::as_str);
+
+        // Imagen externa: basta con su ruta
+        if let Some(uri) = uri.filter(|uri| !uri.starts_with("data:")) {
+            let file = document.directory.join(percent_decode(uri)).to_string_lossy().into_owned();
+            if Path::new(&file).is_file() {
+                scene.texture_paths.push(file.clone());
+                keys.push(Some(file));
+            } else {
+                eprintln!("La textura '{}' no existe; se ignora", file);
+                keys.push(None);
+            }
+            continue;
+        }
+
+        let (bytes, uri_mime_type) = match (uri, image.get("bufferView").and_then(Value::as_u64)) {
+            (Some(uri), _) => load_uri(document, uri)?,
+            (None, Some(view)) => (buffer_view(document, view as usize)?.to_vec(), None),
+            (None, None) => return Err(format!("la imagen {} no tiene datos", index)),
+        };
+        let file_type = match mime_type.or(uri_mime_type).as_deref() {
+            Some("image/png") => ".png",
+            Some("image/jpeg") => ".jpg",
+            other => {
+                eprintln!("La imagen {} tiene un formato no soportado ({:?}); se ignora", index, other);
+                keys.push(None);
+                continue;
+            }
+        };
+        let key = format!("{}#image{}{}", document.path, index, file_type);
+        scene.embedded_images.push(EmbeddedImage { key: key.clone(), file_type: file_type.to_string(), bytes });
+        keys.push(Some(key));
+    }
+    Ok(keys)
+}
+
+/// Materiales metallic-roughness. Se usan el color base, metalicidad y rugosidad (factores y
+/// texturas), el normal map, la emisión (con KHR_materials_emissive_strength) y la transmisión
+/// (KHR_materials_transmission, KHR_materials_ior). El modo BLEND se aproxima como transmisión;
+/// la oclusión, las texturas de emisión y el recorte por alfa (MASK) no se usan.
+fn load_materials(document: &Document, image_keys: &[Option<String>]) -> Vec<Material> {
+    let textures = array(&document.json, "textures");
+    let texture = |info: Option<&Value>| -> Option<String> {
+        let index = info?.get("index")?.as_u64()? as usize;
+        let source = textures.get(index)?.get("source")?.as_u64()? as usize;
+        image_keys.get(source)?.clone()
+    };
+
+    array(&document.json, "materials")
+        .iter()
+        .map(|material_json| {
+            let pbr_json = material_json.get("pbrMetallicRoughness");
+            let base_color = floats(pbr_json.and_then(|p| p.get("baseColorFactor")), &[1.0, 1.0, 1.0, 1.0]);
+            let metallic = number(pbr_json.and_then(|p| p.get("metallicFactor")), 1.0);
+            let roughness = number(pbr_json.and_then(|p| p.get("roughnessFactor")), 1.0);
+            let extensions = material_json.get("extensions");
+            let extension = |name: &str, key: &str| extensions.and_then(|e| e.get(name)).and_then(|e| e.get(key));
+
+            let mut pbr = PbrMaterial::new(Vector3::new(base_color[0], base_color[1], base_color[2]), metallic, roughness);
+            pbr.metallic_roughness_texture = texture(pbr_json.and_then(|p| p.get("metallicRoughnessTexture")));
+            pbr.transmission = number(extension("KHR_materials_transmission", "transmissionFactor"), 0.0);
+            pbr.ior = number(extension("KHR_materials_ior", "ior"), 1.5);
+            if material_json.get("alphaMode").and_then(Value::as_str) == Some("BLEND") {
+                pbr.transmission = pbr.transmission.max(1.0 - base_color[3]);
+            }
+
+            let mut material = Material::from_pbr(pbr);
+            material.texture = texture(pbr_json.and_then(|p| p.get("baseColorTexture")));
+            let normal_texture = material_json.get("normalTexture");
+            material.normal_map_id = texture(normal_texture);
+            material.normal_map_strength = number(normal_texture.and_then(|n| n.get("scale")), 1.0);
+            let emissive = floats(material_json.get("emissiveFactor"), &[0.0, 0.0, 0.0]);
+            let emissive_strength = number(extension("KHR_materials_emissive_strength", "emissiveStrength"), 1.0);
+            material.emission = Vector3::new(emissive[0], emissive[1], emissive[2]) * emissive_strength;
+            material
+        })
+        .collect()
+}
+
+/// Recorre un nodo y sus hijos acumulando las transformaciones.
+fn visit_node(
+    document: &Document,
+    index: usize,
+    parent: Matrix,
+    materials: &[Material],
+    scene: &mut GltfScene,
+    depth: usize,
+) -> Result<(), String> {
+    // Un grafo con ciclos no es un glTF válido; se corta en lugar de recursar sin fin
+    if depth > 256 {
+        return Err("jerarquía de nodos demasiado profunda (¿hay un ciclo?)".to_string());
+    }
+    let node = array(&document.json, "nodes").get(index).ok_or_else(|| format!("el nodo {} no existe", index))?;
+    // En raylib `a * b` aplica primero `a`: la transformación local va antes que la del padre
+    let world = local_matrix(node) * parent;
+    let name = node.get("name").and_then(Value::as_str).map(str::to_string).unwrap_or_else(|| format!("node{}", index));
+    let position = Vector3::new(world.m12, world.m13, world.m14);
+    let forward = transform_direction(&world, Vector3::new(0.0, 0.0, -1.0)).normalized();
+
+    if let Some(mesh) = node.get("mesh").and_then(Value::as_u64) {
+        scene.meshes.push(build_mesh(document, mesh as usize, &name, &world, materials)?);
+    }
+
+    if let Some(camera) = node.get("camera").and_then(Value::as_u64)
+        && let Some(camera_json) = array(&document.json, "cameras").get(camera as usize)
+    {
+        let projection = match camera_json.get("orthographic") {
+            Some(ortho) if camera_json.get("type").and_then(Value::as_str) == Some("orthographic") => GltfProjection::Orthographic {
+                xmag: number(ortho.get("xmag"), 1.0),
+                ymag: number(ortho.get("ymag"), 1.0),
+            },
+            _ => GltfProjection::Perspective {
+                yfov: number(camera_json.get("perspective").and_then(|p| p.get("yfov")), std::f32::consts::PI / 3.0),
+            },
+        };
+        let up = transform_direction(&world, Vector3::new(0.0, 1.0, 0.0)).normalized();
+        scene.cameras.push(GltfCamera { name: name.clone(), eye: position, forward, up, projection });
+    }
+
+    if let Some(light) = node
+        .get("extensions")
+        .and_then(|e| e.get("KHR_lights_punctual"))
+        .and_then(|e| e.get("light"))
+        .and_then(Value::as_u64)
+    {
+        let lights = document
+            .json
+            .get("extensions")
+            .and_then(|e| e.get("KHR_lights_punctual"))
+            .map(|e| array(e, "lights"))
+            .unwrap_or_default();
+        if let Some(light_json) = lights.get(light as usize) {
+            scene.lights.push(punctual_light(light_json, position, forward));
+        }
+    }
+
+    for child in array(node, "children").iter().filter_map(Value::as_u64) {
+        visit_node(document, child as usize, world, materials, scene, depth + 1)?;
+    }
+    Ok(())
+}
+
+/// Luz de KHR_lights_punctual: las puntuales y focos vienen en candelas, las direccionales en lux.
+/// Los focos iluminan dentro de su cono (`innerConeAngle`, `outerConeAngle`) hacia `direction`.
+fn punctual_light(light_json: &Value, position: Vector3, direction: Vector3) -> Light {
+    let color = floats(light_json.get("color"), &[1.0, 1.0, 1.0]);
+    let color = Vector3::new(color[0], color[1], color[2]);
+    let intensity = number(light_json.get("intensity"), 1.0);
+    match light_json.get("type").and_then(Value::as_str) {
+        Some("directional") => Light::from_lux(position - direction * DIRECTIONAL_LIGHT_DISTANCE, color, intensity),
+        kind => {
+            let mut light = Light::from_candela(position, color, intensity);
+            if let Some(range) = light_json.get("range").and_then(Value::as_f64) {
+                light = light.with_falloff(Falloff::Range { range: range as f32 });
+            }
+            if kind == Some("spot") {
+                let spot = light_json.get("spot");
+                let inner = number(spot.and_then(|spot| spot.get("innerConeAngle")), 0.0);
+                let outer = number(spot.and_then(|spot| spot.get("outerConeAngle")), std::f32::consts::FRAC_PI_4);
+                light = light.with_spot(direction, inner, outer);
+            }
+            light
+        }
+    }
+}
+
+/// Matriz local de un nodo: `matrix` (por columnas) o traslación · rotación · escala.
+fn local_matrix(node: &Value) -> Matrix {
+    if let Some(m) = node.get("matrix").and_then(Value::as_array).filter(|m| m.len() == 16) {
+        let m: Vec<f32> = m.iter().map(|v| v.as_f64().unwrap_or(0.0) as f32).collect();
+        // glTF y raylib guardan las matrices por columnas: m12, m13, m14 es la traslación
+        return Matrix {
+            m0: m[0], m1: m[1], m2: m[2], m3: m[3],
+            m4: m[4], m5: m[5], m6: m[6], m7: m[7],
+            m8: m[8], m9: m[9], m10: m[10], m11: m[11],
+            m12: m[12], m13: m[13], m14: m[14], m15: m[15],
+        };
+    }
+    let t = floats(node.get("translation"), &[0.0, 0.0, 0.0]);
+    let r = floats(node.get("rotation"), &[0.0, 0.0, 0.0, 1.0]);
+    let s = floats(node.get("scale"), &[1.0, 1.0, 1.0]);
+    // `Quaternion::to_matrix` de raylib-rs sale traspuesta respecto a `transform_with`
+    let rotation = Quaternion::new(r[0], r[1], r[2], r[3]).to_matrix().transposed();
+    Matrix::scale(s[0], s[1], s[2]) * rotation * Matrix::translate(t[0], t[1], t[2])
+}
+
+/// Une todas las primitivas triangulares de una malla glTF en una `Mesh` en coordenadas del mundo.
+fn build_mesh(document: &Document, index: usize, name: &str, world: &Matrix, materials: &[Material]) -> Result<Mesh, String> {
+    let mesh_json = array(&document.json, "meshes").get(index).ok_or_else(|| format!("la malla {} no existe", index))?;
+    let normal_matrix = world.inverted().transposed();
+    // Una escala negativa invierte el sentido de giro de los triángulos
+    let axis = |v: Vector3| transform_direction(world, v);
+    let mirrored = axis(Vector3::new(1.0, 0.0, 0.0)).cross(axis(Vector3::new(0.0, 1.0, 0.0))).dot(axis(Vector3::new(0.0, 0.0, 1.0))) < 0.0;
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut tangents: Vec<[f32; 4]> = Vec::new();
+    let mut faces = Vec::new();
+    let mut mesh_materials: Vec<Material> = Vec::new();
+    let mut material_indices: Vec<Option<u64>> = Vec::new();
+
+    for primitive in array(mesh_json, "primitives") {
+        let mode = primitive.get("mode").and_then(Value::as_u64).unwrap_or(4);
+        if !matches!(mode, 4..=6) {
+            continue; // Puntos y líneas no tienen superficie
+        }
+        let attributes = primitive.get("attributes").ok_or("primitiva sin atributos")?;
+        let attribute = |name: &str| attributes.get(name).and_then(Value::as_u64).map(|a| accessor(document, a as usize)).transpose();
+        let (position_data, _) = attribute("POSITION")?.ok_or("primitiva sin POSITION")?;
+        let normal_data = attribute("NORMAL")?;
+        let uv_data = attribute("TEXCOORD_0")?;
+        let tangent_data = attribute("TANGENT")?;
+
+        let base = positions.len();
+        let vertex_count = position_data.len() / 3;
+        for vertex in position_data.chunks_exact(3) {
+            positions.push(Vector3::new(vertex[0] as f32, vertex[1] as f32, vertex[2] as f32).transform_with(*world));
+        }
+        let has_normals = normal_data.as_ref().is_some_and(|(data, _)| data.len() / 3 == vertex_count);
+        if let Some((data, _)) = normal_data.filter(|_| has_normals) {
+            normals.resize(base, Vector3::zero());
+            for normal in data.chunks_exact(3) {
+                normals.push(transform_direction(&normal_matrix, Vector3::new(normal[0] as f32, normal[1] as f32, normal[2] as f32)).normalized());
+            }
+        }
+        let has_uvs = uv_data.as_ref().is_some_and(|(data, _)| data.len() / 2 == vertex_count);
+        if let Some((data, _)) = uv_data.filter(|_| has_uvs) {
+            uvs.resize(base, (0.0, 0.0));
+            // glTF ya tiene el origen de las UVs arriba a la izquierda, como las imágenes
+            uvs.extend(data.chunks_exact(2).map(|uv| (uv[0] as f32, uv[1] as f32)));
+        }
+        // Las tangentes del archivo (xyz y el signo de la bitangente en w) se usan tal cual; si
+        // faltan, `Mesh::new` las genera. Un espejo invierte el sentido de la bitangente
+        let has_tangents = has_normals && tangent_data.as_ref().is_some_and(|(data, components)| *components == 4 && data.len() / 4 == vertex_count);
+        if let Some((data, _)) = tangent_data.filter(|_| has_tangents) {
+            tangents.resize(base, [1.0, 0.0, 0.0, 1.0]);
+            for tangent in data.chunks_exact(4) {
+                let direction = transform_direction(world, Vector3::new(tangent[0] as f32, tangent[1] as f32, tangent[2] as f32)).normalized();
+                let w = if (tangent[3] < 0.0) != mirrored { -1.0 } else { 1.0 };
+                tangents.push([direction.x, direction.y, direction.z, w]);
+            }
+        }
+
+        let indices: Vec<usize> = match primitive.get("indices").and_then(Value::as_u64) {
+            Some(indices) => accessor(document, indices as usize)?.0.iter().map(|i| *i as usize).collect(),
+            None => (0..vertex_count).collect(),
+        };
+        if let Some(bad) = indices.iter().find(|i| **i >= vertex_count) {
+            return Err(format!("la malla {} usa el vértice {} que no existe", index, bad));
+        }
+        let triangles: Vec<[usize; 3]> = match mode {
+            5 => (2..indices.len())
+                .map(|i| if i % 2 == 0 { [indices[i - 2], indices[i - 1], indices[i]] } else { [indices[i - 1], indices[i - 2], indices[i]] })
+                .collect(),
+            6 => (2..indices.len()).map(|i| [indices[0], indices[i - 1], indices[i]]).collect(),
+            _ => indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect(),
+        };
+
+        let material_index = primitive.get("material").and_then(Value::as_u64);
+        let material = match material_indices.iter().position(|m| *m == material_index) {
+            Some(i) => i,
+            None => {
+                // Material por defecto de glTF: blanco, metálico y rugoso
+                let material = material_index
+                    .and_then(|m| materials.get(m as usize).cloned())
+                    .unwrap_or_else(|| Material::from_pbr(PbrMaterial::new(Vector3::one(), 1.0, 1.0)));
+                material_indices.push(material_index);
+                mesh_materials.push(material);
+                mesh_materials.len() - 1
+            }
+        };
+        for triangle in triangles {
+            let triangle = if mirrored { [triangle[0], triangle[2], triangle[1]] } else { triangle };
+            let vertices = triangle.map(|i| base + i);
+            faces.push(MeshFace {
+                positions: vertices,
+                normals: has_normals.then_some(vertices),
+                uvs: has_uvs.then_some(vertices),
+                tangents: has_tangents.then_some(vertices),
+                material,
+            });
+        }
+    }
+    Ok(Mesh::new(name, positions, normals, uvs, tangents, faces, mesh_materials))
+}
+
+/// Bytes de un bufferView.
+fn buffer_view(document: &Document, index: usize) -> Result<&[u8], String> {
+    let view = array(&document.json, "bufferViews").get(index).ok_or_else(|| format!("el bufferView {} no existe", index))?;
+    let buffer = view.get("buffer").and_then(Value::as_u64).unwrap_or(0) as usize;
+    let offset = view.get("byteOffset").and_then(Value::as_u64).unwrap_or(0) as usize;
+    let length = view.get("byteLength").and_then(Value::as_u64).unwrap_or(0) as usize;
+    document
+        .buffers
+        .get(buffer)
+        .and_then(|data| data.get(offset..offset + length))
+        .ok_or_else(|| format!("el bufferView {} se sale de su buffer", index))
+}
+
+/// Valores de un accessor como números (normalizados si el accessor lo pide) y su número de
+/// componentes por elemento. Se admiten accessors dispersos (`sparse`).
+fn accessor(document: &Document, index: usize) -> Result<(Vec<f64>, usize), String> {
+    let accessor = array(&document.json, "accessors").get(index).ok_or_else(|| format!("el accessor {} no existe", index))?;
+    let count = accessor.get("count").and_then(Value::as_u64).unwrap_or(0) as usize;
+    let component_type = accessor.get("componentType").and_then(Value::as_u64).unwrap_or(5126);
+    let normalized = accessor.get("normalized").and_then(Value::as_bool).unwrap_or(false);
+    let components = match accessor.get("type").and_then(Value::as_str).unwrap_or("SCALAR") {
+        "SCALAR" => 1,
+        "VEC2" => 2,
+        "VEC3" => 3,
+        "VEC4" | "MAT2" => 4,
+        "MAT3" => 9,
+        "MAT4" => 16,
+        other => return Err(format!("tipo de accessor '{}' desconocido", other)),
+    };
+
+    let mut values = match accessor.get("bufferView").and_then(Value::as_u64) {
+        Some(view) => {
+            let stride = array(&document.json, "bufferViews")
+                .get(view as usize)
+                .and_then(|v| v.get("byteStride"))
+                .and_then(Value::as_u64)
+                .map(|s| s as usize);
+            let offset = accessor.get("byteOffset").and_then(Value::as_u64).unwrap_or(0) as usize;
+            read_components(buffer_view(document, view as usize)?, offset, stride, count, components, component_type, normalized)?
+        }
+        None => vec![0.0; count * components], // Sin datos: todo ceros salvo lo que aporte `sparse`
+    };
+
+    if let Some(sparse) = accessor.get("sparse") {
+        let sparse_count = sparse.get("count").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let part = |key: &str| sparse.get(key).ok_or_else(|| format!("sparse sin '{}'", key));
+        let (index_json, value_json) = (part("indices")?, part("values")?);
+        let view_of = |json: &Value| json.get("bufferView").and_then(Value::as_u64).ok_or("sparse sin bufferView");
+        let offset_of = |json: &Value| json.get("byteOffset").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let index_type = index_json.get("componentType").and_then(Value::as_u64).unwrap_or(5125);
+        let targets = read_components(buffer_view(document, view_of(index_json)? as usize)?, offset_of(index_json), None, sparse_count, 1, index_type, false)?;
+        let replacements = read_components(buffer_view(document, view_of(value_json)? as usize)?, offset_of(value_json), None, sparse_count, components, component_type, normalized)?;
+        for (target, replacement) in targets.iter().zip(replacements.chunks_exact(components)) {
+            let start = *target as usize * components;
+            values.get_mut(start..start + components).ok_or("índice sparse fuera de rango")?.copy_from_slice(replacement);
+        }
+    }
+    Ok((values, components))
+}
+
+/// Lee `count` elementos de `components` componentes del tipo `component_type` de glTF.
+fn read_components(
+    data: &[u8],
+    offset: usize,
+    stride: Option<usize>,
+    count: usize,
+    components: usize,
+    component_type: u64,
+    normalized: bool,
+) -> Result<Vec<f64>, String> {
+    let size = match component_type {
+        5120 | 5121 => 1,
+        5122 | 5123 => 2,
+        5125 | 5126 => 4,
+        other => return Err(format!("tipo de componente {} desconocido", other)),
+    };
+    let stride = stride.unwrap_or(size * components);
+    let mut values = Vec::with_capacity(count * components);
+    for element in 0..count {
+        for component in 0..components {
+            let start = offset + element * stride + component * size;
+            let b = data.get(start..start + size).ok_or("accessor fuera de su bufferView")?;
+            let value = match component_type {
+                5120 => { let v = b[0] as i8 as f64; if normalized { (v / 127.0).max(-1.0) } else { v } }
+                5121 => { let v = b[0] as f64; if normalized { v / 255.0 } else { v } }
+                5122 => { let v = i16::from_le_bytes([b[0], b[1]]) as f64; if normalized { (v / 32767.0).max(-1.0) } else { v } }
+                5123 => { let v = u16::from_le_bytes([b[0], b[1]]) as f64; if normalized { v / 65535.0 } else { v } }
+                5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            };
+            values.push(value);
+        }
+    }
+    Ok(values)
+}
+
+fn array<'a>(json: &'a Value, key: &str) -> &'a [Value] {
+    json.get(key).and_then(Value::as_array).map(Vec::as_slice).unwrap_or(&[])
+}
+
+fn number(value: Option<&Value>, default: f32) -> f32 {
+    value.and_then(Value::as_f64).map(|v| v as f32).unwrap_or(default)
+}
+
+/// Lista de números con los valores por defecto de la especificación si falta o está incompleta.
+fn floats(value: Option<&Value>, default: &[f32]) -> Vec<f32> {
+    match value.and_then(Value::as_array) {
+        Some(values) if values.len() >= default.len() => values.iter().map(|v| v.as_f64().unwrap_or(0.0) as f32).collect(),
+        _ => default.to_vec(),
+    }
+}
+
+fn decode_base64(input: &str) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(input.len() * 3 / 4);
+    let mut accumulator = 0u32;
+    let mut bits = 0;
+    for c in input.bytes().filter(|c| !c.is_ascii_whitespace() && *c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return Err(format!("carácter '{}' no válido en base64", c as char)),
+        };
+        accumulator = (accumulator << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((accumulator >> bits) as u8);
+        }
+    }
+    Ok(output)
+}
+
+/// Las URI relativas pueden venir con caracteres escapados (`%20` para los espacios).
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(value) = uri.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            output.push(value);
+            i += 3;
+        } else {
+            output.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// GLB con un bloque JSON (rellenado con espacios hasta múltiplo de 4) y uno binario.
+    fn glb(json: &str, binary: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        json.resize(json.len().div_ceil(4) * 4, b' ');
+        let mut binary = binary.to_vec();
+        binary.resize(binary.len().div_ceil(4) * 4, 0);
+        let total = 12 + 8 + json.len() + 8 + binary.len();
+        let mut bytes = Vec::new();
+        for word in [GLB_MAGIC, 2, total as u32, json.len() as u32, GLB_CHUNK_JSON] {
+            bytes.extend(word.to_le_bytes());
+        }
+        bytes.extend(&json);
+        bytes.extend((binary.len() as u32).to_le_bytes());
+        bytes.extend(GLB_CHUNK_BIN.to_le_bytes());
+        bytes.extend(&binary);
+        bytes
+    }
+
+    fn document(json: Value, buffer: Vec<u8>) -> Document {
+        Document { json, buffers: vec![buffer], path: "test.gltf".to_string(), directory: PathBuf::new() }
+    }
+
+    #[test]
+    fn decodes_base64_with_and_without_padding() {
+        assert_eq!(decode_base64("TWFu").unwrap(), b"Man");
+        assert_eq!(decode_base64("TWE=").unwrap(), b"Ma");
+        assert_eq!(decode_base64("TQ==").unwrap(), b"M");
+        assert_eq!(decode_base64("TQ").unwrap(), b"M");
+        assert_eq!(decode_base64("-_8=").unwrap(), [0xfb, 0xff]);
+        assert!(decode_base64("TW*u").is_err());
+    }
+
+    #[test]
+    fn splits_glb_chunks() {
+        let (json, binary) = parse_container(&glb(r#"{"asset":{"version":"2.0"}}"#, &[1, 2, 3])).unwrap();
+        assert_eq!(json["asset"]["version"], "2.0");
+        assert_eq!(binary.unwrap(), [1, 2, 3, 0]);
+
+        let (json, binary) = parse_container(br#"{"asset":{}}"#).unwrap();
+        assert!(json.get("asset").is_some() && binary.is_none());
+
+        let mut truncated = glb(r#"{"asset":{}}"#, &[]);
+        truncated.truncate(24);
+        assert!(parse_container(&truncated).is_err());
+    }
+
+    #[test]
+    fn reads_strided_normalized_accessors() {
+        // Vértices intercalados de 8 bytes: dos u16 normalizados y 4 bytes de otro atributo
+        let mut buffer = Vec::new();
+        for (x, y) in [(0u16, 65535u16), (32768, 0)] {
+            buffer.extend(x.to_le_bytes());
+            buffer.extend(y.to_le_bytes());
+            buffer.extend([0xAA; 4]);
+        }
+        let json = serde_json::json!({
+            "bufferViews": [{ "buffer": 0, "byteLength": 16, "byteStride": 8 }],
+            "accessors": [{ "bufferView": 0, "componentType": 5123, "normalized": true, "count": 2, "type": "VEC2" }],
+        });
+        let (values, components) = accessor(&document(json, buffer), 0).unwrap();
+        assert_eq!(components, 2);
+        assert_eq!(values.len(), 4);
+        assert_eq!((values[0], values[1], values[3]), (0.0, 1.0, 0.0));
+        assert!((values[2] - 32768.0 / 65535.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn applies_sparse_accessors() {
+        // Sin bufferView base (todo ceros); el elemento 2 se sustituye por 7.5
+        let mut buffer = Vec::new();
+        buffer.extend(2u16.to_le_bytes());
+        buffer.extend([0, 0]);
+        buffer.extend(7.5f32.to_le_bytes());
+        let json = serde_json::json!({
+            "bufferViews": [{ "buffer": 0, "byteLength": 2 }, { "buffer": 0, "byteOffset": 4, "byteLength": 4 }],
+            "accessors": [{
+                "componentType": 5126, "count": 3, "type": "SCALAR",
+                "sparse": { "count": 1, "indices": { "bufferView": 0, "componentType": 5123 }, "values": { "bufferView": 1 } },
+            }],
+        });
+        let (values, _) = accessor(&document(json, buffer), 0).unwrap();
+        assert_eq!(values, vec![0.0, 0.0, 7.5]);
+    }
+
+    #[test]
+    fn loads_a_glb_with_a_triangle_and_a_spot_light() {
+        let positions: Vec<u8> = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "byteLength": 36 }],
+            "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+            "accessors": [{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }],
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+            "extensions": { "KHR_lights_punctual": { "lights": [
+                { "type": "spot", "intensity": 10, "spot": { "innerConeAngle": 0.2, "outerConeAngle": 0.4 } }
+            ] } },
+            "nodes": [
+                { "mesh": 0 },
+                { "translation": [0, 0, 5], "extensions": { "KHR_lights_punctual": { "light": 0 } } }
+            ]
+        }"#;
+        let scene = load_glb("spot", json, &positions).unwrap();
+
+        assert_eq!(scene.meshes.len(), 1);
+        assert_eq!(scene.meshes[0].triangle_count(), 1);
+        let light = scene.lights[0];
+        assert_eq!(light.position, Vector3::new(0.0, 0.0, 5.0));
+        let spot = light.spot.unwrap();
+        assert_eq!(spot.direction, Vector3::new(0.0, 0.0, -1.0));
+        assert_eq!((spot.inner_angle, spot.outer_angle), (0.2, 0.4));
+        // Un foco que mira a -Z ilumina el origen pero no un punto a 90° de su eje
+        assert!(light.intensity_at(Vector3::zero()) > 0.0);
+        assert_eq!(light.intensity_at(Vector3::new(5.0, 0.0, 5.0)), 0.0);
+    }
+
+    /// Escribe un GLB temporal, lo carga y lo borra.
+    fn load_glb(name: &str, json: &str, binary: &[u8]) -> Result<GltfScene, String> {
+        let path = std::env::temp_dir().join(format!("raytracer-gltf-{}-{}.glb", name, std::process::id()));
+        std::fs::write(&path, glb(json, binary)).unwrap();
+        let scene = load_gltf(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        scene
+    }
+
+    #[test]
+    fn uses_tangents_from_the_file_and_generates_missing_ones() {
+        // Un triángulo con normales +Z, UVs y tangentes -Y con la bitangente invertida (w = -1)
+        let floats = [
+            [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0].as_slice(),
+            &[0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            &[0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            &[0.0, -1.0, 0.0, -1.0, 0.0, -1.0, 0.0, -1.0, 0.0, -1.0, 0.0, -1.0],
+        ];
+        let binary: Vec<u8> = floats.concat().iter().flat_map(|v| v.to_le_bytes()).collect();
+        let json = |attributes: &str| {
+            format!(
+                r#"{{
+                "asset": {{ "version": "2.0" }},
+                "buffers": [{{ "byteLength": 144 }}],
+                "bufferViews": [
+                    {{ "buffer": 0, "byteLength": 36 }}, {{ "buffer": 0, "byteOffset": 36, "byteLength": 36 }},
+                    {{ "buffer": 0, "byteOffset": 72, "byteLength": 24 }}, {{ "buffer": 0, "byteOffset": 96, "byteLength": 48 }}
+                ],
+                "accessors": [
+                    {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }},
+                    {{ "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC3" }},
+                    {{ "bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC2" }},
+                    {{ "bufferView": 3, "componentType": 5126, "count": 3, "type": "VEC4" }}
+                ],
+                "meshes": [{{ "primitives": [{{ "attributes": {{ {} }} }}] }}],
+                "nodes": [{{ "mesh": 0 }}]
+            }}"#,
+                attributes
+            )
+        };
+
+        let scene = load_glb("tangents", &json(r#""POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2, "TANGENT": 3"#), &binary).unwrap();
+        let mesh = &scene.meshes[0];
+        assert_eq!(mesh.tangents, vec![[0.0, -1.0, 0.0, -1.0]; 3]);
+        assert_eq!(mesh.faces[0].tangents, Some([0, 1, 2]));
+
+        // Sin TANGENT se generan a partir de las UVs: u crece hacia +X
+        let scene = load_glb("generated", &json(r#""POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2"#), &binary).unwrap();
+        let mesh = &scene.meshes[0];
+        assert_eq!(mesh.tangents, vec![[1.0, 0.0, 0.0, 1.0]; 3]);
+    }
+}
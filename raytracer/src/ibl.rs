@@ -0,0 +1,209 @@
+// ibl.rs
+use raylib::prelude::*;
+use std::f32::consts::PI;
+
+use crate::environment::{EnvironmentMap, equirect_direction, equirect_uv, luminance, rotate_y};
+use crate::sampling::{Distribution1D, random_f32};
+
+/// Resolución del mapa lat-long reducido a partir del cual se precalcula todo.
+const PROXY_WIDTH: usize = 64;
+const PROXY_HEIGHT: usize = 32;
+/// Resolución de cada nivel prefiltrado para reflejos difusos/brillantes.
+const LEVEL_WIDTH: usize = 32;
+const LEVEL_HEIGHT: usize = 16;
+/// Rugosidades de los niveles prefiltrados; la rugosidad 0 usa el entorno original.
+const LEVEL_ROUGHNESS: [f32; 4] = [0.25, 0.5, 0.75, 1.0];
+
+/// Cómo contribuye el entorno a la iluminación en `cast_ray`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IblMode {
+    Off,
+    /// Irradiancia por armónicos esféricos y reflejos prefiltrados (rápido, sin sombras).
+    Precomputed,
+    /// Muestreo por importancia del entorno con rayos de visibilidad (estocástico).
+    Sampled { samples: u32 },
+}
+
+/// Iluminación basada en imagen precalculada a partir del skybox o del HDRI.
+/// El giro y la intensidad del HDRI se aplican al consultarla, así que cambiarlos no obliga a
+/// precalcularla de nuevo.
+pub struct ImageBasedLighting {
+    pub rotation: f32,                // Giro alrededor de Y del entorno respecto al precalculado
+    pub intensity: f32,               // Multiplicador de la radiancia precalculada
+    sh: [Vector3; 9],                 // Coeficientes SH de orden 2 de la radiancia
+    proxy: EnvironmentMap,            // Entorno reducido, base del muestreo por importancia
+    levels: Vec<EnvironmentMap>,      // Entorno convolucionado para cada rugosidad de LEVEL_ROUGHNESS
+    marginal: Distribution1D,         // Distribución de filas del proxy
+    conditional: Vec<Distribution1D>, // Distribución de columnas dentro de cada fila
+}
+
+impl ImageBasedLighting {
+    /// Precalcula la iluminación a partir de una función dirección → radiancia.
+    pub fn build(radiance: impl Fn(Vector3) -> Vector3) -> Self {
+        // 1. Mapa lat-long reducido, con supermuestreo 2x2 por texel para evitar aliasing.
+        let mut pixels = Vec::with_capacity(PROXY_WIDTH * PROXY_HEIGHT);
+        for y in 0..PROXY_HEIGHT {
+            for x in 0..PROXY_WIDTH {
+                let mut sum = Vector3::zero();
+                for (sx, sy) in [(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)] {
+                    let u = (x as f32 + sx) / PROXY_WIDTH as f32;
+                    let v = (y as f32 + sy) / PROXY_HEIGHT as f32;
+                    sum += radiance(equirect_direction(u, v));
+                }
+                pixels.push(sum * 0.25);
+            }
+        }
+        let proxy = EnvironmentMap::latlong(PROXY_WIDTH, PROXY_HEIGHT, pixels);
+
+        // 2. Proyección en armónicos esféricos para la irradiancia difusa.
+        let mut sh = [Vector3::zero(); 9];
+        for y in 0..PROXY_HEIGHT {
+            let solid_angle = proxy.texel_solid_angle(y);
+            for x in 0..PROXY_WIDTH {
+                let direction = texel_direction(x, y, PROXY_WIDTH, PROXY_HEIGHT);
+                let basis = sh_basis(direction);
+                let radiance = proxy.texel(x, y) * solid_angle;
+                for (coefficient, b) in sh.iter_mut().zip(basis) {
+                    *coefficient += radiance * b;
+                }
+            }
+        }
+
+        // 3. Niveles prefiltrados con un lóbulo de Phong equivalente a cada rugosidad.
+        let levels = LEVEL_ROUGHNESS
+            .iter()
+            .map(|&roughness| prefilter(&proxy, roughness))
+            .collect();
+
+        // 4. Tablas para muestrear direcciones en proporción a su luminancia.
+        let mut row_weights = Vec::with_capacity(PROXY_HEIGHT);
+        let mut conditional = Vec::with_capacity(PROXY_HEIGHT);
+        for y in 0..PROXY_HEIGHT {
+            let sin_theta = ((y as f32 + 0.5) / PROXY_HEIGHT as f32 * PI).sin();
+            // Un pequeño mínimo garantiza probabilidad no nula en todo el cielo.
+            let weights: Vec<f32> = (0..PROXY_WIDTH)
+                .map(|x| (luminance(proxy.texel(x, y)) + 1e-3) * sin_theta)
+                .collect();
+            let row = Distribution1D::new(&weights);
+            row_weights.push(row.total);
+            conditional.push(row);
+        }
+        let marginal = Distribution1D::new(&row_weights);
+
+        ImageBasedLighting { rotation: 0.0, intensity: 1.0, sh, proxy, levels, marginal, conditional }
+    }
+
+    /// Dirección del mundo en el marco en que se precalculó.
+    fn to_local(&self, direction: Vector3) -> Vector3 {
+        rotate_y(direction, -self.rotation)
+    }
+
+    /// Radiancia difusa que sale de una superficie blanca lambertiana con esa normal (E / π).
+    pub fn irradiance(&self, normal: Vector3) -> Vector3 {
+        // Convolución con el coseno: Â0 = π, Â1 = 2π/3, Â2 = π/4 (Ramamoorthi & Hanrahan).
+        const BAND: [f32; 9] = [PI, 2.0 * PI / 3.0, 2.0 * PI / 3.0, 2.0 * PI / 3.0, PI / 4.0, PI / 4.0, PI / 4.0, PI / 4.0, PI / 4.0];
+        let basis = sh_basis(self.to_local(normal.normalized()));
+        let mut irradiance = Vector3::zero();
+        for i in 0..9 {
+            irradiance += self.sh[i] * (BAND[i] * basis[i]);
+        }
+        let radiance = irradiance * (self.intensity / PI);
+        Vector3::new(radiance.x.max(0.0), radiance.y.max(0.0), radiance.z.max(0.0))
+    }
+
+    /// Radiancia reflejada prefiltrada para una rugosidad en [0, 1].
+    /// `sharp` es la radiancia del entorno original en esa dirección (nivel de rugosidad 0).
+    pub fn specular(&self, direction: Vector3, roughness: f32, sharp: Vector3) -> Vector3 {
+        let roughness = roughness.clamp(0.0, 1.0);
+        let local = self.to_local(direction);
+        let mut previous_roughness = 0.0;
+        let mut previous = sharp;
+        for (level, &level_roughness) in self.levels.iter().zip(LEVEL_ROUGHNESS.iter()) {
+            let current = level.sample(local) * self.intensity;
+            if roughness <= level_roughness {
+                let t = (roughness - previous_roughness) / (level_roughness - previous_roughness);
+                return previous * (1.0 - t) + current * t;
+            }
+            previous_roughness = level_roughness;
+            previous = current;
+        }
+        previous
+    }
+
+    /// Elige una dirección con probabilidad proporcional a la luminancia del entorno.
+    /// Devuelve (dirección, densidad de probabilidad por ángulo sólido).
+    pub fn sample_direction(&self) -> (Vector3, f32) {
+        let (y, offset_y, row_probability) = self.marginal.sample(random_f32());
+        let (x, offset_x, column_probability) = self.conditional[y].sample(random_f32());
+        let u = (x as f32 + offset_x) / PROXY_WIDTH as f32;
+        let v = (y as f32 + offset_y) / PROXY_HEIGHT as f32;
+        let direction = rotate_y(equirect_direction(u, v), self.rotation);
+        let sin_theta = (v * PI).sin().max(1e-4);
+        let pdf = row_probability * column_probability * (PROXY_WIDTH * PROXY_HEIGHT) as f32 / (2.0 * PI * PI * sin_theta);
+        (direction, pdf)
+    }
+
+    /// Densidad de probabilidad con la que `sample_direction` elegiría `direction`.
+    pub fn pdf(&self, direction: Vector3) -> f32 {
+        let (u, v) = equirect_uv(self.to_local(direction));
+        let x = ((u * PROXY_WIDTH as f32) as usize).min(PROXY_WIDTH - 1);
+        let y = ((v * PROXY_HEIGHT as f32) as usize).min(PROXY_HEIGHT - 1);
+        let sin_theta = (v * PI).sin().max(1e-4);
+        self.marginal.probability(y) * self.conditional[y].probability(x) * (PROXY_WIDTH * PROXY_HEIGHT) as f32
+            / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+fn texel_direction(x: usize, y: usize, width: usize, height: usize) -> Vector3 {
+    equirect_direction((x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32)
+}
+
+/// Base de armónicos esféricos reales hasta la banda 2.
+fn sh_basis(d: Vector3) -> [f32; 9] {
+    [
+        0.282_095,
+        0.488_603 * d.y,
+        0.488_603 * d.z,
+        0.488_603 * d.x,
+        1.092_548 * d.x * d.y,
+        1.092_548 * d.y * d.z,
+        0.315_392 * (3.0 * d.z * d.z - 1.0),
+        1.092_548 * d.x * d.z,
+        0.546_274 * (d.x * d.x - d.y * d.y),
+    ]
+}
+
+/// Convoluciona el entorno con un lóbulo cos^n centrado en cada dirección de salida.
+fn prefilter(source: &EnvironmentMap, roughness: f32) -> EnvironmentMap {
+    let alpha = (roughness * roughness).max(1e-3);
+    let exponent = (2.0 / (alpha * alpha) - 2.0).max(0.0);
+
+    // (dirección, radiancia, ángulo sólido) de cada texel de origen.
+    let source_texels: Vec<(Vector3, Vector3, f32)> = (0..source.height)
+        .flat_map(|y| (0..source.width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let direction = texel_direction(x, y, source.width, source.height);
+            (direction, source.texel(x, y), source.texel_solid_angle(y))
+        })
+        .collect();
+
+    let mut pixels = Vec::with_capacity(LEVEL_WIDTH * LEVEL_HEIGHT);
+    for y in 0..LEVEL_HEIGHT {
+        for x in 0..LEVEL_WIDTH {
+            let center = texel_direction(x, y, LEVEL_WIDTH, LEVEL_HEIGHT);
+            let mut sum = Vector3::zero();
+            let mut weight_sum = 0.0;
+            for (direction, radiance, solid_angle) in &source_texels {
+                let cos = center.dot(*direction);
+                if cos <= 0.0 {
+                    continue;
+                }
+                let weight = cos.powf(exponent) * solid_angle;
+                sum += *radiance * weight;
+                weight_sum += weight;
+            }
+            pixels.push(if weight_sum > 0.0 { sum / weight_sum } else { Vector3::zero() });
+        }
+    }
+    EnvironmentMap::latlong(LEVEL_WIDTH, LEVEL_HEIGHT, pixels)
+}
@@ -0,0 +1,190 @@
+// instance.rs
+use raylib::prelude::Vector3;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::area_light::EmissivePatch;
+use crate::bvh::Bvh;
+use crate::material::Material;
+use crate::motion::MotionTransform;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::transform::Transform;
+
+/// Geometría que se comparte entre instancias (nivel inferior, BLAS): un conjunto de primitivas
+/// en su propio espacio con una BVH sobre ellas. Se construye una vez y se referencia con `Arc`.
+pub struct Blas {
+    pub name: String,
+    objects: Vec<Box<dyn RayIntersect>>,
+    bvh: Bvh,
+    bounded: Vec<usize>,   // Índices en `objects` de los elementos de la BVH
+    unbounded: Vec<usize>, // Objetos sin caja (planos infinitos): se prueban siempre
+}
+
+impl Blas {
+    pub fn new(name: &str, objects: Vec<Box<dyn RayIntersect>>) -> Self {
+        let (bounded, unbounded): (Vec<usize>, Vec<usize>) = (0..objects.len()).partition(|&i| objects[i].bounding_box().is_some());
+        let bounds: Vec<Aabb> = bounded.iter().filter_map(|&i| objects[i].bounding_box()).collect();
+        Blas {
+            name: name.to_string(),
+            bvh: Bvh::build(&bounds),
+            objects,
+            bounded,
+            unbounded,
+        }
+    }
+
+    pub fn object_count(&self) -> usize {
+        self.objects.len()
+    }
+}
+
+impl RayIntersect for Blas {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Intersect {
+        closest_hit(&self.bvh, &self.bounded, &self.unbounded, ray_origin, ray_direction, |i, origin, direction| {
+            self.objects[i].ray_intersect(origin, direction, time)
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.unbounded.is_empty().then(|| self.bvh.bounds())
+    }
+
+    fn emissive_patches(&self) -> Vec<EmissivePatch> {
+        self.objects.iter().flat_map(|object| object.emissive_patches()).collect()
+    }
+}
+
+/// Una colocación ligera de una geometría compartida: solo guarda la referencia, su
+/// transformación y, si se quiere, un material que sustituye a todos los de la geometría.
+#[derive(Clone)]
+pub struct Instance {
+    pub geometry: Arc<dyn RayIntersect>,
+    pub transform: Transform,
+    pub material: Option<Arc<Material>>,
+    /// Si la instancia se mueve durante el fotograma, sustituye a `transform` al trazar.
+    pub motion: Option<MotionTransform>,
+}
+
+impl Instance {
+    pub fn new(geometry: Arc<dyn RayIntersect>, transform: Transform) -> Self {
+        Instance { geometry, transform, material: None, motion: None }
+    }
+
+    /// Instancia que se mueve durante el fotograma (para el desenfoque de movimiento).
+    pub fn with_motion(mut self, motion: MotionTransform) -> Self {
+        self.transform = motion.start();
+        self.motion = (!motion.is_static()).then_some(motion);
+        self
+    }
+
+    pub fn with_material(mut self, material: Arc<Material>) -> Self {
+        self.material = Some(material);
+        self
+    }
+}
+
+impl RayIntersect for Instance {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Intersect {
+        let transform = match &self.motion {
+            Some(motion) => motion.at(time),
+            None => self.transform,
+        };
+        let (origin, direction) = transform.local_ray(ray_origin, ray_direction);
+        let mut hit = self.geometry.ray_intersect(&origin, &direction, time);
+        if hit.is_intersecting && let Some(material) = &self.material {
+            hit.material = Material::clone(material);
+        }
+        transform.intersect_to_world(hit, ray_origin, ray_direction)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let bounds = self.geometry.bounding_box()?;
+        Some(match &self.motion {
+            Some(motion) => motion.bounds(&bounds),
+            None => self.transform.bounds(&bounds),
+        })
+    }
+
+    /// Los emisores de la geometría colocados con la instancia (al principio del fotograma si
+    /// se mueve). Con un material que sustituye a los de la geometría, emite según ese material.
+    fn emissive_patches(&self) -> Vec<EmissivePatch> {
+        let patches = self.geometry.emissive_patches();
+        let patches = match &self.material {
+            None => patches,
+            Some(material) if material.emission.dot(material.emission) > 0.0 => {
+                patches.into_iter().map(|patch| EmissivePatch { emission: material.emission, ..patch }).collect()
+            }
+            Some(_) => Vec::new(),
+        };
+        patches.iter().map(|patch| patch.transformed(&self.transform)).collect()
+    }
+}
+
+/// Nivel superior (TLAS): una BVH sobre las cajas de las instancias en el mundo. Cada rayo
+/// baja por ella hasta las instancias candidatas y, ya en su espacio, por la BVH de la geometría.
+pub struct Tlas {
+    pub instances: Vec<Instance>,
+    bvh: Bvh,
+    bounded: Vec<usize>,
+    unbounded: Vec<usize>,
+}
+
+impl Tlas {
+    pub fn new(instances: Vec<Instance>) -> Self {
+        let (bounded, unbounded): (Vec<usize>, Vec<usize>) = (0..instances.len()).partition(|&i| instances[i].bounding_box().is_some());
+        let bounds: Vec<Aabb> = bounded.iter().filter_map(|&i| instances[i].bounding_box()).collect();
+        Tlas {
+            bvh: Bvh::build(&bounds),
+            instances,
+            bounded,
+            unbounded,
+        }
+    }
+}
+
+impl RayIntersect for Tlas {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Intersect {
+        closest_hit(&self.bvh, &self.bounded, &self.unbounded, ray_origin, ray_direction, |i, origin, direction| {
+            self.instances[i].ray_intersect(origin, direction, time)
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.unbounded.is_empty().then(|| self.bvh.bounds())
+    }
+
+    fn emissive_patches(&self) -> Vec<EmissivePatch> {
+        self.instances.iter().flat_map(|instance| instance.emissive_patches()).collect()
+    }
+}
+
+/// Impacto más cercano entre los elementos de una BVH (`bounded[i]` es el elemento de su hoja `i`)
+/// y los que no tienen caja.
+fn closest_hit(
+    bvh: &Bvh,
+    bounded: &[usize],
+    unbounded: &[usize],
+    ray_origin: &Vector3,
+    ray_direction: &Vector3,
+    intersect: impl Fn(usize, &Vector3, &Vector3) -> Intersect,
+) -> Intersect {
+    let mut closest = Intersect::empty();
+    let mut max_distance = f32::INFINITY;
+    for &i in unbounded {
+        let hit = intersect(i, ray_origin, ray_direction);
+        if hit.is_intersecting && hit.distance < max_distance {
+            max_distance = hit.distance;
+            closest = hit;
+        }
+    }
+    bvh.traverse(ray_origin, ray_direction, max_distance, |index, max_distance| {
+        let hit = intersect(bounded[index], ray_origin, ray_direction);
+        if !hit.is_intersecting || hit.distance >= max_distance {
+            return None;
+        }
+        let distance = hit.distance;
+        closest = hit;
+        Some(distance)
+    });
+    closest
+}
@@ -1,11 +1,67 @@
 // light.rs
 use raylib::prelude::*;
+use std::f32::consts::PI;
+
+/// Cómo disminuye la luz con la distancia.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Falloff {
+    /// La misma intensidad a cualquier distancia (luz direccional o de estilo clásico).
+    None,
+    /// Ley física del inverso del cuadrado: `1 / d²`.
+    InverseSquare,
+    /// Baja linealmente de 1 junto a la luz a 0 en `range`.
+    Linear { range: f32 },
+    /// Inverso del cuadrado con un corte suave que llega a 0 en `range`
+    /// (ventana `(1 - (d / range)⁴)²`, como en los motores de tiempo real).
+    Range { range: f32 },
+}
+
+impl Falloff {
+    /// Factor por el que se multiplica la intensidad a esa distancia.
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        // Evita que el inverso del cuadrado se dispare pegado a la luz
+        let distance_squared = (distance * distance).max(1e-4);
+        match *self {
+            Falloff::None => 1.0,
+            Falloff::InverseSquare => 1.0 / distance_squared,
+            Falloff::Linear { range } => (1.0 - distance / range.max(1e-6)).clamp(0.0, 1.0),
+            Falloff::Range { range } => {
+                let ratio = distance / range.max(1e-6);
+                let window = (1.0 - ratio.powi(4)).clamp(0.0, 1.0);
+                window * window / distance_squared
+            }
+        }
+    }
+}
+
+/// Cono de un foco: ilumina por completo dentro de `inner_angle` alrededor de `direction` y se
+/// apaga suavemente hasta `outer_angle` (ángulos desde el eje, en radianes).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spot {
+    pub direction: Vector3,
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+}
+
+impl Spot {
+    /// Factor en [0, 1] para un punto en la dirección `to_point` desde la luz. Entre los dos
+    /// ángulos interpola en coseno y eleva al cuadrado, como propone KHR_lights_punctual.
+    pub fn attenuation(&self, to_point: Vector3) -> f32 {
+        let cos_angle = self.direction.normalized().dot(to_point.normalized());
+        let cos_inner = self.inner_angle.cos();
+        let cos_outer = self.outer_angle.cos();
+        let t = ((cos_angle - cos_outer) / (cos_inner - cos_outer).max(1e-4)).clamp(0.0, 1.0);
+        t * t
+    }
+}
 
 #[derive(Clone, Copy, Debug)]
 pub struct Light {
     pub position: Vector3,
     pub color: Vector3,
     pub intensity: f32,
+    pub falloff: Falloff,
+    pub spot: Option<Spot>, // Si es un foco, su cono; si no, ilumina en todas direcciones
 }
 
 impl Light {
@@ -14,6 +70,42 @@ impl Light {
             position,
             color,
             intensity,
+            falloff: Falloff::None,
+            spot: None,
+        }
+    }
+
+    /// Luz puntual con intensidad luminosa en candelas; cae con el inverso del cuadrado.
+    /// La iluminancia (lux) a d metros es `candela / d²` y una superficie blanca mate la refleja
+    /// como luminancia `lux / π`, que es lo que guarda `intensity`.
+    pub fn from_candela(position: Vector3, color: Vector3, candela: f32) -> Self {
+        Light {
+            falloff: Falloff::InverseSquare,
+            ..Light::new(position, color, candela / PI)
         }
     }
-}
\ No newline at end of file
+
+    /// Luz lejana (sol) a partir de su iluminancia en lux; no se atenúa con la distancia.
+    pub fn from_lux(position: Vector3, color: Vector3, lux: f32) -> Self {
+        Light::new(position, color, lux / PI)
+    }
+
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// Convierte la luz en un foco que apunta hacia `direction`.
+    pub fn with_spot(mut self, direction: Vector3, inner_angle: f32, outer_angle: f32) -> Self {
+        self.spot = Some(Spot { direction, inner_angle, outer_angle: outer_angle.max(inner_angle) });
+        self
+    }
+
+    /// Intensidad que llega a `point` una vez aplicada la atenuación por distancia y, si es un
+    /// foco, la de su cono.
+    pub fn intensity_at(&self, point: Vector3) -> f32 {
+        let to_point = point - self.position;
+        let cone = self.spot.map_or(1.0, |spot| spot.attenuation(to_point));
+        self.intensity * self.falloff.attenuation(to_point.length()) * cone
+    }
+}
@@ -14,26 +14,84 @@ mod material;
 mod light;
 mod snell;
 mod textures;
+mod environment;
+mod sampling;
+mod ibl;
+mod settings;
+mod tangent;
+mod relief;
+mod pbr;
+mod material_library;
+mod area_light;
+mod occlusion;
+mod plane;
+mod triangle;
+mod quad;
+mod scene;
+mod aabb;
+mod bvh;
+mod mesh;
+mod obj;
+mod gltf;
+mod disk;
+mod cylinder;
+mod cone;
+mod capsule;
+mod torus;
+mod csg;
+mod sdf;
+mod transform;
+mod instance;
+mod scene_graph;
+mod animation;
+mod motion;
 use framebuffer::Framebuffer;
 use ray_intersect::{RayIntersect, Intersect};
 use cube::Cube;
-use camera::Camera;
+use camera::{ApertureShape, Camera, CameraMode, Projection};
 use material::{Material, vector3_to_color};
-use light::Light;
+use light::{Falloff, Light};
 use snell::{reflect, refract};
 use textures::{TextureManager, SkyboxTextures};
+use ibl::{IblMode, ImageBasedLighting};
+use settings::{RenderSettings, exposure_from_ev100};
+use pbr::{PbrMaterial, PbrSurface, fresnel_schlick};
+use material_library::MaterialLibrary;
+use area_light::AreaLight;
+use occlusion::{AoMode, VoxelGrid};
+use plane::Plane;
+use triangle::Triangle;
+use quad::Quad;
+use scene::Scene;
+use obj::ObjModel;
+use gltf::GltfScene;
+use disk::Disk;
+use cylinder::Cylinder;
+use cone::Cone;
+use capsule::Capsule;
+use torus::Torus;
+use csg::Csg;
+use sdf::{Sdf, SdfObject};
+use transform::{Transform, Transformed};
+use instance::Blas;
+use scene_graph::SceneGraph;
+use animation::{Animation, Interpolation, LightAnimation, MaterialAnimation, NodeAnimation, Track};
+use motion::{MotionTransform, Moving, Shutter};
+use std::sync::Arc;
+use std::collections::HashMap;
 
 fn cast_shadow(
     intersect: &Intersect,
     light: &Light,
-    objects: &[Cube], //poner sphere si se quiere usar esferas
+    time: f32,
+    objects: &[Box<dyn RayIntersect>],
 ) -> f32 {
     let light_direction = (light.position - intersect.point).normalized();
     let shadow_ray_origin = intersect.point + intersect.normal * 0.001; // Bias para evitar auto-intersección
     let light_distance = (light.position - shadow_ray_origin).length();
     
     for object in objects {
-        let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_direction);
+        let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_direction, time);
         if shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance {
             return 0.7;
         }
@@ -41,6 +99,139 @@ fn cast_shadow(
     0.0
 }
 
+/// Estima por Monte Carlo la luz difusa que llega del entorno, combinando direcciones
+/// muestreadas según su luminancia y según el coseno, y descartando las que bloquea la geometría.
+fn sample_environment_lighting(
+    intersect: &Intersect,
+    normal: &Vector3,
+    time: f32,
+    objects: &[Box<dyn RayIntersect>],
+    texture_manager: &TextureManager,
+    ibl: &ImageBasedLighting,
+    samples: u32,
+) -> Vector3 {
+    // Radiancia·coseno que llega por `direction`, o cero si algo la tapa.
+    let incoming = |direction: Vector3, cos_theta: f32| {
+        let origin = offset_origin(intersect, &direction);
+        if objects.iter().any(|object| object.ray_intersect(&origin, &direction, time).is_intersecting) {
+            Vector3::zero()
+        } else {
+            texture_manager.environment_radiance(direction) * cos_theta
+        }
+    };
+    // MIS con heurística de balance: una muestra según la luminancia del entorno y otra según
+    // el coseno, cada una dividida por la suma de ambas densidades. Así ni un cielo casi uniforme
+    // ni un sol pequeño dejan ruido.
+    let mut total = Vector3::zero();
+    for _ in 0..samples {
+        let (direction, environment_pdf) = ibl.sample_direction();
+        let cos_theta = normal.dot(direction);
+        if cos_theta > 0.0 && environment_pdf > 0.0 {
+            total += incoming(direction, cos_theta) / (environment_pdf + cos_theta / PI);
+        }
+
+        let direction = sampling::cosine_sample_hemisphere(*normal);
+        let cos_theta = normal.dot(direction);
+        if cos_theta > 0.0 {
+            total += incoming(direction, cos_theta) / (cos_theta / PI + ibl.pdf(direction));
+        }
+    }
+    // Radiancia difusa saliente de una superficie blanca: E / π.
+    total / (samples.max(1) as f32 * PI)
+}
+
+/// Radiancia difusa que aporta el entorno según el modo de IBL (cero si no hay IBL).
+fn environment_irradiance(
+    intersect: &Intersect,
+    normal: &Vector3,
+    time: f32,
+    objects: &[Box<dyn RayIntersect>],
+    texture_manager: &TextureManager,
+    settings: &RenderSettings,
+) -> Vector3 {
+    let Some(ibl) = texture_manager.ibl() else {
+        return Vector3::zero();
+    };
+    let irradiance = match settings.ibl_mode {
+        IblMode::Off => Vector3::zero(),
+        IblMode::Precomputed => ibl.irradiance(*normal),
+        IblMode::Sampled { samples } => sample_environment_lighting(intersect, normal, time, objects, texture_manager, ibl, samples),
+    };
+    irradiance * settings.ibl_strength
+}
+
+/// Reflejo del entorno prefiltrado para una rugosidad (cero si la IBL está apagada).
+fn environment_specular(
+    direction: Vector3,
+    roughness: f32,
+    texture_manager: &TextureManager,
+    settings: &RenderSettings,
+) -> Vector3 {
+    match texture_manager.ibl() {
+        Some(ibl) if settings.ibl_mode != IblMode::Off => {
+            let sharp = texture_manager.environment_radiance(direction);
+            ibl.specular(direction, roughness, sharp) * settings.ibl_strength
+        }
+        _ => Vector3::zero(),
+    }
+}
+
+/// Rayo secundario que genera una superficie: reflejo o refracción con un índice de refracción.
+enum Scatter {
+    Reflect,
+    Refract(f32),
+}
+
+/// Traza el reflejo o la refracción de una superficie. Con rugosidad 0 es un único rayo perfecto;
+/// si no, promedia `samples` rayos desviados por normales de microfaceta GGX (reflejos brillantes,
+/// vidrio esmerilado).
+fn trace_glossy(
+    intersect: &Intersect,
+    normal: &Vector3,
+    ray_direction: &Vector3,
+    roughness: f32,
+    scatter: Scatter,
+    samples: u32,
+    trace: impl Fn(&Vector3, &Vector3) -> Vector3,
+) -> Vector3 {
+    let scatter_direction = |microfacet: &Vector3| match scatter {
+        Scatter::Reflect => reflect(ray_direction, microfacet),
+        Scatter::Refract(refractive_index) => {
+            let refracted = refract(ray_direction, microfacet, refractive_index);
+            // Reflexión total interna: el rayo se refleja en lugar de atravesar
+            if refracted.length() > 0.0 { refracted } else { reflect(ray_direction, microfacet) }
+        }
+    };
+    let trace_direction = |direction: Vector3| {
+        let origin = offset_origin(intersect, &direction);
+        trace(&origin, &direction)
+    };
+
+    if roughness <= 0.0 {
+        return trace_direction(scatter_direction(normal));
+    }
+
+    let alpha = pbr::roughness_to_alpha(roughness);
+    let incoming_side = ray_direction.dot(*normal);
+    let samples = samples.max(1);
+    let mut total = Vector3::zero();
+    for _ in 0..samples {
+        let microfacet = sampling::sample_ggx_microfacet(*normal, alpha);
+        let mut direction = scatter_direction(&microfacet);
+        // Los reflejos deben volver hacia el lado del observador y las refracciones cruzar la superficie
+        let crosses_surface = direction.dot(*normal) * incoming_side > 0.0;
+        if crosses_surface != matches!(scatter, Scatter::Refract(_)) {
+            direction = scatter_direction(normal);
+        }
+        total += trace_direction(direction);
+    }
+    total / samples as f32
+}
+
+/// Por debajo de esta rugosidad los materiales PBR trazan un rayo de reflejo en lugar
+/// de usar solo el entorno prefiltrado, para que reflejen también la geometría.
+const PBR_TRACED_REFLECTION_ROUGHNESS: f32 = 0.3;
+
 const ORIGIN_BIAS: f32 = 1e-4;
 fn offset_origin(intersect: &Intersect, ray_direction: &Vector3) -> Vector3 {
     let offset = intersect.normal * ORIGIN_BIAS;
@@ -51,14 +242,18 @@ fn offset_origin(intersect: &Intersect, ray_direction: &Vector3) -> Vector3 {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn cast_ray(
     ray_origin: &Vector3,
     ray_direction: &Vector3,
-    objects: &[Cube],
-    light: &Light,
-    emissive_objects: &[&Cube],
+    time: f32,
+    objects: &[Box<dyn RayIntersect>],
+    scene_lights: &[Light],
+    area_lights: &[AreaLight],
+    voxels: &VoxelGrid,
     depth: u32,
     texture_manager: &TextureManager,
+    settings: &RenderSettings,
 ) -> Vector3 {
     if depth > 1 { // Limitar profundidad para rendimiento
         return texture_manager.sample_skybox(*ray_direction);
@@ -67,7 +262,7 @@ pub fn cast_ray(
     let mut intersect = Intersect::empty();
     let mut zbuffer = f32::INFINITY;
     for object in objects {
-        let tmp = object.ray_intersect(ray_origin, ray_direction);
+        let tmp = object.ray_intersect(ray_origin, ray_direction, time);
         if tmp.is_intersecting && tmp.distance < zbuffer {
             zbuffer = tmp.distance;
             intersect = tmp;
@@ -82,306 +277,1081 @@ pub fn cast_ray(
     let emission = intersect.material.emission;
     
     // Cálculo de Iluminación (Directa e Indirecta)
-    let mut total_diffuse_intensity = 0.0;
+    let mut total_diffuse = Vector3::zero();
     let mut total_specular = Vector3::zero();
 
     // Crear una lista de todas las fuentes de luz para esta intersección
-    let mut lights: Vec<Light> = vec![*light];
+    let mut lights: Vec<Light> = scene_lights.to_vec();
 
-    // Añadir luces desde los objetos emisivos
-    for emissive_cube in emissive_objects {
-        let cube_center = (emissive_cube.min_bounds + emissive_cube.max_bounds) * 0.5;
-        let diff_vec = cube_center - intersect.point;
-        if diff_vec.dot(diff_vec) < 0.01 { continue; }
-        
-        lights.push(Light::new(
-            cube_center,
-            emissive_cube.material.emission.normalized(),
-            emissive_cube.material.emission.length()
-        ));
+    // El sol del HDRI (si hay uno cargado) actúa como luz direccional
+    if let Some(sun) = texture_manager.environment_light(intersect.point) {
+        lights.push(sun);
+    }
+
+    // Añadir luces muestreadas sobre la superficie de los objetos emisivos
+    let light_samples = if depth == 0 { settings.area_light_samples } else { 1 };
+    for area_light in area_lights {
+        area_light.sample_lights(intersect.point, light_samples, &mut lights);
     }
     
     let view_direction = (*ray_origin - intersect.point).normalized();
+
+    // Rayos secundarios (reflejo y refracción) y cuántos trazar en superficies rugosas
+    let trace = |origin: &Vector3, direction: &Vector3| {
+        cast_ray(origin, direction, time, objects, scene_lights, area_lights, voxels, depth + 1, texture_manager, settings)
+    };
+    let glossy_samples = if depth == 0 { settings.glossy_samples } else { 1 };
+
+    // Oclusión ambiental (en los rebotes basta con un rayo)
+    let ao_mode = match settings.ao_mode {
+        AoMode::Hemisphere { max_distance, .. } if depth > 0 => AoMode::Hemisphere { samples: 1, max_distance },
+        mode => mode,
+    };
+    let occlusion = occlusion::ambient_occlusion(ao_mode, intersect.point, intersect.normal, time, objects, voxels);
+
+    // Parallax occlusion mapping: desplaza las UVs antes de cualquier consulta de textura
+    if intersect.material.parallax_occlusion
+        && let Some(height_map) = &intersect.material.height_map
+    {
+        let (u, v) = relief::parallax_occlusion_uv(texture_manager, height_map, &intersect, view_direction, intersect.material.height_scale);
+        intersect.u = u;
+        intersect.v = v;
+    }
+
     let mut normal = intersect.normal;
-    // ... (lógica de normal map sin cambios) ...
-    if let Some(normal_map_path) = &intersect.material.normal_map_id {
-        let texture = texture_manager.get_texture(normal_map_path).unwrap();
-        let width = texture.width() as u32; let height = texture.height() as u32;
+    if let Some(normal_map_path) = &intersect.material.normal_map_id
+        && let Some((width, height)) = texture_manager.texture_size(normal_map_path)
+    {
+        let (width, height) = (width as u32, height as u32);
         let tx = (intersect.u * width as f32) as u32; let ty = (intersect.v * height as f32) as u32;
         if let Some(tex_normal) = texture_manager.get_normal_from_map(normal_map_path, tx, ty) {
-            let tangent = Vector3::new(normal.y, -normal.x, 0.0).normalized();
-            let bitangent = normal.cross(tangent);
-            let transformed_normal_x = tex_normal.x * tangent.x + tex_normal.y * bitangent.x + tex_normal.z * normal.x;
-            let transformed_normal_y = tex_normal.x * tangent.y + tex_normal.y * bitangent.y + tex_normal.z * normal.y;
-            let transformed_normal_z = tex_normal.x * tangent.z + tex_normal.y * bitangent.z + tex_normal.z * normal.z;
-            normal = Vector3::new(transformed_normal_x, transformed_normal_y, transformed_normal_z).normalized();
+            // Base tangente de la primitiva (alineada con sus UVs), ortogonalizada contra la normal
+            let (tangent, bitangent) = relief::shading_frame(&intersect, normal);
+            let strength = intersect.material.normal_map_strength;
+            normal = (tangent * (tex_normal.x * strength) + bitangent * (tex_normal.y * strength) + normal * tex_normal.z).normalized();
         }
     }
+    // Bump mapping: el gradiente del mapa de alturas inclina la normal
+    if let Some(height_map) = &intersect.material.height_map {
+        normal = relief::bump_normal(texture_manager, height_map, &intersect, normal, intersect.material.height_scale);
+    }
     
+    let diffuse_color = if let Some(texture_path) = &intersect.material.texture
+        && let Some((width, height)) = texture_manager.texture_size(texture_path)
+    {
+        let (width, height) = (width as u32, height as u32);
+        let tx = (intersect.u * width as f32) as u32; let ty = (intersect.v * height as f32) as u32;
+        texture_manager.get_pixel_color(texture_path, tx, ty)
+    } else {
+        intersect.material.diffuse
+    };
+
+    // Modelo físico metallic-roughness (GGX + Smith), si el material lo usa
+    if let Some(pbr) = &intersect.material.pbr {
+        let base_color = if intersect.material.texture.is_some() { diffuse_color * pbr.base_color } else { pbr.base_color };
+        let metallic_roughness = match &pbr.metallic_roughness_texture {
+            Some(path) => texture_manager.sample_texture(path, intersect.u, intersect.v),
+            None => Vector3::one(),
+        };
+        let surface = PbrSurface::new(pbr, base_color, metallic_roughness);
+        let n_dot_v = normal.dot(view_direction).max(1e-4);
+
+        // Luz directa
+        let mut direct = Vector3::zero();
+        for current_light in &lights {
+            let light_direction = (current_light.position - intersect.point).normalized();
+            let shadow_intensity = cast_shadow(&intersect, current_light, time, objects);
+            let light_intensity = current_light.intensity_at(intersect.point) * (1.0 - shadow_intensity);
+            direct += current_light.color * surface.direct_lighting(normal, view_direction, light_direction) * light_intensity;
+        }
+
+        // Entorno difuso
+        let fresnel = fresnel_schlick(surface.f0, n_dot_v);
+        let irradiance = environment_irradiance(&intersect, &normal, time, objects, texture_manager, settings) + settings.ambient_light;
+        let ambient = irradiance * surface.base_color * surface.diffuse_weight(fresnel) * occlusion;
+
+        // Reflejo especular: trazado en superficies pulidas, prefiltrado en las rugosas
+        let reflect_direction = reflect(ray_direction, &normal).normalized();
+        let mut reflected = environment_specular(reflect_direction, surface.roughness, texture_manager, settings);
+        let traced_weight = (1.0 - surface.roughness / PBR_TRACED_REFLECTION_ROUGHNESS).clamp(0.0, 1.0);
+        if traced_weight > 0.0 {
+            let traced = trace_glossy(&intersect, &normal, ray_direction, surface.roughness, Scatter::Reflect, glossy_samples, trace);
+            reflected = traced * traced_weight + reflected * (1.0 - traced_weight);
+        }
+        let specular_environment = reflected * pbr::environment_brdf(surface.f0, surface.roughness, n_dot_v);
+
+        // Transmisión (vidrio, agua): lo que no se refleja atraviesa la superficie teñido por el color base
+        let mut transmitted = Vector3::zero();
+        let transmission_weight = surface.transmission * (1.0 - surface.metallic);
+        if transmission_weight > 0.0 {
+            let traced = trace_glossy(&intersect, &normal, ray_direction, surface.roughness, Scatter::Refract(pbr.ior), glossy_samples, trace);
+            transmitted = traced * surface.base_color * (Vector3::one() - fresnel) * transmission_weight;
+        }
+
+        return emission + direct + ambient + specular_environment + transmitted;
+    }
+
+    // Modelo clásico (Phong)
     // Iterar sobre todas las luces (la principal y las de los objetos emisivos)
     for current_light in &lights {
         let light_direction = (current_light.position - intersect.point).normalized();
         let reflection_direction = reflect(&-light_direction, &normal).normalized();
         
-        let shadow_intensity = cast_shadow(&intersect, current_light, objects);
-        let light_intensity = current_light.intensity * (1.0 - shadow_intensity);
+        let shadow_intensity = cast_shadow(&intersect, current_light, time, objects);
+        let light_intensity = current_light.intensity_at(intersect.point) * (1.0 - shadow_intensity);
         
-        total_diffuse_intensity += normal.dot(light_direction).max(0.0) * light_intensity;
+        total_diffuse += current_light.color * (normal.dot(light_direction).max(0.0) * light_intensity);
         
         let specular_intensity = view_direction.dot(reflection_direction).max(0.0).powf(intersect.material.specular) * light_intensity;
         total_specular += current_light.color * specular_intensity;
     }
 
-    let diffuse_color = if let Some(texture_path) = &intersect.material.texture {
-        let texture = texture_manager.get_texture(texture_path).unwrap();
-        let width = texture.width() as u32; let height = texture.height() as u32;
-        let tx = (intersect.u * width as f32) as u32; let ty = (intersect.v * height as f32) as u32;
-        texture_manager.get_pixel_color(texture_path, tx, ty)
-    } else {
-        intersect.material.diffuse
-    };
-    let diffuse = diffuse_color * total_diffuse_intensity;
+    let diffuse = diffuse_color * total_diffuse;
     let specular = total_specular;
+
+    // Luz ambiente e iluminación del entorno (IBL), oscurecidas por la oclusión ambiental,
+    // más el reflejo brillante prefiltrado
+    let irradiance = environment_irradiance(&intersect, &normal, time, objects, texture_manager, settings) + settings.ambient_light;
+    let ambient = diffuse_color * irradiance * occlusion;
+    let mut glossy = Vector3::zero();
+    if settings.ibl_mode != IblMode::Off {
+        let reflect_direction = reflect(ray_direction, &normal).normalized();
+        let prefiltered = environment_specular(reflect_direction, intersect.material.phong_roughness(), texture_manager, settings);
+        // Fresnel de Schlick con F0 = 0.04 (dieléctrico); los espejos ya trazan su propio rayo.
+        let cos_theta = normal.dot(view_direction).clamp(0.0, 1.0);
+        let fresnel = 0.04 + 0.96 * (1.0 - cos_theta).powi(5);
+        glossy = prefiltered * (fresnel * intersect.material.albedo[1].min(1.0) * (1.0 - intersect.material.reflectivity));
+    }
     
-    // Reflejo
+    // Reflejo (brillante si el material es rugoso)
     let mut reflection_color = Vector3::zero();
     let reflectivity = intersect.material.reflectivity;
     if reflectivity > 0.0 {
-        let reflect_direction = reflect(ray_direction, &normal);
-        let reflect_origin = offset_origin(&intersect, &reflect_direction);
-        reflection_color = cast_ray(&reflect_origin, &reflect_direction, objects, light, emissive_objects, depth + 1, texture_manager);
+        reflection_color = trace_glossy(&intersect, &normal, ray_direction, intersect.material.roughness, Scatter::Reflect, glossy_samples, trace);
     }
     
-    // Transparencia
+    // Transparencia (esmerilada si el material es rugoso)
     let mut refraction_color = Vector3::zero();
     let transparency = intersect.material.transparency;
     if transparency > 0.0 {
-        let refract_direction = refract(ray_direction, &normal, intersect.material.refractive_index);
-        let refract_origin = offset_origin(&intersect, &refract_direction);
-        refraction_color = cast_ray(&refract_origin, &refract_direction, objects, light, emissive_objects, depth + 1, texture_manager);
+        refraction_color = trace_glossy(&intersect, &normal, ray_direction, intersect.material.roughness, Scatter::Refract(intersect.material.refractive_index), glossy_samples, trace);
     }
     
-    // Color final = Emisión + Luz Recibida (Difusa y Especular) + Reflejos + Refracciones
+    // Color final = Emisión + Luz Recibida (Difusa y Especular) + Entorno + Reflejos + Refracciones
     let color = emission + 
-                diffuse * intersect.material.albedo[0] + 
+                (diffuse + ambient) * intersect.material.albedo[0] + 
                 specular * intersect.material.albedo[1] + 
+                glossy + 
                 reflection_color * reflectivity + 
                 refraction_color * transparency;
     color
 }
 
+/// Renderiza la escena vista desde `camera` y devuelve los colores de los píxeles fila a fila,
+/// con la exposición de `settings` ya aplicada.
+#[allow(clippy::too_many_arguments)]
 pub fn render(
     width: i32,
     height: i32,
-    objects: &[Cube],
+    objects: &[Box<dyn RayIntersect>],
     camera: &Camera,
-    light: &Light,
-    emissive_objects: &[&Cube],
+    lights: &[Light],
+    area_lights: &[AreaLight],
+    voxels: &VoxelGrid,
     texture_manager: &TextureManager,
+    settings: &RenderSettings,
 ) -> Vec<Color> {
-    let aspect_ratio = width as f32 / height as f32;
-    let fov = PI / 3.0;
-    let perspective_scale = (fov * 0.5).tan();
-    let camera_eye = camera.eye;
+    render_radiance(width, height, objects, camera, lights, area_lights, voxels, texture_manager, settings)
+        .into_iter()
+        .map(|radiance| vector3_to_color(radiance * settings.exposure))
+        .collect()
+}
+
+/// Como `render`, pero devuelve la radiancia lineal de cada píxel, sin exposición ni conversión a
+/// 8 bits, para poder promediar varias pasadas antes de convertirlas a color una sola vez.
+#[allow(clippy::too_many_arguments)]
+pub fn render_radiance(
+    width: i32,
+    height: i32,
+    objects: &[Box<dyn RayIntersect>],
+    camera: &Camera,
+    lights: &[Light],
+    area_lights: &[AreaLight],
+    voxels: &VoxelGrid,
+    texture_manager: &TextureManager,
+    settings: &RenderSettings,
+) -> Vec<Vector3> {
+    // Con el obturador abierto cada píxel promedia varios instantes del fotograma, y con apertura
+    // varios puntos de la lente; cada muestra elige las dos cosas a la vez
+    let time_samples = if settings.shutter.is_open() { settings.time_samples.max(1) } else { 1 };
+    let lens_samples = if camera.aperture_radius > 0.0 { settings.lens_samples.max(1) } else { 1 };
+    let samples = time_samples.max(lens_samples);
 
     (0..height)
         .into_par_iter()
         .flat_map(|y| (0..width).into_par_iter().map(move |x| (x, y)))
         .map(|(x, y)| {
-            let screen_x = (2.0 * x as f32) / width as f32 - 1.0;
-            let screen_y = -(2.0 * y as f32) / height as f32 + 1.0;
-            let screen_x = screen_x * aspect_ratio * perspective_scale;
-            let screen_y = screen_y * perspective_scale;
-            let ray_direction = Vector3::new(screen_x, screen_y, -1.0).normalized();
-            let rotated_direction = camera.basis_change(&ray_direction);
-            let pixel_color_vec = cast_ray(
-                &camera_eye,
-                &rotated_direction,
-                objects,
-                light,
-                emissive_objects,
-                0,
-                texture_manager,
-            );
-            vector3_to_color(pixel_color_vec)
+            let mut pixel_color_vec = Vector3::zero();
+            for sample in 0..samples {
+                let time = settings.shutter.sample(sample, samples);
+                let moved;
+                let camera = if camera.motion.is_some() {
+                    moved = camera.at(time);
+                    &moved
+                } else {
+                    camera
+                };
+                // Los píxeles fuera de la proyección (ojo de pez) quedan negros
+                let Some((origin, direction)) = camera.primary_ray(x, y, width, height) else { continue };
+                let (ray_origin, ray_direction) = camera.lens_ray(origin, direction);
+                pixel_color_vec += cast_ray(
+                    &ray_origin,
+                    &ray_direction,
+                    time,
+                    objects,
+                    lights,
+                    area_lights,
+                    voxels,
+                    0,
+                    texture_manager,
+                    settings,
+                );
+            }
+            pixel_color_vec / samples as f32
         })
         .collect()
 }
 
-fn main() {
-    let window_width = 800;
-    let window_height = 600;
-    let (mut window, raylib_thread) = raylib::init()
-        .size(window_width, window_height)
-        .title("Raytracer Minecraft")
-        .log_level(TraceLogLevel::LOG_WARNING)
-        .build();
-    
-    let mut texture_manager = TextureManager::new();
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/grass.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/glass.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/magma.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/diamond_ore.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/oak.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/wood_planks.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/stone.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/obsidian.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/water.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/leaves.png");
-    texture_manager.load_texture(&mut window, &raylib_thread, "assets/dirt.png");
-
-    let skybox = SkyboxTextures {
-        front: "assets/skybox/front.png".to_string(),
-        back: "assets/skybox/back.png".to_string(),
-        left: "assets/skybox/left.png".to_string(),
-        right: "assets/skybox/right.png".to_string(),
-        top: "assets/skybox/top.png".to_string(),
-        bottom: "assets/skybox/bottom.png".to_string(),
+/// Imagen en escala de grises de la oclusión ambiental vista desde la cámara (blanco = sin oclusión).
+/// Si la oclusión está apagada en `settings` se usa el muestreo del hemisferio.
+pub fn render_ambient_occlusion(
+    width: i32,
+    height: i32,
+    objects: &[Box<dyn RayIntersect>],
+    camera: &Camera,
+    voxels: &VoxelGrid,
+    settings: &RenderSettings,
+) -> Vec<Color> {
+    let mode = match settings.ao_mode {
+        AoMode::Off => AoMode::Hemisphere { samples: 16, max_distance: 1.0 },
+        mode => mode,
     };
 
-    texture_manager.load_skybox(&mut window, &raylib_thread, skybox);
-    
-    let zero_emission = Vector3::zero();
+    (0..height)
+        .into_par_iter()
+        .flat_map(|y| (0..width).into_par_iter().map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let time = settings.shutter.open;
+            let Some((ray_origin, ray_direction)) = camera.primary_ray(x, y, width, height) else {
+                return Color::BLACK;
+            };
+            let mut intersect = Intersect::empty();
+            let mut zbuffer = f32::INFINITY;
+            for object in objects {
+                let tmp = object.ray_intersect(&ray_origin, &ray_direction, time);
+                if tmp.is_intersecting && tmp.distance < zbuffer {
+                    zbuffer = tmp.distance;
+                    intersect = tmp;
+                }
+            }
+            let visibility = if intersect.is_intersecting {
+                occlusion::ambient_occlusion(mode, intersect.point, intersect.normal, time, objects, voxels)
+            } else {
+                1.0
+            };
+            vector3_to_color(Vector3::new(visibility, visibility, visibility))
+        })
+        .collect()
+}
 
-    let glass = Material {
-        diffuse: Vector3::new(1.0, 1.0, 1.0), albedo: [0.0,5.0], specular: 125.0, reflectivity: 0.1, 
-        transparency: 0.9, refractive_index: 1.5, texture: Some("assets/glass.png".to_string()), 
-        normal_map_id: None, emission: zero_emission,
-    };
-    let dirt = Material {
-        diffuse: Vector3::new(0.4, 0.26, 0.13), albedo: [0.8, 0.2], specular: 1.0, reflectivity: 0.0, 
-        transparency: 0.0, refractive_index: 1.0, texture: Some("assets/dirt.png".to_string()), 
-        normal_map_id: None, emission: zero_emission,
-    };
-    let grass = Material {
-        diffuse: Vector3::new(0.2, 0.6, 0.2), albedo: [0.7, 0.3], specular: 2.0, reflectivity: 0.0, 
-        transparency: 0.0, refractive_index: 1.0, texture: Some("assets/grass.png".to_string()), 
-        normal_map_id: None, emission: zero_emission,
-    };
-    let leaves = Material {
-        diffuse: Vector3::new(0.1, 0.5, 0.1), albedo: [0.6, 0.4], specular: 3.0, reflectivity: 0.0, 
-        transparency: 0.0, refractive_index: 1.2, texture: Some("assets/leaves.png".to_string()), 
-        normal_map_id: None, emission: zero_emission,
-    };
-    let magma = Material {
-        diffuse: Vector3::new(1.0, 0.3, 0.0), albedo: [0.9, 0.1], specular: 50.0, reflectivity: 0.0, 
-        transparency: 0.0, refractive_index: 1.0, texture: Some("assets/magma.png".to_string()), 
-        normal_map_id: None, emission: Vector3::new(1.0, 0.4, 0.1) * 0.5, // <-- Emite luz naranja
-    };
-    let oak = Material {
-        diffuse: Vector3::new(0.6, 0.4, 0.2), albedo: [0.8, 0.2], specular: 5.0, reflectivity: 0.0, 
-        transparency: 0.0, refractive_index: 1.0, texture: Some("assets/oak.png".to_string()), 
-        normal_map_id: None, emission: zero_emission,
-    };
-    let wood_planks = Material {
-        diffuse: Vector3::new(0.6, 0.4, 0.2), albedo: [0.8, 0.2], specular: 5.0, reflectivity: 0.0, 
-        transparency: 0.0, refractive_index: 1.0, texture: Some("assets/wood_planks.png".to_string()), 
-        normal_map_id: None, emission: zero_emission,
-    };
-    let stone = Material {
-        diffuse: Vector3::new(0.5, 0.5, 0.5), albedo: [0.7, 0.3], specular: 8.0, reflectivity: 0.0, 
-        transparency: 0.0, refractive_index: 0.5, texture: Some("assets/stone.png".to_string()), 
-        normal_map_id: None, emission: zero_emission,
-    };
-    let diamond_ore = Material {
-        diffuse: Vector3::new(0.4, 0.4, 0.4), albedo: [0.6, 0.4], specular: 20.0, reflectivity: 0.01, 
-        transparency: 0.0, refractive_index: 0.5, texture: Some("assets/diamond_ore.png".to_string()), 
-        normal_map_id: None, emission: zero_emission,
-    };
-    let obsidian = Material {
-        diffuse: Vector3::new(0.1, 0.05, 0.15), albedo: [0.8, 0.2], specular: 10.0, reflectivity: 0.1, 
-        transparency: 0.0, refractive_index: 1.0, texture: Some("assets/obsidian.png".to_string()), 
-        normal_map_id: None, emission: zero_emission,
-    };
-    let water = Material {
-        diffuse: Vector3::new(0.0, 0.3, 0.9), albedo: [0.6, 0.4], specular: 30.0, reflectivity: 0.15, 
-        transparency: 0.6, refractive_index: 1.2, texture: Some("assets/water.png".to_string()), 
-        normal_map_id: None, emission: zero_emission,
-    };
+/// Coloca bloques de la biblioteca como instancias: cada material se convierte en un único cubo
+/// unidad compartido la primera vez que se usa y los bloques solo guardan su posición y tamaño.
+fn block_builder(library: &MaterialLibrary) -> impl FnMut(&mut Scene, Vector3, f32, &str) + '_ {
+    let mut blocks: HashMap<String, Arc<Cube>> = HashMap::new();
+    move |scene, center, size, name| {
+        let block = blocks
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(library.cube(Vector3::zero(), 1.0, name).unwrap_or_else(|e| panic!("{}", e))));
+        scene.add_block(block, center, size);
+    }
+}
 
-    let mut objects: Vec<Cube> = Vec::new();
-    // ... (la creación de la escena con bucles no cambia) ...
+/// Escena principal: un paisaje de bloques de Minecraft con una luz blanca.
+fn minecraft_scene(library: &MaterialLibrary) -> Scene {
+    let mut block = block_builder(library);
+
+    let mut scene = Scene::new();
     for x_int in -5..=-1 {
         for z_int in -5..=1 {
             if (x_int == -4 || x_int == -3) && (z_int == 0 || z_int == -1) { continue; }
             if x_int == -2 && z_int == -4 { continue; } // Dejar hueco para el magma
-            objects.push(Cube::new(Vector3::new(x_int as f32, 0.0, z_int as f32), 1.0, stone.clone()));
+            block(&mut scene, Vector3::new(x_int as f32, 0.0, z_int as f32), 1.0, "stone");
         }
     }
-    objects.push(Cube::new(Vector3::new(-3.5, -0.5, -0.5), 2.0, water.clone()));
-    objects.push(Cube::new(Vector3::new(-2.0, 0.0, -4.0), 1.0, magma.clone()));
+    block(&mut scene, Vector3::new(-3.5, -0.5, -0.5), 2.0, "water");
+    block(&mut scene, Vector3::new(-2.0, 0.0, -4.0), 1.0, "magma");
 
     for y in 1..=3 {
-        objects.push(Cube::new(Vector3::new(-5.0, y as f32, -4.0), 1.0, stone.clone()));
-        objects.push(Cube::new(Vector3::new(-1.0, y as f32, -4.0), 1.0, stone.clone()));
-    }
-    objects.push(Cube::new(Vector3::new(-4.0, 1.0, -5.0), 1.0, diamond_ore.clone()));
-    objects.push(Cube::new(Vector3::new(-4.0, 2.0, -5.0), 1.0, stone.clone()));
-    objects.push(Cube::new(Vector3::new(-3.0, 1.0, -5.0), 1.0, diamond_ore.clone()));
-    objects.push(Cube::new(Vector3::new(-3.0, 2.0, -5.0), 1.0, diamond_ore.clone()));
-    objects.push(Cube::new(Vector3::new(-2.0, 1.0, -5.0), 1.0, stone.clone()));
-    objects.push(Cube::new(Vector3::new(-2.0, 2.0, -5.0), 1.0, stone.clone()));
-    for x in -4..=-2 { objects.push(Cube::new(Vector3::new(x as f32, 3.0, -4.0), 1.0, stone.clone())); }
+        block(&mut scene, Vector3::new(-5.0, y as f32, -4.0), 1.0, "stone");
+        block(&mut scene, Vector3::new(-1.0, y as f32, -4.0), 1.0, "stone");
+    }
+    block(&mut scene, Vector3::new(-4.0, 1.0, -5.0), 1.0, "diamond_ore");
+    block(&mut scene, Vector3::new(-4.0, 2.0, -5.0), 1.0, "stone");
+    block(&mut scene, Vector3::new(-3.0, 1.0, -5.0), 1.0, "diamond_ore");
+    block(&mut scene, Vector3::new(-3.0, 2.0, -5.0), 1.0, "diamond_ore");
+    block(&mut scene, Vector3::new(-2.0, 1.0, -5.0), 1.0, "stone");
+    block(&mut scene, Vector3::new(-2.0, 2.0, -5.0), 1.0, "stone");
+    for x in -4..=-2 { block(&mut scene, Vector3::new(x as f32, 3.0, -4.0), 1.0, "stone"); }
     for y in 1..=3 {
-        objects.push(Cube::new(Vector3::new(0.0, y as f32, 1.0), 1.0, obsidian.clone()));
-        objects.push(Cube::new(Vector3::new(0.0, y as f32, -2.0), 1.0, obsidian.clone()));
+        block(&mut scene, Vector3::new(0.0, y as f32, 1.0), 1.0, "obsidian");
+        block(&mut scene, Vector3::new(0.0, y as f32, -2.0), 1.0, "obsidian");
     }
     for z in -1..=0 {
-        objects.push(Cube::new(Vector3::new(0.0, 0.0, z as f32), 1.0, obsidian.clone()));
-        objects.push(Cube::new(Vector3::new(0.0, 4.0, z as f32), 1.0, obsidian.clone()));
+        block(&mut scene, Vector3::new(0.0, 0.0, z as f32), 1.0, "obsidian");
+        block(&mut scene, Vector3::new(0.0, 4.0, z as f32), 1.0, "obsidian");
     }
     for x_int in 1..=5 {
         for z_int in -4..=1 {
-            let material = if (2..=4).contains(&x_int) && (-1..=0).contains(&z_int) { grass.clone() } else { dirt.clone() };
-            objects.push(Cube::new(Vector3::new(x_int as f32, 0.0, z_int as f32), 1.0, material));
+            let material = if (2..=4).contains(&x_int) && (-1..=0).contains(&z_int) { "grass" } else { "dirt" };
+            block(&mut scene, Vector3::new(x_int as f32, 0.0, z_int as f32), 1.0, material);
         }
     }
-    objects.push(Cube::new(Vector3::new(3.0, 0.0, -2.0), 1.0, wood_planks.clone()));
-    objects.push(Cube::new(Vector3::new(3.0, 0.0, -3.0), 1.0, wood_planks.clone()));
+    block(&mut scene, Vector3::new(3.0, 0.0, -2.0), 1.0, "wood_planks");
+    block(&mut scene, Vector3::new(3.0, 0.0, -3.0), 1.0, "wood_planks");
     for x in 2..=4 {
-        for z in -4..=-2 { objects.push(Cube::new(Vector3::new(x as f32, 3.0, z as f32), 1.0, wood_planks.clone())); }
-        for y in 1..=2 { objects.push(Cube::new(Vector3::new(x as f32, y as f32, -4.0), 1.0, wood_planks.clone())); }
+        for z in -4..=-2 { block(&mut scene, Vector3::new(x as f32, 3.0, z as f32), 1.0, "wood_planks"); }
+        for y in 1..=2 { block(&mut scene, Vector3::new(x as f32, y as f32, -4.0), 1.0, "wood_planks"); }
     }
     for y in 1..=2 {
-        objects.push(Cube::new(Vector3::new(2.0, y as f32, -2.0), 1.0, wood_planks.clone()));
-        objects.push(Cube::new(Vector3::new(4.0, y as f32, -2.0), 1.0, wood_planks.clone()));
-    }
-    objects.push(Cube::new(Vector3::new(2.0, 1.0, -3.0), 1.0, wood_planks.clone()));
-    objects.push(Cube::new(Vector3::new(2.0, 2.0, -3.0), 1.0, glass.clone()));
-    objects.push(Cube::new(Vector3::new(4.0, 1.0, -3.0), 1.0, wood_planks.clone()));
-    objects.push(Cube::new(Vector3::new(4.0, 2.0, -3.0), 1.0, glass.clone()));
-    for y in 1..=3 { objects.push(Cube::new(Vector3::new(5.0, y as f32, 1.0), 1.0, oak.clone())); }
-    objects.push(Cube::new(Vector3::new(5.0, 5.0, 1.0), 1.0, leaves.clone()));
-    objects.push(Cube::new(Vector3::new(5.0, 4.0, 1.0), 1.0, leaves.clone()));
-    objects.push(Cube::new(Vector3::new(6.0, 4.0, 1.0), 1.0, leaves.clone()));
-    objects.push(Cube::new(Vector3::new(4.0, 4.0, 1.0), 1.0, leaves.clone()));
-    objects.push(Cube::new(Vector3::new(5.0, 4.0, 2.0), 1.0, leaves.clone()));
-    objects.push(Cube::new(Vector3::new(5.0, 4.0, 0.0), 1.0, leaves.clone()));
-
-    // Pre-filtrar los cubos que emiten luz
-    let emissive_cubes: Vec<&Cube> = objects.iter().filter(|c| c.material.emission.dot(c.material.emission) > 0.0).collect();
+        block(&mut scene, Vector3::new(2.0, y as f32, -2.0), 1.0, "wood_planks");
+        block(&mut scene, Vector3::new(4.0, y as f32, -2.0), 1.0, "wood_planks");
+    }
+    block(&mut scene, Vector3::new(2.0, 1.0, -3.0), 1.0, "wood_planks");
+    block(&mut scene, Vector3::new(2.0, 2.0, -3.0), 1.0, "glass");
+    block(&mut scene, Vector3::new(4.0, 1.0, -3.0), 1.0, "wood_planks");
+    block(&mut scene, Vector3::new(4.0, 2.0, -3.0), 1.0, "glass");
+    for y in 1..=3 { block(&mut scene, Vector3::new(5.0, y as f32, 1.0), 1.0, "oak"); }
+    block(&mut scene, Vector3::new(5.0, 5.0, 1.0), 1.0, "leaves");
+    block(&mut scene, Vector3::new(5.0, 4.0, 1.0), 1.0, "leaves");
+    block(&mut scene, Vector3::new(6.0, 4.0, 1.0), 1.0, "leaves");
+    block(&mut scene, Vector3::new(4.0, 4.0, 1.0), 1.0, "leaves");
+    block(&mut scene, Vector3::new(5.0, 4.0, 2.0), 1.0, "leaves");
+    block(&mut scene, Vector3::new(5.0, 4.0, 0.0), 1.0, "leaves");
+
+    scene.add_light(Light::new(Vector3::new(0.5, 5.0, 5.0), Vector3::new(1.0, 1.0, 1.0), 1.2));
+    scene
+}
+
+/// Escena de prueba de luz de colores: una pared y un suelo blancos iluminados por una luz roja
+/// y otra azul; donde se solapan la pared debe verse magenta.
+fn colored_lights_scene(library: &MaterialLibrary) -> Scene {
+    let mut block = block_builder(library);
+
+    let mut scene = Scene::new();
+    for x in -4..=4 {
+        for z in -3..=1 { block(&mut scene, Vector3::new(x as f32, 0.0, z as f32), 1.0, "white"); }
+        for y in 1..=4 { block(&mut scene, Vector3::new(x as f32, y as f32, -3.0), 1.0, "white"); }
+    }
+
+    let falloff = Falloff::Range { range: 8.0 };
+    let red = Light::new(Vector3::new(-2.0, 2.0, -1.0), Vector3::new(1.0, 0.0, 0.0), 4.0).with_falloff(falloff);
+    let blue = Light::new(Vector3::new(2.0, 2.0, -1.0), Vector3::new(0.0, 0.0, 1.0), 4.0).with_falloff(falloff);
+    scene.add_light(red);
+    scene.add_light(blue);
+    scene
+}
+
+/// Escena de prueba de primitivas: un suelo infinito, una rampa de triángulos, un cartel (quad)
+/// y un panel luminoso que actúa como luz de área.
+fn primitives_scene(library: &MaterialLibrary) -> Scene {
+    let material = |name: &str| library.material(name).unwrap_or_else(|e| panic!("{}", e));
+
+    let mut scene = Scene::new();
+    scene.add(Plane::new(Vector3::new(0.0, -0.5, 0.0), Vector3::new(0.0, 1.0, 0.0), material("stone")));
+
+    // Rampa: dos triángulos que suben de z = 2 a z = -1
+    let (a, b) = (Vector3::new(-3.0, -0.5, 2.0), Vector3::new(-1.0, -0.5, 2.0));
+    let (c, d) = (Vector3::new(-1.0, 1.0, -1.0), Vector3::new(-3.0, 1.0, -1.0));
+    let ramp = material("wood_planks");
+    scene.add(Triangle::new(a, b, c, ramp.clone()).with_uvs([(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]));
+    scene.add(Triangle::new(a, c, d, ramp).with_uvs([(0.0, 0.0), (1.0, 1.0), (0.0, 1.0)]));
+
+    // Cartel sobre un poste
+    scene.add_cube(Cube::new(Vector3::new(2.0, 0.0, 0.0), 0.2, material("oak")));
+    scene.add_cube(Cube::new(Vector3::new(2.0, 0.5, 0.0), 0.2, material("oak")));
+    scene.add_quad(Quad::new(Vector3::new(1.0, 0.8, 0.1), Vector3::new(2.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0), material("bricks")));
+
+    // Panel luminoso en el techo que ilumina hacia abajo
+    let panel = Material { emission: Vector3::new(2.0, 1.9, 1.7), ..material("white") };
+    scene.add_quad(Quad::new(Vector3::new(-1.0, 3.0, -1.0), Vector3::new(2.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 2.0), panel));
+
+    // Antorcha: palo de roble con un bloque de magma que ilumina
+    let up = Vector3::new(0.0, 1.0, 0.0);
+    scene.add(Cylinder::new(Vector3::new(-0.3, -0.5, 1.0), up, 0.06, 0.6, material("oak")));
+    scene.add_cube(Cube::new(Vector3::new(-0.3, 0.17, 1.0), 0.14, material("magma")));
+
+    // Tubería abierta, aro de obsidiana, árbol cónico, cápsula de vidrio y un charco
+    scene.add(Cylinder::new(Vector3::new(-3.0, 0.0, -2.0), Vector3::new(1.0, 0.0, 0.0), 0.25, 2.5, material("obsidian")).open());
+    scene.add(Torus::new(Vector3::new(0.5, 0.25, -2.0), Vector3::new(0.0, 0.0, 1.0), 0.6, 0.15, material("obsidian")));
+    scene.add(Cone::new(Vector3::new(3.5, -0.5, -1.5), up, 0.6, 1.5, material("leaves")));
+    scene.add(Capsule::new(Vector3::new(0.0, -0.2, 2.5), Vector3::new(1.0, -0.2, 2.5), 0.3, material("glass")));
+    scene.add(Disk::new(Vector3::new(2.5, -0.49, 2.0), up, 0.7, material("water")));
+
+    // CSG: muro de ladrillo con un arco de medio punto tallado (hueco recto + medio cilindro)
+    let wall = Cube::from_bounds(Vector3::new(-1.5, -0.5, -3.6), Vector3::new(1.5, 1.5, -3.2), material("bricks"));
+    let depth = Vector3::new(0.0, 0.0, 1.0);
+    let doorway = Cube::from_bounds(Vector3::new(-0.4, -0.6, -3.7), Vector3::new(0.4, 0.6, -3.1), material("stone"));
+    let arch = Cylinder::new(Vector3::new(0.0, 0.6, -3.7), depth, 0.4, 0.6, material("stone"));
+    scene.add(Csg::difference(wall, Csg::union(doorway, arch)).with_cut_material(material("stone")));
+    // Bloque redondeado: intersección de un cubo y una esfera (cápsula de longitud cero)
+    let center = Vector3::new(1.0, -0.1, 1.0);
+    let block = Cube::new(center, 0.8, material("diamond_ore"));
+    scene.add(Csg::intersection(block, Capsule::new(center, center, 0.55, material("diamond_ore"))));
+
+    // SDF: slime gelatinoso de tres esferas fundidas y una fila de losas redondeadas repetidas
+    let slime = Sdf::sphere(Vector3::new(3.2, -0.22, 0.6), 0.28)
+        .smooth_union(Sdf::sphere(Vector3::new(3.5, -0.3, 0.3), 0.2), 0.15)
+        .smooth_union(Sdf::sphere(Vector3::new(3.45, -0.05, 0.55), 0.14), 0.12);
+    let slime_material = Material { diffuse: Vector3::new(0.35, 0.85, 0.3), texture: None, roughness: 0.3, ..material("water") };
+    scene.add(SdfObject::new(slime, slime_material));
+    let slab = Sdf::rounded_cuboid(Vector3::new(0.0, -0.45, 3.5), Vector3::new(0.6, 0.1, 0.6), 0.05);
+    let stepping_stones = slab.repeat_limited(Vector3::new(0.9, 0.0, 0.0), Vector3::new(2.0, 0.0, 0.0));
+    scene.add(SdfObject::new(stepping_stones, material("stone")));
+
+    // Transformaciones: caja girada e inclinada, cartel ladeado y un huevo (esfera estirada)
+    let tilted_crate = Transform::identity()
+        .rotated(up, 35f32.to_radians())
+        .rotated(Vector3::new(1.0, 0.0, 0.0), 10f32.to_radians())
+        .translated(Vector3::new(-2.2, -0.15, 3.1));
+    scene.add(Transformed::new(Cube::new(Vector3::zero(), 0.6, material("wood_planks")), tilted_crate));
+    let sign = Quad::new(Vector3::new(-0.4, 0.0, 0.0), Vector3::new(0.8, 0.0, 0.0), Vector3::new(0.0, 0.5, 0.0), material("oak"));
+    let leaning = Transform::rotation(Vector3::new(0.0, 0.0, 1.0), -12f32.to_radians()).translated(Vector3::new(4.3, 0.3, -0.3));
+    scene.add(Transformed::new(sign, leaning));
+    let egg = Capsule::new(Vector3::zero(), Vector3::zero(), 1.0, material("white"));
+    let stretch = Transform::scale(Vector3::new(0.18, 0.26, 0.18)).translated(Vector3::new(4.2, -0.24, 1.4));
+    scene.add(Transformed::new(egg, stretch));
+
+    // Desenfoque de movimiento: un bloque de tierra que cae girando durante el fotograma
+    let dirt = Cube::new(Vector3::zero(), 0.3, material("dirt"));
+    let fall = MotionTransform::between(
+        Transform::translation(Vector3::new(-0.8, 1.0, 0.0)),
+        Transform::rotation(Vector3::new(1.0, 0.0, 1.0).normalized(), 0.4).translated(Vector3::new(-0.8, 0.5, 0.0)),
+    );
+    scene.add(Moving::new(dirt, fall));
+
+    scene.add_light(Light::new(Vector3::new(0.5, 5.0, 5.0), Vector3::new(1.0, 1.0, 1.0), 0.8));
+    scene
+}
+
+/// Escena de instancias: un bosque sobre un prado en el que todos los bloques de pasto y todos los
+/// árboles comparten una única geometría cada uno. Se construye como grafo de escena: el grupo
+/// "forest" (con la estatua "statue" en el centro) se puede ocultar o girar mientras se ejecuta.
+fn forest_scene(library: &MaterialLibrary) -> SceneGraph {
+    let cube = |center: Vector3, size: f32, name: &str| library.cube(center, size, name).unwrap_or_else(|e| panic!("{}", e));
+    let up = Vector3::new(0.0, 1.0, 0.0);
+
+    let grass: Arc<dyn RayIntersect> = Arc::new(cube(Vector3::zero(), 1.0, "grass"));
+    let mut tree_parts: Vec<Box<dyn RayIntersect>> = Vec::new();
+    for y in 1..=3 {
+        tree_parts.push(Box::new(cube(Vector3::new(0.0, y as f32, 0.0), 1.0, "oak")));
+    }
+    for x in -1..=1 {
+        for z in -1..=1 {
+            for y in 3..=4 {
+                if (x != 0 || z != 0) && !(y == 4 && x != 0 && z != 0) {
+                    tree_parts.push(Box::new(cube(Vector3::new(x as f32, y as f32, z as f32), 1.0, "leaves")));
+                }
+            }
+        }
+    }
+    tree_parts.push(Box::new(cube(Vector3::new(0.0, 5.0, 0.0), 1.0, "leaves")));
+    let tree: Arc<dyn RayIntersect> = Arc::new(Blas::new("tree", tree_parts));
+
+    let mut graph = SceneGraph::new();
+    let root = graph.root();
+    let meadow = graph.add_group(root, "meadow", Transform::identity());
+    for x in -12..12 {
+        for z in -12..12 {
+            let position = Vector3::new(x as f32, 0.0, z as f32);
+            graph.add_instance(meadow, &format!("grass {} {}", x, z), grass.clone(), Transform::translation(position));
+        }
+    }
+
+    // Árboles en una rejilla con giros de 90° y tamaños que varían de forma determinista
+    let forest = graph.add_group(root, "forest", Transform::identity());
+    for i in -2..=2i32 {
+        for j in -2..=2i32 {
+            let variation = ((i * 7 + j * 13).rem_euclid(5)) as f32;
+            let scale = 0.6 + 0.1 * variation;
+            let placement = Transform::scale(Vector3::new(scale, scale, scale))
+                .rotated(up, variation * PI / 2.0)
+                .translated(Vector3::new(i as f32 * 4.5 + variation * 0.3, 0.0, j as f32 * 4.5 - variation * 0.2));
+            let name = if i == 0 && j == 0 { "statue".to_string() } else { format!("tree {} {}", i, j) };
+            graph.add_instance(forest, &name, tree.clone(), placement);
+        }
+    }
+    // La estatua es la misma geometría con otro material
+    if let Some(statue) = graph.find("statue") {
+        let gold = Material::from_pbr(PbrMaterial::new(Vector3::new(1.0, 0.78, 0.34), 1.0, 0.25));
+        graph.node_mut(statue).material = Some(Arc::new(gold));
+    }
+
+    graph.add_light(root, "sun", Light::new(Vector3::new(6.0, 12.0, 8.0), Vector3::new(1.0, 1.0, 1.0), 1.0));
+    graph
+}
+
+/// Animación de prueba del bosque: un vuelo de cámara alrededor de la estatua mientras gira y
+/// brilla, y el sol cruza el cielo del amanecer al atardecer.
+fn forest_animation() -> Animation {
+    use Interpolation::{CatmullRom, EaseInOut, Linear};
+    let mut animation = Animation::new();
+
+    let mut eye = Track::new();
+    let path = [(0.0, 10.0, 18.0), (11.0, 5.0, 9.0), (9.0, 3.0, -7.0), (-7.0, 4.0, -9.0), (-12.0, 7.0, 8.0), (0.0, 10.0, 18.0)];
+    for (i, (x, y, z)) in path.into_iter().enumerate() {
+        eye = eye.key(i as f32 * 2.0, Vector3::new(x, y, z), CatmullRom);
+    }
+    animation.camera.eye = Some(eye);
+    animation.camera.center = Some(
+        Track::new()
+            .key(0.0, Vector3::new(0.0, 1.0, 0.0), EaseInOut)
+            .key(5.0, Vector3::new(0.0, 3.0, 0.0), EaseInOut)
+            .key(10.0, Vector3::new(0.0, 1.0, 0.0), EaseInOut),
+    );
+
+    // La estatua está en el origen con escala 0.6 y da una vuelta completa
+    let scale = Vector3::new(0.6, 0.6, 0.6);
+    animation.nodes.push(NodeAnimation {
+        node: "statue".to_string(),
+        scale: Some(Track::new().key(0.0, scale, Linear)),
+        rotation: Some(Track::new().key(0.0, Vector3::zero(), Linear).key(10.0, Vector3::new(0.0, 2.0 * PI, 0.0), Linear)),
+        ..NodeAnimation::default()
+    });
+    animation.materials.push(MaterialAnimation {
+        node: "statue".to_string(),
+        emission: Some(
+            Track::new()
+                .key(0.0, Vector3::zero(), EaseInOut)
+                .key(5.0, Vector3::new(0.6, 0.45, 0.15), EaseInOut)
+                .key(10.0, Vector3::zero(), EaseInOut),
+        ),
+        ..MaterialAnimation::default()
+    });
+
+    // Ciclo de día: el sol sale por el este anaranjado, blanco a mediodía y se pone por el oeste
+    animation.lights.push(LightAnimation {
+        node: "sun".to_string(),
+        position: Some(
+            Track::new()
+                .key(0.0, Vector3::new(-14.0, 1.0, 4.0), CatmullRom)
+                .key(5.0, Vector3::new(0.0, 14.0, 4.0), CatmullRom)
+                .key(10.0, Vector3::new(14.0, 1.0, 4.0), CatmullRom),
+        ),
+        color: Some(
+            Track::new()
+                .key(0.0, Vector3::new(1.0, 0.55, 0.3), EaseInOut)
+                .key(5.0, Vector3::new(1.0, 1.0, 1.0), EaseInOut)
+                .key(10.0, Vector3::new(1.0, 0.45, 0.25), EaseInOut),
+        ),
+        intensity: Some(Track::new().key(0.0, 0.3, EaseInOut).key(5.0, 1.0, EaseInOut).key(10.0, 0.3, EaseInOut)),
+    });
+    animation
+}
+
+/// Vuelta completa de la cámara alrededor de su centro en `duration` segundos, a la misma
+/// distancia y altura. Es la animación de las escenas que no traen una propia.
+fn turntable_animation(camera: &Camera, duration: f32) -> Animation {
+    let relative = camera.eye - camera.center;
+    let radius = (relative.x * relative.x + relative.z * relative.z).sqrt();
+    let start = relative.z.atan2(relative.x);
+    let steps = 8;
+    let mut eye = Track::new();
+    for i in 0..=steps {
+        let angle = start + 2.0 * PI * i as f32 / steps as f32;
+        let offset = Vector3::new(radius * angle.cos(), relative.y, radius * angle.sin());
+        eye = eye.key(duration * i as f32 / steps as f32, camera.center + offset, Interpolation::CatmullRom);
+    }
+    let mut animation = Animation::new();
+    animation.camera.eye = Some(eye);
+    animation
+}
+
+/// Deja la cámara y la escena como están en `time`. Con el obturador abierto también guarda su
+/// movimiento durante el fotograma, de `frame_duration` segundos. Las escenas que no son un grafo
+/// solo animan la cámara.
+fn apply_animation(
+    animation: &Animation,
+    time: f32,
+    frame_duration: f32,
+    settings: &RenderSettings,
+    camera: &mut Camera,
+    graph: Option<&mut SceneGraph>,
+    scene: &mut Scene,
+) {
+    let motion_blur = settings.shutter.is_open();
+    match graph {
+        Some(graph) => {
+            if motion_blur {
+                animation.apply_motion(time, frame_duration, camera, graph);
+            } else {
+                animation.apply(time, camera, graph);
+            }
+            *scene = graph.flatten();
+        }
+        None if motion_blur => animation.apply_camera_motion(time, frame_duration, camera),
+        None => animation.apply_camera(time, camera),
+    }
+}
+
+fn main() {
+    let window_width = 800;
+    let window_height = 600;
     
-    let mut camera = Camera::new(Vector3::new(0.0, 8.0, 10.0), Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+    // Materiales compartidos; `--materials ruta.mat` añade o sustituye materiales
+    let args: Vec<String> = std::env::args().collect();
+    // Si falta la biblioteca se sigue con una vacía: las escenas de bloques avisarán del primer
+    // material que no encuentren, pero los modelos importados no la necesitan
+    let mut library = MaterialLibrary::load("assets/materials.mat").unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        MaterialLibrary::new()
+    });
+    if let Some(index) = args.iter().position(|a| a == "--materials")
+        && let Some(path) = args.get(index + 1)
+        && let Err(e) = library.load_file(path)
+    {
+        eprintln!("{}", e);
+    }
+
+    // Modelo OBJ opcional: `--obj ruta/modelo.obj` (sus MTL se buscan junto al archivo)
+    let model: Option<ObjModel> = args
+        .iter()
+        .position(|a| a == "--obj")
+        .and_then(|index| args.get(index + 1))
+        .and_then(|path| obj::load_obj(path).map_err(|e| eprintln!("{}", e)).ok());
+    // Escena glTF 2.0 opcional: `--gltf ruta/escena.gltf` o `.glb`
+    let gltf_scene: Option<GltfScene> = args
+        .iter()
+        .position(|a| a == "--gltf")
+        .and_then(|index| args.get(index + 1))
+        .and_then(|path| gltf::load_gltf(path).map_err(|e| eprintln!("{}", e)).ok());
+
+    let mut texture_manager = TextureManager::new();
+    let model_textures = model.iter().flat_map(|model| model.materials.texture_paths());
+    let gltf_textures = gltf_scene.iter().flat_map(|gltf| gltf.texture_paths.clone());
+    for path in library.texture_paths().into_iter().chain(model_textures).chain(gltf_textures) {
+        // Una imagen que no se puede leer se descarta: esa superficie se ve sin textura
+        if let Err(e) = texture_manager.load_texture(&path) {
+            eprintln!("{}; se ignora", e);
+        }
+    }
+    for image in gltf_scene.iter().flat_map(|gltf| &gltf.embedded_images) {
+        if let Err(e) = texture_manager.load_texture_from_memory(&image.key, &image.file_type, &image.bytes) {
+            eprintln!("{}; se ignora", e);
+        }
+    }
+
+    let skybox = SkyboxTextures {
+        front: "assets/skybox/front.png".to_string(),
+        back: "assets/skybox/back.png".to_string(),
+        left: "assets/skybox/left.png".to_string(),
+        right: "assets/skybox/right.png".to_string(),
+        top: "assets/skybox/top.png".to_string(),
+        bottom: "assets/skybox/bottom.png".to_string(),
+    };
+
+    if let Err(e) = texture_manager.load_skybox(skybox) {
+        eprintln!("{}; se usa el cielo por defecto", e);
+    }
+
+    // HDRI opcional: `cargo run --release -- --hdri ruta/al/cielo.hdr`
+    if let Some(index) = args.iter().position(|a| a == "--hdri")
+        && let Some(path) = args.get(index + 1)
+        && let Err(e) = texture_manager.load_environment(path, 0.0, 1.0)
+    {
+        eprintln!("{}", e);
+    }
+    texture_manager.update_ibl();
+    let mut settings = RenderSettings::default();
+    
+    // Escena: `--scene colored-lights`, `--scene primitives` o `--scene forest` cargan las escenas de prueba
+    // Las escenas jerárquicas se guardan como grafo para poder moverlas u ocultarlas en marcha
+    let mut graph: Option<SceneGraph> = None;
+    let scene_name = args.iter().position(|a| a == "--scene").and_then(|i| args.get(i + 1)).map(|name| name.as_str());
+    let mut scene = match scene_name {
+        Some("colored-lights") => colored_lights_scene(&library),
+        Some("primitives") => primitives_scene(&library),
+        Some("forest") => {
+            graph = Some(forest_scene(&library));
+            Scene::new()
+        }
+        // Sin biblioteca, los modelos importados se muestran solos en lugar de sobre el mundo de bloques
+        None if library.names().is_empty() && (model.is_some() || gltf_scene.is_some()) => Scene::new(),
+        _ => minecraft_scene(&library),
+    };
+    // Las mallas importadas se añaden tal cual (o como nodos del grafo, con su nombre);
+    // sus triángulos emisivos se muestrean como luces de área
+    let mut add_mesh = |scene: &mut Scene, mesh: mesh::Mesh| match graph.as_mut() {
+        Some(graph) => {
+            let root = graph.root();
+            let name = mesh.name.clone();
+            graph.add_object(root, &name, mesh, Transform::identity());
+        }
+        None => scene.add(mesh),
+    };
+    for mesh in model.map(|model| model.meshes).unwrap_or_default() {
+        add_mesh(&mut scene, mesh);
+    }
+    // La escena glTF aporta mallas, luces y, si tiene, la cámara inicial
+    let gltf_camera = gltf_scene.as_ref().and_then(GltfScene::default_camera);
+    let gltf_ev100 = gltf_scene.as_ref().and_then(GltfScene::suggested_ev100);
+    if let Some(gltf) = gltf_scene {
+        for mesh in gltf.meshes {
+            add_mesh(&mut scene, mesh);
+        }
+        for light in gltf.lights {
+            match graph.as_mut() {
+                Some(graph) => {
+                    let root = graph.root();
+                    graph.add_light(root, "light", light);
+                }
+                None => scene.add_light(light),
+            }
+        }
+    }
+    match &graph {
+        Some(graph) => scene = graph.flatten(),
+        None => scene.build_tlas(),
+    }
+    
+    let mut camera = gltf_camera.unwrap_or_else(|| Camera::new(Vector3::new(0.0, 8.0, 10.0), Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)));
     let rotation_speed = PI / 100.0;
     let zoom_speed = 0.1;
     let vertical_speed = 0.1;
-    let light = Light::new(Vector3::new(0.5, 5.0, 5.0), Vector3::new(1.0, 1.0, 1.0), 1.2);
+    // Modo vuelo: velocidades por segundo para que no dependan de los FPS
+    let mut camera_mode = CameraMode::Orbit;
+    let fly_speed = 4.0; // unidades por segundo
+    let look_speed = PI / 2.0; // radianes por segundo con las flechas
+    let roll_speed = PI / 2.0; // radianes por segundo
+    let mouse_sensitivity = 0.003; // radianes por píxel
+
+    // Proyección: `--projection perspective|orthographic|isometric|fisheye|equirectangular|cubemap`,
+    // `--fov 90` (grados, perspectiva y ojo de pez) y `--ortho-height 12` (alto visible en ortográfica)
+    let number_arg = |name: &str| args.iter().position(|a| a == name).and_then(|i| args.get(i + 1)).and_then(|v| v.parse::<f32>().ok());
+    let fov_arg = number_arg("--fov").map(f32::to_radians);
+    if let Some(fov) = fov_arg {
+        camera.fov = fov;
+    }
+    let ortho_height = number_arg("--ortho-height").unwrap_or_else(|| camera.view_height());
+    match args.iter().position(|a| a == "--projection").and_then(|i| args.get(i + 1)).map(|name| name.as_str()) {
+        Some("orthographic") => camera.projection = Projection::Orthographic { height: ortho_height },
+        Some("isometric") => camera.set_isometric(ortho_height),
+        Some("fisheye") => camera.projection = Projection::Fisheye { fov: fov_arg.unwrap_or(PI) },
+        Some("equirectangular") => camera.projection = Projection::Equirectangular,
+        Some("cubemap") => camera.projection = Projection::CubeMap,
+        Some("perspective") | None => {}
+        Some(other) => eprintln!("Proyección desconocida: {}", other),
+    }
+
+    // Animación de la escena (el bosque trae la suya; las demás, una vuelta de cámara)
+    let animation = match scene_name {
+        Some("forest") => forest_animation(),
+        _ => turntable_animation(&camera, 8.0),
+    };
+
+    // Exposición en unidades físicas: `--ev100 15` (pleno sol; 7 en un interior). Las luces de glTF
+    // vienen en candelas y lux, así que sin la opción se calcula un EV100 para ellas
+    if let Some(ev100) = number_arg("--ev100").or(gltf_ev100) {
+        settings.exposure = exposure_from_ev100(ev100);
+    }
+
+    // Desenfoque de movimiento: `--shutter 0.5` abre el obturador la mitad de cada fotograma
+    // y `--time-samples 16` fija los instantes por píxel
+    if let Some(shutter) = number_arg("--shutter") {
+        settings.shutter = Shutter::new(0.0, shutter.clamp(0.0, 1.0));
+    }
+    if let Some(samples) = number_arg("--time-samples") {
+        settings.time_samples = samples.max(1.0) as u32;
+    }
+    // Profundidad de campo: `--aperture 0.1` (radio) o `--f-stop 2.8`, `--focus 6` (distancia),
+    // `--aperture-blades 6` (bokeh poligonal) y `--autofocus` (enfoca el centro en cada fotograma)
+    if let Some(radius) = number_arg("--aperture") {
+        camera.aperture_radius = radius.max(0.0);
+    }
+    if let Some(f_stop) = number_arg("--f-stop") {
+        camera.set_f_stop(f_stop);
+    }
+    if let Some(distance) = number_arg("--focus") {
+        camera.focus_distance = distance;
+    }
+    if let Some(blades) = number_arg("--aperture-blades") {
+        camera.aperture_shape = ApertureShape::Polygon { blades: blades.max(3.0) as u32, rotation: 0.0 };
+    }
+    if let Some(samples) = number_arg("--lens-samples") {
+        settings.lens_samples = samples.max(1.0) as u32;
+    }
+    let auto_focus = args.iter().any(|a| a == "--autofocus");
+
+    // Secuencia de imágenes: `--render-frames INICIO FIN [--fps 24] [--output frames/frame_####.png]`.
+    // Se renderiza sin abrir ninguna ventana y el programa termina
+    if let Some(index) = args.iter().position(|a| a == "--render-frames") {
+        let frame_arg = |offset: usize| args.get(index + offset).and_then(|value| value.parse::<u32>().ok());
+        let fps = number_arg("--fps").unwrap_or(24.0);
+        let (first, last) = match (frame_arg(1), frame_arg(2)) {
+            (Some(first), Some(last)) => (first, last),
+            _ => (0, (animation.duration() * fps).ceil() as u32),
+        };
+        let pattern = args.iter().position(|a| a == "--output").and_then(|i| args.get(i + 1)).map(|p| p.as_str()).unwrap_or("frames/frame_####.png");
+        if let Some(parent) = std::path::Path::new(pattern).parent()
+            && let Err(e) = std::fs::create_dir_all(parent)
+        {
+            eprintln!("No se pudo crear {}: {}", parent.display(), e);
+            return;
+        }
+        // `--size 2048x1024` cambia la resolución de las imágenes (útil para panoramas 2:1)
+        let (width, height) = args
+            .iter()
+            .position(|a| a == "--size")
+            .and_then(|i| args.get(i + 1))
+            .and_then(|size| size.split_once('x'))
+            .and_then(|(w, h)| Some((w.parse::<i32>().ok()?, h.parse::<i32>().ok()?)))
+            .unwrap_or((window_width, window_height));
+        let mut framebuffer = Framebuffer::new(width, height);
+        for frame in first..=last {
+            let start_time = std::time::Instant::now();
+            apply_animation(&animation, frame as f32 / fps, 1.0 / fps, &settings, &mut camera, graph.as_mut(), &mut scene);
+            if auto_focus {
+                camera.auto_focus(&scene.objects, settings.shutter.open);
+            }
+            let pixels = render(width, height, &scene.objects, &camera, &scene.lights, &scene.area_lights, &scene.voxels, &texture_manager, &settings);
+            framebuffer.set_pixels(&pixels);
+            let path = animation::frame_path(pattern, frame);
+            framebuffer.export(&path);
+            println!("{} ({}/{}, {:.2} s)", path, frame - first + 1, last - first + 1, start_time.elapsed().as_secs_f32());
+        }
+        return;
+    }
+
+    // La ventana (y su contexto OpenGL) solo se crea para el modo interactivo; todo lo anterior,
+    // incluida la carga de texturas, usa solo la CPU
+    let (mut window, raylib_thread) = raylib::init()
+        .size(window_width, window_height)
+        .title("Raytracer Minecraft")
+        .log_level(TraceLogLevel::LOG_WARNING)
+        .build();
+    // P reproduce o pausa la animación en la ventana
+    let mut playing = false;
+    let mut animation_started = false;
+    let mut animation_time = 0.0;
+    let preview_frame_duration = 1.0 / 24.0;
+    // Con desenfoque de movimiento la ventana acumula un instante por píxel en cada fotograma
+    // mientras no cambie nada, y la imagen se va limpiando
+    let mut accumulation: Vec<Vector3> = Vec::new();
+    let mut accumulated_frames = 0;
 
     let mut texture = window.load_texture_from_image(&raylib_thread, &Image::gen_image_color(window_width, window_height, Color::BLACK)).expect("No se pudo cargar la textura");
 
     while !window.window_should_close() {
         let start_time = std::time::Instant::now();
+        let mut scene_changed = false;
         
-        if window.is_key_down(KeyboardKey::KEY_LEFT) { camera.orbit(rotation_speed, 0.0); }
-        if window.is_key_down(KeyboardKey::KEY_RIGHT) { camera.orbit(-rotation_speed, 0.0); }
-        if window.is_key_down(KeyboardKey::KEY_UP) { camera.orbit(0.0, -rotation_speed); }
-        if window.is_key_down(KeyboardKey::KEY_DOWN) { camera.orbit(0.0, rotation_speed); }
-        if window.is_key_down(KeyboardKey::KEY_D) { camera.zoom(zoom_speed); }
-        if window.is_key_down(KeyboardKey::KEY_A) { camera.zoom(-zoom_speed); }
-        if window.is_key_down(KeyboardKey::KEY_W) { camera.eye.y += vertical_speed; camera.center.y += vertical_speed; camera.update_basis(); }
-        if window.is_key_down(KeyboardKey::KEY_S) { camera.eye.y -= vertical_speed; camera.center.y -= vertical_speed; camera.update_basis(); }
+        // C cambia entre orbitar y volar; al volar el ratón queda capturado para mirar
+        if window.is_key_pressed(KeyboardKey::KEY_C) {
+            camera_mode = match camera_mode {
+                CameraMode::Orbit => {
+                    window.disable_cursor();
+                    CameraMode::Fly
+                }
+                CameraMode::Fly => {
+                    window.enable_cursor();
+                    CameraMode::Orbit
+                }
+            };
+        }
+        match camera_mode {
+            CameraMode::Orbit => {
+                if window.is_key_down(KeyboardKey::KEY_LEFT) { camera.orbit(rotation_speed, 0.0); }
+                if window.is_key_down(KeyboardKey::KEY_RIGHT) { camera.orbit(-rotation_speed, 0.0); }
+                if window.is_key_down(KeyboardKey::KEY_UP) { camera.orbit(0.0, -rotation_speed); }
+                if window.is_key_down(KeyboardKey::KEY_DOWN) { camera.orbit(0.0, rotation_speed); }
+                if window.is_key_down(KeyboardKey::KEY_D) { camera.zoom(zoom_speed); }
+                if window.is_key_down(KeyboardKey::KEY_A) { camera.zoom(-zoom_speed); }
+                if window.is_key_down(KeyboardKey::KEY_W) { camera.eye.y += vertical_speed; camera.center.y += vertical_speed; camera.update_basis(); }
+                if window.is_key_down(KeyboardKey::KEY_S) { camera.eye.y -= vertical_speed; camera.center.y -= vertical_speed; camera.update_basis(); }
+            }
+            CameraMode::Fly => {
+                let dt = window.get_frame_time();
+                let axis = |positive: KeyboardKey, negative: KeyboardKey| {
+                    window.is_key_down(positive) as i32 as f32 - window.is_key_down(negative) as i32 as f32
+                };
+                // WASD según la vista, Espacio/Ctrl en vertical; Mayús acelera y Alt frena
+                let movement = Vector3::new(
+                    axis(KeyboardKey::KEY_D, KeyboardKey::KEY_A),
+                    axis(KeyboardKey::KEY_SPACE, KeyboardKey::KEY_LEFT_CONTROL),
+                    axis(KeyboardKey::KEY_W, KeyboardKey::KEY_S),
+                );
+                if movement.length() > 0.0 {
+                    let boost = if window.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) {
+                        4.0
+                    } else if window.is_key_down(KeyboardKey::KEY_LEFT_ALT) {
+                        0.25
+                    } else {
+                        1.0
+                    };
+                    let step = movement.normalized() * (fly_speed * boost * dt);
+                    camera.fly(step.x, step.y, step.z);
+                }
+                // Ratón o flechas para mirar, Q/E para inclinar y R para enderezar
+                let mouse = window.get_mouse_delta();
+                let yaw = -mouse.x * mouse_sensitivity + axis(KeyboardKey::KEY_LEFT, KeyboardKey::KEY_RIGHT) * look_speed * dt;
+                let pitch = -mouse.y * mouse_sensitivity + axis(KeyboardKey::KEY_UP, KeyboardKey::KEY_DOWN) * look_speed * dt;
+                if yaw != 0.0 || pitch != 0.0 {
+                    camera.look(yaw, pitch);
+                }
+                let roll = axis(KeyboardKey::KEY_E, KeyboardKey::KEY_Q);
+                if roll != 0.0 {
+                    camera.roll(roll * roll_speed * dt);
+                }
+                if window.is_key_pressed(KeyboardKey::KEY_R) {
+                    camera.reset_roll();
+                }
+            }
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_I) {
+            settings.ibl_mode = match settings.ibl_mode {
+                IblMode::Off => IblMode::Precomputed,
+                IblMode::Precomputed => IblMode::Sampled { samples: 4 },
+                IblMode::Sampled { .. } => IblMode::Off,
+            };
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_G) {
+            settings.glossy_samples = match settings.glossy_samples { 1 => 4, 4 => 16, _ => 1 };
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_K) {
+            settings.ao_mode = match settings.ao_mode {
+                AoMode::Off => AoMode::VoxelNeighbours,
+                AoMode::VoxelNeighbours => AoMode::Hemisphere { samples: 8, max_distance: 1.0 },
+                AoMode::Hemisphere { .. } => AoMode::Off,
+            };
+        }
+        // Grafo de escena: H muestra u oculta el bosque y J/L giran la estatua
+        if let Some(graph) = graph.as_mut() {
+            let mut changed = false;
+            if window.is_key_pressed(KeyboardKey::KEY_H) && let Some(forest) = graph.find("forest") {
+                let visible = graph.node(forest).visible;
+                graph.set_visible(forest, !visible);
+                changed = true;
+            }
+            for (key, angle) in [(KeyboardKey::KEY_J, rotation_speed), (KeyboardKey::KEY_L, -rotation_speed)] {
+                if window.is_key_down(key) && let Some(statue) = graph.find("statue") {
+                    let turned = Transform::rotation(Vector3::new(0.0, 1.0, 0.0), angle).then(&graph.node(statue).transform);
+                    graph.set_transform(statue, turned);
+                    changed = true;
+                }
+            }
+            if changed {
+                scene = graph.flatten();
+                scene_changed = true;
+            }
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_P) {
+            playing = !playing;
+            animation_started = true;
+        }
+        // B abre o cierra el obturador (medio fotograma)
+        let shutter_toggled = window.is_key_pressed(KeyboardKey::KEY_B);
+        if shutter_toggled {
+            settings.shutter = if settings.shutter.is_open() { Shutter::default() } else { Shutter::new(0.0, 0.5) };
+        }
+        if playing {
+            // Se avanza con el tiempo real del fotograma anterior y se repite al acabar
+            animation_time = (animation_time + window.get_frame_time()) % animation.duration().max(1e-3);
+        }
+        // Profundidad de campo: F enfoca el centro, Z/X cierran/abren el diafragma medio paso
+        // y N cambia la forma del diafragma (circular → hexagonal → pentagonal)
+        if window.is_key_pressed(KeyboardKey::KEY_F) { camera.auto_focus(&scene.objects, settings.shutter.open); }
+        if window.is_key_pressed(KeyboardKey::KEY_X) {
+            camera.aperture_radius = if camera.aperture_radius > 0.0 { camera.aperture_radius * 2.0_f32.sqrt() } else { 0.02 };
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_Z) {
+            camera.aperture_radius /= 2.0_f32.sqrt();
+            if camera.aperture_radius < 0.02 { camera.aperture_radius = 0.0; }
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_N) {
+            camera.aperture_shape = match camera.aperture_shape {
+                ApertureShape::Circular => ApertureShape::Polygon { blades: 6, rotation: 0.0 },
+                ApertureShape::Polygon { blades: 6, .. } => ApertureShape::Polygon { blades: 5, rotation: PI / 2.0 },
+                ApertureShape::Polygon { .. } => ApertureShape::Circular,
+            };
+        }
+        // Proyección: 1 perspectiva, 2 ortográfica, 3 ojo de pez, 4 panorama 360°, 5 vista isométrica,
+        // 6 las seis caras de un cubo;
+        // la rueda del ratón cambia el campo de visión (o el alto visible en ortográfica)
+        if window.is_key_pressed(KeyboardKey::KEY_ONE) { camera.projection = Projection::Perspective; }
+        if window.is_key_pressed(KeyboardKey::KEY_TWO) { camera.projection = Projection::Orthographic { height: camera.view_height() }; }
+        if window.is_key_pressed(KeyboardKey::KEY_THREE) { camera.projection = Projection::Fisheye { fov: PI }; }
+        if window.is_key_pressed(KeyboardKey::KEY_FOUR) { camera.projection = Projection::Equirectangular; }
+        if window.is_key_pressed(KeyboardKey::KEY_FIVE) {
+            let height = match camera.projection {
+                Projection::Orthographic { height } => height,
+                _ => camera.view_height(),
+            };
+            camera.set_isometric(height);
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_SIX) { camera.projection = Projection::CubeMap; }
+        let wheel = window.get_mouse_wheel_move();
+        if wheel != 0.0 {
+            let zoom = 0.9_f32.powf(wheel);
+            match &mut camera.projection {
+                Projection::Perspective => camera.fov = (camera.fov * zoom).clamp(10f32.to_radians(), 150f32.to_radians()),
+                Projection::Orthographic { height } => *height *= zoom,
+                Projection::Fisheye { fov } => *fov = (*fov * zoom).clamp(PI / 2.0, 2.0 * PI),
+                Projection::Equirectangular | Projection::CubeMap => {}
+            }
+            camera.update_basis();
+        }
+        if playing || (shutter_toggled && animation_started) {
+            apply_animation(&animation, animation_time, preview_frame_duration, &settings, &mut camera, graph.as_mut(), &mut scene);
+            scene_changed = true;
+        }
+        // Exportar la oclusión ambiental como imagen aparte
+        if window.is_key_pressed(KeyboardKey::KEY_O) {
+            let pixels = render_ambient_occlusion(window_width, window_height, &scene.objects, &camera, &scene.voxels, &settings);
+            let mut framebuffer = Framebuffer::new(window_width, window_height);
+            framebuffer.set_pixels(&pixels);
+            framebuffer.export("ao.png");
+        }
+        // Exposición en pasos de 1/3 de diafragma
+        if window.is_key_pressed(KeyboardKey::KEY_COMMA) { settings.exposure /= 2.0_f32.powf(1.0 / 3.0); }
+        if window.is_key_pressed(KeyboardKey::KEY_PERIOD) { settings.exposure *= 2.0_f32.powf(1.0 / 3.0); }
+        if window.is_key_down(KeyboardKey::KEY_LEFT_BRACKET) { texture_manager.rotate_environment(-rotation_speed); }
+        if window.is_key_down(KeyboardKey::KEY_RIGHT_BRACKET) { texture_manager.rotate_environment(rotation_speed); }
+        if window.is_key_down(KeyboardKey::KEY_MINUS) { texture_manager.scale_environment_intensity(0.98); }
+        if window.is_key_down(KeyboardKey::KEY_EQUAL) { texture_manager.scale_environment_intensity(1.02); }
         
-        let pixel_data = render(window_width, window_height, &objects, &camera, &light, &emissive_cubes, &texture_manager);
+        let held_keys = [KeyboardKey::KEY_J, KeyboardKey::KEY_L, KeyboardKey::KEY_LEFT_BRACKET, KeyboardKey::KEY_RIGHT_BRACKET, KeyboardKey::KEY_MINUS, KeyboardKey::KEY_EQUAL];
+        let input = window.get_key_pressed().is_some() || held_keys.iter().any(|key| window.is_key_down(*key));
+        let camera_changed = camera.is_changed();
+        // El desenfoque de movimiento y la profundidad de campo se acumulan en varios fotogramas
+        let progressive = settings.shutter.is_open() || camera.aperture_radius > 0.0;
+        let pixel_data = if progressive {
+            if camera_changed || scene_changed || input || accumulation.len() != (window_width * window_height) as usize {
+                accumulation = vec![Vector3::zero(); (window_width * window_height) as usize];
+                accumulated_frames = 0;
+            }
+            let single_sample = RenderSettings { time_samples: 1, lens_samples: 1, ..settings };
+            let frame = render_radiance(window_width, window_height, &scene.objects, &camera, &scene.lights, &scene.area_lights, &scene.voxels, &texture_manager, &single_sample);
+            accumulated_frames += 1;
+            // Se acumula la radiancia lineal y se convierte a color una sola vez al mostrarla
+            for (sum, radiance) in accumulation.iter_mut().zip(&frame) {
+                *sum += *radiance;
+            }
+            accumulation
+                .iter()
+                .map(|sum| vector3_to_color(*sum / accumulated_frames as f32 * settings.exposure))
+                .collect()
+        } else {
+            render(window_width, window_height, &scene.objects, &camera, &scene.lights, &scene.area_lights, &scene.voxels, &texture_manager, &settings)
+        };
         
         let pixel_bytes: &[u8] = unsafe {
             std::slice::from_raw_parts(pixel_data.as_ptr() as *const u8, pixel_data.len() * size_of::<Color>())
@@ -397,6 +1367,20 @@ fn main() {
             let elapsed = start_time.elapsed().as_millis() as f32 / 1000.0;
             let fps = if elapsed > 0.0 { (1.0 / elapsed).round() as i32 } else { 0 };
             d.draw_text(&format!("FPS: {}", fps), 10, 10, 20, Color::WHITE);
+            d.draw_text(&format!("IBL: {:?}", settings.ibl_mode), 10, 35, 20, Color::WHITE);
+            d.draw_text(&format!("Muestras brillantes: {}", settings.glossy_samples), 10, 60, 20, Color::WHITE);
+            d.draw_text(&format!("AO: {:?}", settings.ao_mode), 10, 85, 20, Color::WHITE);
+            d.draw_text(&format!("Cámara: {:?}, proyección: {:?}", camera_mode, camera.projection), 10, 110, 20, Color::WHITE);
+            if playing {
+                d.draw_text(&format!("Animación: {:.2} s", animation_time), 10, 135, 20, Color::WHITE);
+            }
+            if camera.aperture_radius > 0.0 {
+                let aperture = format!("Apertura: {:.3} (f/{:.1}), enfoque a {:.2}", camera.aperture_radius, camera.f_stop(), camera.focus_distance);
+                d.draw_text(&aperture, 10, 160, 20, Color::WHITE);
+            }
+            if progressive {
+                d.draw_text(&format!("Pasadas acumuladas: {}", accumulated_frames), 10, 185, 20, Color::WHITE);
+            }
         }
     }
 }
\ No newline at end of file
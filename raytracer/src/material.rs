@@ -1,6 +1,8 @@
 // material.rs
 use raylib::prelude::*;
 
+use crate::pbr::PbrMaterial;
+
 #[derive(Debug, Clone)]
 pub struct Material {
     pub diffuse: Vector3, // Color
@@ -9,9 +11,15 @@ pub struct Material {
     pub reflectivity: f32, // reflectividad, 1.0 espejo, 0.0 no refleja nada
     pub transparency: f32, // transparencia, 1.0 perfectamente transparente, 0.0 no transparente
     pub refractive_index: f32, // indice de refraccion
+    pub roughness: f32, // rugosidad de reflejos y refracciones: 0.0 espejo/vidrio perfecto, 1.0 muy difuso
     pub texture: Option<String>, // path to texture
     pub normal_map_id: Option<String>, // path to normal map
+    pub normal_map_strength: f32, // intensidad del normal map: 0.0 lo ignora, 1.0 lo aplica tal cual
+    pub height_map: Option<String>, // path to height map (gris: blanco = alto)
+    pub height_scale: f32, // profundidad del relieve en unidades UV; negativo invierte el mapa (blanco = hundido)
+    pub parallax_occlusion: bool, // además del bump, desplaza las UVs con parallax occlusion mapping
     pub emission: Vector3, // Color y fuerza de la luz que emite el material
+    pub pbr: Option<PbrMaterial>, // si existe, se sombrea con el modelo metallic-roughness en lugar del clásico
 }
 
 impl Material {
@@ -23,9 +31,29 @@ impl Material {
             reflectivity,
             transparency,
             refractive_index,
+            roughness: 0.0,
             texture,
             normal_map_id,
+            normal_map_strength: 1.0,
+            height_map: None,
+            height_scale: 0.0,
+            parallax_occlusion: false,
             emission,
+            pbr: None,
+        }
+    }
+
+    /// Material con el modelo físico metallic-roughness. Los campos clásicos se rellenan
+    /// con valores aproximados para que el resto del código (sombras, filtros) siga funcionando.
+    pub fn from_pbr(pbr: PbrMaterial) -> Self {
+        Material {
+            diffuse: pbr.base_color,
+            specular: 2.0 / (pbr.roughness * pbr.roughness).max(1e-3) - 2.0,
+            transparency: pbr.transmission,
+            refractive_index: pbr.ior,
+            roughness: pbr.roughness,
+            pbr: Some(pbr),
+            ..Material::default()
         }
     }
     
@@ -37,9 +65,43 @@ impl Material {
             reflectivity: 0.0,
             transparency: 0.0,
             refractive_index: 0.0,
+            roughness: 0.0,
+            texture: None,
+            normal_map_id: None,
+            normal_map_strength: 1.0,
+            height_map: None,
+            height_scale: 0.0,
+            parallax_occlusion: false,
+            emission: Vector3::zero(),
+            pbr: None,
+        }
+    }
+
+    /// Rugosidad aproximada en [0, 1] equivalente al exponente de Phong `specular`.
+    pub fn phong_roughness(&self) -> f32 {
+        (2.0 / (self.specular.max(0.0) + 2.0)).sqrt().clamp(0.0, 1.0)
+    }
+}
+
+impl Default for Material {
+    /// Material blanco mate sin texturas; base para definir materiales con `..Material::default()`.
+    fn default() -> Self {
+        Material {
+            diffuse: Vector3::one(),
+            albedo: [1.0, 0.0],
+            specular: 1.0,
+            reflectivity: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            roughness: 0.0,
             texture: None,
             normal_map_id: None,
+            normal_map_strength: 1.0,
+            height_map: None,
+            height_scale: 0.0,
+            parallax_occlusion: false,
             emission: Vector3::zero(),
+            pbr: None,
         }
     }
 }
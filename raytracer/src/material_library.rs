@@ -0,0 +1,455 @@
+// material_library.rs
+use raylib::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::cube::{Cube, CubeFace};
+use crate::material::Material;
+use crate::pbr::PbrMaterial;
+
+/// Material de la biblioteca junto con sus sustituciones por cara.
+#[derive(Debug, Clone)]
+pub struct LibraryMaterial {
+    pub material: Material,
+    pub faces: Vec<(CubeFace, Material)>,
+}
+
+/// Colección de materiales con nombre que se comparte entre escenas.
+///
+/// Formato de los ficheros `.mat` (una clave por línea; `#` al principio de la línea o tras un
+/// espacio inicia un comentario, así que las rutas pueden contenerlo):
+///
+/// ```text
+/// [stone]
+/// diffuse = 0.5 0.5 0.5
+/// albedo = 0.7 0.3
+/// texture = assets/stone.png
+///
+/// [mossy_stone]
+/// extends = stone          # copia todos los campos (y caras) de otro material
+/// diffuse = 0.4 0.6 0.4
+///
+/// [grass.bottom]           # sustitución para una cara: parte del material `grass`
+/// texture = assets/dirt.png
+/// ```
+///
+/// Las claves son los campos de `Material` (`normal_map` para `normal_map_id`) y,
+/// con prefijo `pbr.`, los de `PbrMaterial`; cualquier clave `pbr.` activa el modelo físico.
+#[derive(Debug, Clone, Default)]
+pub struct MaterialLibrary {
+    materials: HashMap<String, LibraryMaterial>,
+}
+
+impl MaterialLibrary {
+    pub fn new() -> Self {
+        MaterialLibrary::default()
+    }
+
+    /// Carga una biblioteca desde un fichero.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut library = MaterialLibrary::new();
+        library.load_file(path)?;
+        Ok(library)
+    }
+
+    /// Añade los materiales de un fichero; los nombres repetidos sustituyen a los existentes.
+    pub fn load_file(&mut self, path: &str) -> Result<(), String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("No se pudo leer la biblioteca de materiales {}: {}", path, e))?;
+        self.parse(&source, path)
+    }
+
+    /// Interpreta el texto de una biblioteca y lo fusiona con esta. `origin` solo se usa en los errores.
+    /// Si hay cualquier error (sintaxis, rangos, texturas que no existen) la biblioteca no cambia
+    /// y se devuelven todos los problemas encontrados, uno por línea.
+    pub fn parse(&mut self, source: &str, origin: &str) -> Result<(), String> {
+        let mut parsed: Vec<(String, LibraryMaterial)> = Vec::new();
+        let mut errors = Vec::new();
+        // Sección actual: (material, cara, línea de la cabecera)
+        let mut current: Option<(String, Option<CubeFace>, usize)> = None;
+
+        for (index, raw_line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let line = strip_comment(raw_line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let Some(header) = header.strip_suffix(']') else {
+                    errors.push(format!("{}:{}: falta ']' en la cabecera", origin, line_number));
+                    current = None;
+                    continue;
+                };
+                let header = header.trim();
+                let (name, face) = match header.split_once('.') {
+                    Some((name, face_name)) => match CubeFace::from_name(face_name.trim()) {
+                        Some(face) => (name.trim(), Some(face)),
+                        None => {
+                            errors.push(format!("{}:{}: cara desconocida '{}' (right, left, top, bottom, front, back)", origin, line_number, face_name.trim()));
+                            current = None;
+                            continue;
+                        }
+                    },
+                    None => (header, None),
+                };
+                if name.is_empty() {
+                    errors.push(format!("{}:{}: sección sin nombre", origin, line_number));
+                    current = None;
+                    continue;
+                }
+
+                match face {
+                    None => {
+                        let entry = LibraryMaterial { material: Material::default(), faces: Vec::new() };
+                        parsed.retain(|(n, _)| n != name);
+                        parsed.push((name.to_string(), entry));
+                    }
+                    Some(face) => {
+                        // La cara parte del material base tal y como está definido hasta aquí
+                        let base = parsed
+                            .iter()
+                            .rev()
+                            .find(|(n, _)| n == name)
+                            .map(|(_, entry)| entry.material.clone())
+                            .or_else(|| self.get(name).cloned());
+                        let Some(base) = base else {
+                            errors.push(format!("{}:{}: la cara '{}' se define antes que el material '{}'", origin, line_number, header, name));
+                            current = None;
+                            continue;
+                        };
+                        // Un material de una biblioteca anterior se copia para añadirle la cara
+                        if !parsed.iter().any(|(n, _)| n == name)
+                            && let Some(inherited) = self.materials.get(name)
+                        {
+                            parsed.push((name.to_string(), inherited.clone()));
+                        }
+                        if let Some(entry) = find_entry(&mut parsed, name) {
+                            entry.faces.retain(|(f, _)| *f != face);
+                            entry.faces.push((face, base));
+                        }
+                    }
+                }
+                current = Some((name.to_string(), face, line_number));
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                errors.push(format!("{}:{}: se esperaba 'clave = valor'", origin, line_number));
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let Some((name, face, _)) = &current else {
+                errors.push(format!("{}:{}: '{}' fuera de una sección [material]", origin, line_number, key));
+                continue;
+            };
+
+            if key == "extends" {
+                if face.is_some() {
+                    errors.push(format!("{}:{}: 'extends' no se puede usar en una cara", origin, line_number));
+                    continue;
+                }
+                let parent = parsed
+                    .iter()
+                    .rev()
+                    .find(|(n, _)| n == value)
+                    .map(|(_, entry)| entry.clone())
+                    .or_else(|| self.materials.get(value).cloned());
+                match (parent, find_entry(&mut parsed, name)) {
+                    (Some(parent), Some(entry)) => *entry = parent,
+                    (None, _) => errors.push(format!("{}:{}: el material '{}' no existe", origin, line_number, value)),
+                    _ => {}
+                }
+                continue;
+            }
+
+            let Some(entry) = find_entry(&mut parsed, name) else {
+                continue;
+            };
+            let material = match face {
+                Some(face) => match entry.faces.iter_mut().find(|(f, _)| f == face) {
+                    Some((_, material)) => material,
+                    None => continue,
+                },
+                None => &mut entry.material,
+            };
+            if let Err(e) = set_field(material, key, value) {
+                errors.push(format!("{}:{}: {}", origin, line_number, e));
+            }
+        }
+
+        for (name, entry) in &parsed {
+            validate(name, None, &entry.material, origin, &mut errors);
+            for (face, material) in &entry.faces {
+                validate(name, Some(*face), material, origin, &mut errors);
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
+        }
+        for (name, entry) in parsed {
+            self.materials.insert(name, entry);
+        }
+        Ok(())
+    }
+
+    /// Fusiona otra biblioteca; sus materiales sustituyen a los de igual nombre.
+    pub fn merge(&mut self, other: MaterialLibrary) {
+        self.materials.extend(other.materials);
+    }
+
+    /// Registra un material definido en código.
+    pub fn insert(&mut self, name: &str, material: Material) {
+        self.materials.insert(name.to_string(), LibraryMaterial { material, faces: Vec::new() });
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Material> {
+        self.materials.get(name).map(|entry| &entry.material)
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&LibraryMaterial> {
+        self.materials.get(name)
+    }
+
+    /// Copia del material con ese nombre, o un error que lo indica.
+    pub fn material(&self, name: &str) -> Result<Material, String> {
+        self.get(name)
+            .cloned()
+            .ok_or_else(|| format!("El material '{}' no está en la biblioteca", name))
+    }
+
+    /// Cubo con el material `name` y sus sustituciones por cara.
+    pub fn cube(&self, center: Vector3, size: f32, name: &str) -> Result<Cube, String> {
+        let entry = self
+            .materials
+            .get(name)
+            .ok_or_else(|| format!("El material '{}' no está en la biblioteca", name))?;
+        let mut cube = Cube::new(center, size, entry.material.clone());
+        for (face, material) in &entry.faces {
+            cube = cube.with_face_material(*face, material.clone());
+        }
+        Ok(cube)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.materials.keys().map(|n| n.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    /// Todas las imágenes que usan los materiales (color, normales, alturas, metallic-roughness),
+    /// sin repetir, para cargarlas en el `TextureManager`.
+    pub fn texture_paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self
+            .materials
+            .values()
+            .flat_map(|entry| std::iter::once(&entry.material).chain(entry.faces.iter().map(|(_, m)| m)))
+            .flat_map(material_textures)
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+}
+
+/// Quita el comentario de una línea. Solo cuenta el `#` que empieza la línea o va tras un espacio,
+/// para no cortar rutas como `assets/tile#2.png`.
+pub(crate) fn strip_comment(line: &str) -> &str {
+    let mut previous: Option<char> = None;
+    for (index, c) in line.char_indices() {
+        if c == '#' && previous.is_none_or(char::is_whitespace) {
+            return &line[..index];
+        }
+        previous = Some(c);
+    }
+    line
+}
+
+fn find_entry<'a>(parsed: &'a mut [(String, LibraryMaterial)], name: &str) -> Option<&'a mut LibraryMaterial> {
+    parsed.iter_mut().rev().find(|(n, _)| n == name).map(|(_, entry)| entry)
+}
+
+fn material_textures(material: &Material) -> Vec<String> {
+    [
+        material.texture.as_ref(),
+        material.normal_map_id.as_ref(),
+        material.height_map.as_ref(),
+        material.pbr.as_ref().and_then(|pbr| pbr.metallic_roughness_texture.as_ref()),
+    ]
+    .into_iter()
+    .flatten()
+    .cloned()
+    .collect()
+}
+
+fn set_field(material: &mut Material, key: &str, value: &str) -> Result<(), String> {
+    if let Some(pbr_key) = key.strip_prefix("pbr.") {
+        let base_color = material.diffuse;
+        let pbr = material.pbr.get_or_insert_with(|| PbrMaterial::new(base_color, 0.0, 0.5));
+        match pbr_key {
+            "base_color" => pbr.base_color = parse_vector(value)?,
+            "metallic" => pbr.metallic = parse_f32(value)?,
+            "roughness" => pbr.roughness = parse_f32(value)?,
+            "ior" => pbr.ior = parse_f32(value)?,
+            "transmission" => pbr.transmission = parse_f32(value)?,
+            "metallic_roughness_texture" => pbr.metallic_roughness_texture = parse_path(value),
+            _ => return Err(format!("clave desconocida '{}'", key)),
+        }
+        return Ok(());
+    }
+
+    match key {
+        "diffuse" => material.diffuse = parse_vector(value)?,
+        "albedo" => {
+            let values = parse_floats(value, 2)?;
+            material.albedo = [values[0], values[1]];
+        }
+        "specular" => material.specular = parse_f32(value)?,
+        "reflectivity" => material.reflectivity = parse_f32(value)?,
+        "transparency" => material.transparency = parse_f32(value)?,
+        "refractive_index" => material.refractive_index = parse_f32(value)?,
+        "roughness" => material.roughness = parse_f32(value)?,
+        "texture" => material.texture = parse_path(value),
+        "normal_map" => material.normal_map_id = parse_path(value),
+        "normal_map_strength" => material.normal_map_strength = parse_f32(value)?,
+        "height_map" => material.height_map = parse_path(value),
+        "height_scale" => material.height_scale = parse_f32(value)?,
+        "parallax_occlusion" => {
+            material.parallax_occlusion = match value {
+                "true" => true,
+                "false" => false,
+                _ => return Err(format!("'{}' no es true/false", value)),
+            }
+        }
+        "emission" => material.emission = parse_vector(value)?,
+        _ => return Err(format!("clave desconocida '{}'", key)),
+    }
+    Ok(())
+}
+
+/// `none` quita la imagen heredada.
+fn parse_path(value: &str) -> Option<String> {
+    if value == "none" { None } else { Some(value.to_string()) }
+}
+
+fn parse_f32(value: &str) -> Result<f32, String> {
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .ok_or_else(|| format!("'{}' no es un número", value))
+}
+
+fn parse_floats(value: &str, count: usize) -> Result<Vec<f32>, String> {
+    let values = value
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .map(parse_f32)
+        .collect::<Result<Vec<f32>, String>>()?;
+    if values.len() != count {
+        return Err(format!("se esperaban {} números en '{}'", count, value));
+    }
+    Ok(values)
+}
+
+fn parse_vector(value: &str) -> Result<Vector3, String> {
+    let values = parse_floats(value, 3)?;
+    Ok(Vector3::new(values[0], values[1], values[2]))
+}
+
+/// Comprueba rangos físicamente razonables y que las imágenes existan.
+fn validate(name: &str, face: Option<CubeFace>, material: &Material, origin: &str, errors: &mut Vec<String>) {
+    let label = match face {
+        Some(face) => format!("{}: [{}.{}]", origin, name, face.name()),
+        None => format!("{}: [{}]", origin, name),
+    };
+    let mut check = |ok: bool, message: &str| {
+        if !ok {
+            errors.push(format!("{} {}", label, message));
+        }
+    };
+    let non_negative = |v: Vector3| v.x >= 0.0 && v.y >= 0.0 && v.z >= 0.0;
+    let unit = |v: f32| (0.0..=1.0).contains(&v);
+
+    check(non_negative(material.diffuse), "diffuse no puede ser negativo");
+    check(material.albedo[0] >= 0.0 && material.albedo[1] >= 0.0, "albedo no puede ser negativo");
+    check(material.specular >= 0.0, "specular no puede ser negativo");
+    check(unit(material.reflectivity), "reflectivity debe estar en [0, 1]");
+    check(unit(material.transparency), "transparency debe estar en [0, 1]");
+    check(material.reflectivity + material.transparency <= 1.0 + 1e-4, "reflectivity + transparency no puede superar 1");
+    check(material.refractive_index > 0.0, "refractive_index debe ser positivo");
+    check(unit(material.roughness), "roughness debe estar en [0, 1]");
+    check(material.normal_map_strength >= 0.0, "normal_map_strength no puede ser negativo");
+    check(non_negative(material.emission), "emission no puede ser negativo");
+    if let Some(pbr) = &material.pbr {
+        check(non_negative(pbr.base_color), "pbr.base_color no puede ser negativo");
+        check(unit(pbr.metallic), "pbr.metallic debe estar en [0, 1]");
+        check(unit(pbr.roughness), "pbr.roughness debe estar en [0, 1]");
+        check(unit(pbr.transmission), "pbr.transmission debe estar en [0, 1]");
+        check(pbr.ior >= 1.0, "pbr.ior debe ser al menos 1");
+    }
+    for path in material_textures(material) {
+        check(Path::new(&path).is_file(), &format!("la imagen '{}' no existe", path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sections_extends_and_faces() {
+        let source = "\
+# biblioteca de prueba
+[stone]
+diffuse = 0.5 0.5 0.5
+albedo = 0.7, 0.3   # comentario tras un espacio
+
+[mossy]
+extends = stone
+diffuse = 0.4 0.6 0.4
+
+[mossy.top]
+roughness = 0.5
+pbr.metallic = 1
+";
+        let mut library = MaterialLibrary::new();
+        library.parse(source, "test.mat").unwrap();
+
+        let stone = library.get("stone").unwrap();
+        assert_eq!(stone.diffuse, Vector3::new(0.5, 0.5, 0.5));
+        assert_eq!(stone.albedo, [0.7, 0.3]);
+
+        let mossy = library.entry("mossy").unwrap();
+        assert_eq!(mossy.material.diffuse, Vector3::new(0.4, 0.6, 0.4));
+        assert_eq!(mossy.material.albedo, [0.7, 0.3]);
+        let (face, top) = &mossy.faces[0];
+        assert_eq!(*face, CubeFace::Top);
+        assert_eq!(top.diffuse, Vector3::new(0.4, 0.6, 0.4));
+        assert_eq!(top.roughness, 0.5);
+        assert_eq!(top.pbr.as_ref().unwrap().metallic, 1.0);
+        assert!(mossy.material.pbr.is_none());
+    }
+
+    #[test]
+    fn errors_leave_the_library_unchanged() {
+        let mut library = MaterialLibrary::new();
+        let errors = library
+            .parse("[a]\nreflectivity = 2\nfoo = 1\n[b.side]\nclave sin valor\n", "bad.mat")
+            .unwrap_err();
+        assert!(errors.contains("bad.mat:3: clave desconocida 'foo'"));
+        assert!(errors.contains("bad.mat:4: cara desconocida 'side'"));
+        assert!(errors.contains("bad.mat:5: se esperaba 'clave = valor'"));
+        assert!(errors.contains("reflectivity debe estar en [0, 1]"));
+        assert!(library.names().is_empty());
+    }
+
+    #[test]
+    fn hash_only_starts_a_comment_at_the_start_or_after_a_space() {
+        assert_eq!(strip_comment("# todo"), "");
+        assert_eq!(strip_comment("texture = a#b.png # c"), "texture = a#b.png ");
+        assert_eq!(strip_comment("texture = a#b.png\t#c"), "texture = a#b.png\t");
+        assert_eq!(strip_comment("texture = tiles/#2.png"), "texture = tiles/#2.png");
+    }
+}
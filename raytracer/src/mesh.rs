@@ -0,0 +1,186 @@
+// mesh.rs
+use raylib::prelude::Vector3;
+use std::collections::HashMap;
+
+use crate::aabb::Aabb;
+use crate::area_light::EmissivePatch;
+use crate::bvh::Bvh;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect, wrap_uv};
+use crate::sampling::orthonormal_basis;
+use crate::tangent::{compute_vertex_tangents, triangle_tangents};
+use crate::triangle::intersect_triangle;
+
+/// Triángulo de una malla: índices a las posiciones, normales, UVs y tangentes de la malla.
+#[derive(Debug, Clone)]
+pub struct MeshFace {
+    pub positions: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+    pub tangents: Option<[usize; 3]>, // Si falta y hay normales y UVs, `Mesh::new` las genera
+    pub material: usize, // Índice en `Mesh::materials`
+}
+
+/// Malla de triángulos con vértices compartidos y su propia BVH, de forma que el coste
+/// de intersección crece de forma logarítmica con el número de triángulos.
+pub struct Mesh {
+    pub name: String,
+    pub positions: Vec<Vector3>,
+    pub normals: Vec<Vector3>,
+    pub uvs: Vec<(f32, f32)>,
+    pub tangents: Vec<[f32; 4]>, // Tangente por vértice y signo de la bitangente en `w`
+    pub faces: Vec<MeshFace>,
+    pub materials: Vec<Material>,
+    bvh: Bvh,
+}
+
+impl Mesh {
+    pub fn new(
+        name: &str,
+        positions: Vec<Vector3>,
+        normals: Vec<Vector3>,
+        uvs: Vec<(f32, f32)>,
+        mut tangents: Vec<[f32; 4]>,
+        mut faces: Vec<MeshFace>,
+        materials: Vec<Material>,
+    ) -> Self {
+        let bounds: Vec<Aabb> = faces
+            .iter()
+            .map(|face| Aabb::from_points(&face.positions.map(|i| positions[i])))
+            .collect();
+        generate_tangents(&positions, &normals, &uvs, &mut faces, &mut tangents);
+        Mesh {
+            name: name.to_string(),
+            positions,
+            normals,
+            uvs,
+            tangents,
+            faces,
+            materials,
+            bvh: Bvh::build(&bounds),
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    fn face_positions(&self, face: &MeshFace) -> [Vector3; 3] {
+        face.positions.map(|i| self.positions[i])
+    }
+}
+
+impl RayIntersect for Mesh {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Intersect {
+        let mut closest: Option<(usize, f32, f32, f32)> = None;
+        self.bvh.traverse(ray_origin, ray_direction, f32::INFINITY, |index, max_distance| {
+            let (distance, b1, b2) = intersect_triangle(ray_origin, ray_direction, self.face_positions(&self.faces[index]))?;
+            if distance >= max_distance {
+                return None;
+            }
+            closest = Some((index, distance, b1, b2));
+            Some(distance)
+        });
+        let Some((index, distance, b1, b2)) = closest else {
+            return Intersect::empty();
+        };
+
+        let face = &self.faces[index];
+        let b0 = 1.0 - b1 - b2;
+        let vertices = self.face_positions(face);
+        let point = *ray_origin + *ray_direction * distance;
+        let geometric_normal = (vertices[1] - vertices[0]).cross(vertices[2] - vertices[0]).normalized();
+        let normal = match face.normals {
+            Some([n0, n1, n2]) => {
+                let interpolated = self.normals[n0] * b0 + self.normals[n1] * b1 + self.normals[n2] * b2;
+                if interpolated.length() > 1e-8 { interpolated.normalized() } else { geometric_normal }
+            }
+            None => geometric_normal,
+        };
+        let (uvs, (u, v)) = match face.uvs {
+            Some(indices) => {
+                let uvs = indices.map(|i| self.uvs[i]);
+                (uvs, (uvs[0].0 * b0 + uvs[1].0 * b1 + uvs[2].0 * b2, uvs[0].1 * b0 + uvs[1].1 * b1 + uvs[2].1 * b2))
+            }
+            None => ([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)], (b1, b2)),
+        };
+        // Con tangentes por vértice la base se interpola igual que la normal y no salta entre
+        // triángulos; si no, se usan las derivadas de las UVs de este triángulo
+        let vertex_frame = face.tangents.and_then(|indices| {
+            let [t0, t1, t2] = indices.map(|i| self.tangents[i]);
+            let tangent = Vector3::new(t0[0], t0[1], t0[2]) * b0 + Vector3::new(t1[0], t1[1], t1[2]) * b1 + Vector3::new(t2[0], t2[1], t2[2]) * b2;
+            let tangent = tangent - normal * normal.dot(tangent);
+            if tangent.length() <= 1e-8 {
+                return None;
+            }
+            let tangent = tangent.normalized();
+            let w = if t0[3] * b0 + t1[3] * b1 + t2[3] * b2 < 0.0 { -1.0 } else { 1.0 };
+            Some((tangent, normal.cross(tangent) * w))
+        });
+        let (tangent, bitangent) = vertex_frame
+            .or_else(|| triangle_tangents(vertices, uvs))
+            .unwrap_or_else(|| orthonormal_basis(normal));
+        let material = self.materials.get(face.material).cloned().unwrap_or_default();
+
+        Intersect::new(material, distance, normal, point, wrap_uv(u), wrap_uv(v))
+            .with_tangent_frame(tangent, bitangent)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bvh.bounds())
+    }
+
+    /// Cada triángulo con material emisivo es un emisor (MTL `Ke`, `emissiveFactor` de glTF).
+    fn emissive_patches(&self) -> Vec<EmissivePatch> {
+        self.faces
+            .iter()
+            .filter_map(|face| {
+                let emission = self.materials.get(face.material)?.emission;
+                (emission.dot(emission) > 0.0).then(|| EmissivePatch::triangle(self.face_positions(face), emission))
+            })
+            .collect()
+    }
+}
+
+/// Genera tangentes MikkTSpace para las caras que no las traen y tienen normales y UVs. Cada
+/// combinación distinta de posición, normal y UV es un vértice, como en MikkTSpace: las caras que
+/// lo comparten promedian su tangente y las costuras de UV quedan separadas.
+fn generate_tangents(
+    positions: &[Vector3],
+    normals: &[Vector3],
+    uvs: &[(f32, f32)],
+    faces: &mut [MeshFace],
+    tangents: &mut Vec<[f32; 4]>,
+) {
+    let mut vertex_map: HashMap<(usize, usize, usize), usize> = HashMap::new();
+    let mut vertex_positions = Vec::new();
+    let mut vertex_normals = Vec::new();
+    let mut vertex_uvs = Vec::new();
+    let mut triangles = Vec::new();
+    let mut generated = Vec::new();
+    for (face_index, face) in faces.iter().enumerate() {
+        let (None, Some(normal_indices), Some(uv_indices)) = (face.tangents, face.normals, face.uvs) else {
+            continue;
+        };
+        let triangle = [0, 1, 2].map(|corner| {
+            let key = (face.positions[corner], normal_indices[corner], uv_indices[corner]);
+            *vertex_map.entry(key).or_insert_with(|| {
+                vertex_positions.push(positions[key.0]);
+                vertex_normals.push(normals[key.1]);
+                vertex_uvs.push(uvs[key.2]);
+                vertex_positions.len() - 1
+            })
+        });
+        triangles.push(triangle);
+        generated.push(face_index);
+    }
+    if triangles.is_empty() {
+        return;
+    }
+
+    let offset = tangents.len();
+    tangents.extend(compute_vertex_tangents(&vertex_positions, &vertex_normals, &vertex_uvs, &triangles));
+    for (face_index, triangle) in generated.into_iter().zip(triangles) {
+        faces[face_index].tangents = Some(triangle.map(|i| offset + i));
+    }
+}
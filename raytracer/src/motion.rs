@@ -0,0 +1,188 @@
+// motion.rs
+use raylib::prelude::{Matrix, Vector3};
+
+use crate::aabb::Aabb;
+use crate::area_light::EmissivePatch;
+use crate::ray_intersect::{Intersect, RayIntersect, Span};
+use crate::sampling::random_f32;
+use crate::transform::Transform;
+
+/// Tramo del fotograma durante el que el obturador está abierto, en fracciones del fotograma:
+/// `(0, 0.5)` es el obturador de 180° del cine. Si `open == close` no hay desenfoque.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shutter {
+    pub open: f32,
+    pub close: f32,
+}
+
+impl Default for Shutter {
+    fn default() -> Self {
+        Shutter::instant(0.0)
+    }
+}
+
+impl Shutter {
+    pub fn new(open: f32, close: f32) -> Self {
+        Shutter { open, close: close.max(open) }
+    }
+
+    /// Obturador instantáneo en `time`.
+    pub fn instant(time: f32) -> Self {
+        Shutter { open: time, close: time }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.close > self.open
+    }
+
+    /// Instante de la muestra `index` de `count`, estratificado: cada muestra cae al azar dentro
+    /// de su parte del intervalo para que pocas muestras cubran toda la exposición.
+    pub fn sample(&self, index: u32, count: u32) -> f32 {
+        if !self.is_open() {
+            return self.open;
+        }
+        let fraction = (index as f32 + random_f32()) / count.max(1) as f32;
+        self.open + (self.close - self.open) * fraction
+    }
+}
+
+/// Transformación que cambia a lo largo del fotograma: claves en instantes entre 0 y 1 con
+/// interpolación lineal de la matriz entre ellas. Con varias claves por fotograma los giros se
+/// aproximan bien; los puntos se mueven en línea recta entre claves, así que la caja que los
+/// envuelve durante todo el fotograma es la unión de las cajas en cada clave.
+#[derive(Debug, Clone)]
+pub struct MotionTransform {
+    keys: Vec<(f32, Transform)>,
+}
+
+impl MotionTransform {
+    /// Claves `(instante, transformación)`; se ordenan por instante.
+    pub fn new(mut keys: Vec<(f32, Transform)>) -> Self {
+        assert!(!keys.is_empty(), "MotionTransform necesita al menos una clave");
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        MotionTransform { keys }
+    }
+
+    /// Sin movimiento.
+    pub fn fixed(transform: Transform) -> Self {
+        MotionTransform { keys: vec![(0.0, transform)] }
+    }
+
+    /// De `start` al principio del fotograma a `end` al final.
+    pub fn between(start: Transform, end: Transform) -> Self {
+        MotionTransform { keys: vec![(0.0, start), (1.0, end)] }
+    }
+
+    pub fn keys(&self) -> &[(f32, Transform)] {
+        &self.keys
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.keys.len() < 2
+    }
+
+    /// Transformación al principio del fotograma.
+    pub fn start(&self) -> Transform {
+        self.keys[0].1
+    }
+
+    /// Transformación en `time`; fuera de las claves se mantiene la del extremo.
+    pub fn at(&self, time: f32) -> Transform {
+        let next = self.keys.partition_point(|(key_time, _)| *key_time <= time);
+        if next == 0 {
+            return self.keys[0].1;
+        }
+        if next == self.keys.len() {
+            return self.keys[next - 1].1;
+        }
+        let ((t0, a), (t1, b)) = (&self.keys[next - 1], &self.keys[next]);
+        Transform::new(lerp_matrix(&a.matrix, &b.matrix, (time - t0) / (t1 - t0)))
+    }
+
+    /// Aplica primero `self` y después `next`, con claves en los instantes de ambas.
+    pub fn then(&self, next: &MotionTransform) -> MotionTransform {
+        let mut times: Vec<f32> = self.keys.iter().chain(&next.keys).map(|(time, _)| *time).collect();
+        times.sort_by(f32::total_cmp);
+        times.dedup();
+        MotionTransform {
+            keys: times.into_iter().map(|time| (time, self.at(time).then(&next.at(time)))).collect(),
+        }
+    }
+
+    /// Caja que envuelve a `bounds` durante todo el fotograma.
+    pub fn bounds(&self, bounds: &Aabb) -> Aabb {
+        self.keys
+            .iter()
+            .map(|(_, transform)| transform.bounds(bounds))
+            .reduce(|a, b| a.union(&b))
+            .unwrap_or(*bounds)
+    }
+}
+
+fn lerp_matrix(a: &Matrix, b: &Matrix, t: f32) -> Matrix {
+    let lerp = |x: f32, y: f32| x + (y - x) * t;
+    Matrix {
+        m0: lerp(a.m0, b.m0),
+        m1: lerp(a.m1, b.m1),
+        m2: lerp(a.m2, b.m2),
+        m3: lerp(a.m3, b.m3),
+        m4: lerp(a.m4, b.m4),
+        m5: lerp(a.m5, b.m5),
+        m6: lerp(a.m6, b.m6),
+        m7: lerp(a.m7, b.m7),
+        m8: lerp(a.m8, b.m8),
+        m9: lerp(a.m9, b.m9),
+        m10: lerp(a.m10, b.m10),
+        m11: lerp(a.m11, b.m11),
+        m12: lerp(a.m12, b.m12),
+        m13: lerp(a.m13, b.m13),
+        m14: lerp(a.m14, b.m14),
+        m15: lerp(a.m15, b.m15),
+    }
+}
+
+/// Cualquier primitiva que se mueve durante el fotograma: cada rayo la ve colocada con la
+/// transformación del instante que lleva (el `time` del rayo). Su caja cubre todo el recorrido para que
+/// las BVH la encuentren en cualquier instante.
+pub struct Moving<T> {
+    pub object: T,
+    pub motion: MotionTransform,
+}
+
+impl<T: RayIntersect> Moving<T> {
+    pub fn new(object: T, motion: MotionTransform) -> Self {
+        Moving { object, motion }
+    }
+}
+
+impl<T: RayIntersect> RayIntersect for Moving<T> {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Intersect {
+        let transform = self.motion.at(time);
+        let (origin, direction) = transform.local_ray(ray_origin, ray_direction);
+        let hit = self.object.ray_intersect(&origin, &direction, time);
+        transform.intersect_to_world(hit, ray_origin, ray_direction)
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Vec<Span> {
+        let transform = self.motion.at(time);
+        let (origin, direction) = transform.local_ray(ray_origin, ray_direction);
+        self.object
+            .ray_intervals(&origin, &direction, time)
+            .into_iter()
+            .map(|span| Span {
+                enter: transform.intersect_to_world(span.enter, ray_origin, ray_direction),
+                exit: transform.intersect_to_world(span.exit, ray_origin, ray_direction),
+            })
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.object.bounding_box().map(|bounds| self.motion.bounds(&bounds))
+    }
+
+    /// Las luces de área no se mueven: iluminan desde donde está al principio del fotograma.
+    fn emissive_patches(&self) -> Vec<EmissivePatch> {
+        let start = self.motion.start();
+        self.object.emissive_patches().iter().map(|patch| patch.transformed(&start)).collect()
+    }
+}
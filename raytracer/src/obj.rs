@@ -0,0 +1,396 @@
+// obj.rs
+use raylib::prelude::Vector3;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::material::Material;
+use crate::material_library::{strip_comment, MaterialLibrary};
+use crate::mesh::{Mesh, MeshFace};
+
+/// Modelo Wavefront OBJ: una malla por grupo (`g` / `o`) y los materiales de sus MTL.
+pub struct ObjModel {
+    pub meshes: Vec<Mesh>,
+    pub materials: MaterialLibrary,
+}
+
+/// Vértice de una cara tal y como aparece en el OBJ: índices (ya en base 0) a las listas globales.
+#[derive(Clone, Copy)]
+struct FaceVertex {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+/// Grupo en construcción: sus triángulos con índices globales y el material de cada uno.
+struct Group {
+    name: String,
+    triangles: Vec<([FaceVertex; 3], String)>,
+}
+
+/// Carga un OBJ con sus bibliotecas MTL (`mtllib`). Las rutas de los MTL y de las texturas
+/// son relativas a la carpeta del archivo que las nombra. Las caras de más de tres vértices
+/// se triangulan en abanico (se suponen convexas).
+pub fn load_obj(path: &str) -> Result<ObjModel, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| format!("No se pudo leer {}: {}", path, e))?;
+    let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+
+    let mut positions: Vec<Vector3> = Vec::new();
+    let mut normals: Vec<Vector3> = Vec::new();
+    let mut uvs: Vec<(f32, f32)> = Vec::new();
+    let mut materials = MaterialLibrary::new();
+    let mut groups = vec![Group { name: "default".to_string(), triangles: Vec::new() }];
+    let mut current_material = String::new();
+
+    for (index, raw_line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = strip_comment(raw_line).trim();
+        let mut parts = line.split_whitespace();
+        let Some(keyword) = parts.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = parts.collect();
+        let error = |message: String| format!("{}:{}: {}", path, line_number, message);
+
+        match keyword {
+            "v" => positions.push(parse_vector(&arguments).map_err(error)?),
+            "vn" => normals.push(parse_vector(&arguments).map_err(error)?),
+            "vt" => {
+                let u = parse_number(arguments.first().copied()).map_err(error)?;
+                let v = arguments.get(1).map(|v| parse_number(Some(v))).transpose().map_err(error)?.unwrap_or(0.0);
+                // En OBJ v crece hacia arriba; en las imágenes la fila 0 es la de arriba
+                uvs.push((u, 1.0 - v));
+            }
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(error("una cara necesita al menos tres vértices".to_string()));
+                }
+                let vertices = arguments
+                    .iter()
+                    .map(|vertex| parse_face_vertex(vertex, positions.len(), uvs.len(), normals.len()))
+                    .collect::<Result<Vec<FaceVertex>, String>>()
+                    .map_err(error)?;
+                let group = groups.last_mut().expect("siempre hay un grupo");
+                for i in 1..vertices.len() - 1 {
+                    group.triangles.push(([vertices[0], vertices[i], vertices[i + 1]], current_material.clone()));
+                }
+            }
+            "g" | "o" => {
+                let name = if arguments.is_empty() { "default".to_string() } else { arguments.join(" ") };
+                if groups.last().is_some_and(|group| group.triangles.is_empty()) {
+                    groups.pop();
+                }
+                groups.push(Group { name, triangles: Vec::new() });
+            }
+            "usemtl" => current_material = arguments.join(" "),
+            "mtllib" => {
+                // Un MTL que falta no impide ver la geometría
+                for file in &arguments {
+                    let mtl_path = directory.join(file).to_string_lossy().into_owned();
+                    match load_mtl(&mtl_path) {
+                        Ok(library) => materials.merge(library),
+                        Err(e) => eprintln!("{}", e),
+                    }
+                }
+            }
+            _ => {} // s, l, p y demás se ignoran
+        }
+    }
+
+    let meshes = groups
+        .into_iter()
+        .filter(|group| !group.triangles.is_empty())
+        .map(|group| build_mesh(group, &positions, &normals, &uvs, &materials))
+        .collect();
+    Ok(ObjModel { meshes, materials })
+}
+
+/// Pasa los índices globales del OBJ a listas propias de la malla (solo los vértices que usa).
+fn build_mesh(group: Group, positions: &[Vector3], normals: &[Vector3], uvs: &[(f32, f32)], library: &MaterialLibrary) -> Mesh {
+    let mut local_positions = Vec::new();
+    let mut local_normals = Vec::new();
+    let mut local_uvs = Vec::new();
+    let mut position_map = HashMap::new();
+    let mut normal_map = HashMap::new();
+    let mut uv_map = HashMap::new();
+    let mut material_names: Vec<String> = Vec::new();
+    let mut faces = Vec::with_capacity(group.triangles.len());
+
+    fn remap<T: Copy>(index: usize, source: &[T], map: &mut HashMap<usize, usize>, target: &mut Vec<T>) -> usize {
+        *map.entry(index).or_insert_with(|| {
+            target.push(source[index]);
+            target.len() - 1
+        })
+    }
+
+    for (vertices, material_name) in group.triangles {
+        let material = match material_names.iter().position(|name| *name == material_name) {
+            Some(i) => i,
+            None => {
+                material_names.push(material_name);
+                material_names.len() - 1
+            }
+        };
+        let all_normals = vertices.iter().all(|v| v.normal.is_some());
+        let all_uvs = vertices.iter().all(|v| v.uv.is_some());
+        faces.push(MeshFace {
+            positions: vertices.map(|v| remap(v.position, positions, &mut position_map, &mut local_positions)),
+            normals: all_normals.then(|| vertices.map(|v| remap(v.normal.unwrap_or(0), normals, &mut normal_map, &mut local_normals))),
+            uvs: all_uvs.then(|| vertices.map(|v| remap(v.uv.unwrap_or(0), uvs, &mut uv_map, &mut local_uvs))),
+            tangents: None,
+            material,
+        });
+    }
+
+    let materials = material_names
+        .iter()
+        .map(|name| library.get(name).cloned().unwrap_or_default())
+        .collect();
+    Mesh::new(&group.name, local_positions, local_normals, local_uvs, Vec::new(), faces, materials)
+}
+
+/// Interpreta un vértice de cara `v`, `v/vt`, `v//vn` o `v/vt/vn`; los índices negativos
+/// cuentan desde el final de la lista.
+fn parse_face_vertex(vertex: &str, position_count: usize, uv_count: usize, normal_count: usize) -> Result<FaceVertex, String> {
+    let mut fields = vertex.split('/');
+    let resolve = |field: Option<&str>, count: usize| -> Result<Option<usize>, String> {
+        let Some(field) = field.filter(|f| !f.is_empty()) else {
+            return Ok(None);
+        };
+        let index: i64 = field.parse().map_err(|_| format!("índice no válido '{}'", field))?;
+        let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+        if resolved < 0 || resolved >= count as i64 {
+            return Err(format!("el índice {} no existe", index));
+        }
+        Ok(Some(resolved as usize))
+    };
+    let position = resolve(fields.next(), position_count)?.ok_or_else(|| format!("vértice sin posición '{}'", vertex))?;
+    let uv = resolve(fields.next(), uv_count)?;
+    let normal = resolve(fields.next(), normal_count)?;
+    Ok(FaceVertex { position, uv, normal })
+}
+
+fn parse_number(value: Option<&str>) -> Result<f32, String> {
+    let value = value.ok_or_else(|| "falta un número".to_string())?;
+    value.parse::<f32>().map_err(|_| format!("'{}' no es un número", value))
+}
+
+fn parse_vector(arguments: &[&str]) -> Result<Vector3, String> {
+    Ok(Vector3::new(
+        parse_number(arguments.first().copied())?,
+        parse_number(arguments.get(1).copied())?,
+        parse_number(arguments.get(2).copied())?,
+    ))
+}
+
+/// Carga una biblioteca MTL y traduce cada material a `Material`:
+/// Kd → diffuse, Ks → peso especular, Ns → exponente, d/Tr → transparencia, Ni → índice de
+/// refracción, Ke → emisión, map_Kd → textura, map_Bump/bump → mapa de alturas.
+/// Los modelos de iluminación con reflejo trazado (illum 3, 5, 7) reflejan en proporción a Ks.
+pub fn load_mtl(path: &str) -> Result<MaterialLibrary, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| format!("No se pudo leer {}: {}", path, e))?;
+    let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+
+    let mut library = MaterialLibrary::new();
+    let mut current: Option<(String, Material, Vector3, u32)> = None; // (nombre, material, Ks, illum)
+    let finish = |current: Option<(String, Material, Vector3, u32)>, library: &mut MaterialLibrary| {
+        if let Some((name, mut material, specular_color, illum)) = current {
+            let specular_weight = specular_color.x.max(specular_color.y).max(specular_color.z).clamp(0.0, 1.0);
+            material.albedo = [1.0, specular_weight];
+            if matches!(illum, 3 | 5 | 7) {
+                material.reflectivity = specular_weight * (1.0 - material.transparency);
+            }
+            library.insert(&name, material);
+        }
+    };
+
+    for (index, raw_line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = strip_comment(raw_line).trim();
+        let mut parts = line.split_whitespace();
+        let Some(keyword) = parts.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = parts.collect();
+        let error = |message: String| format!("{}:{}: {}", path, line_number, message);
+
+        if keyword == "newmtl" {
+            finish(current.take(), &mut library);
+            let material = Material { specular: 10.0, ..Material::default() };
+            current = Some((arguments.join(" "), material, Vector3::zero(), 2));
+            continue;
+        }
+        let Some((_, material, specular_color, illum)) = current.as_mut() else {
+            continue;
+        };
+        let number = |i: usize| parse_number(arguments.get(i).copied()).map_err(error);
+        match keyword {
+            "Kd" => material.diffuse = parse_vector(&arguments).map_err(error)?,
+            "Ks" => *specular_color = parse_vector(&arguments).map_err(error)?,
+            "Ke" => material.emission = parse_vector(&arguments).map_err(error)?,
+            "Ns" => material.specular = number(0)?.max(0.0),
+            "d" => material.transparency = (1.0 - number(0)?).clamp(0.0, 1.0),
+            "Tr" => material.transparency = number(0)?.clamp(0.0, 1.0),
+            "Ni" => material.refractive_index = number(0)?.max(1e-3),
+            "illum" => *illum = number(0)? as u32,
+            "map_Kd" => material.texture = texture_path(directory, &arguments),
+            "map_Bump" | "map_bump" | "bump" => {
+                material.height_map = texture_path(directory, &arguments);
+                // -bm multiplica la profundidad del relieve
+                let multiplier = arguments
+                    .iter()
+                    .position(|a| *a == "-bm")
+                    .and_then(|i| arguments.get(i + 1))
+                    .and_then(|v| v.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                material.height_scale = 0.02 * multiplier;
+            }
+            _ => {}
+        }
+    }
+    finish(current.take(), &mut library);
+    Ok(library)
+}
+
+/// Ruta de una textura del MTL: lo que queda tras las opciones como `-bm 0.5` o `-o 0 0 0`,
+/// unido con espacios para admitir nombres como `piedra gris.png`.
+/// Si la imagen no existe se avisa y el material se queda sin ella.
+fn texture_path(directory: &Path, arguments: &[&str]) -> Option<String> {
+    let mut index = 0;
+    while let Some(option) = arguments.get(index) {
+        let values = match *option {
+            "-blendu" | "-blendv" | "-cc" | "-clamp" | "-imfchan" | "-type" | "-bm" | "-boost" | "-texres" => 1,
+            "-mm" => 2,
+            // -o, -s y -t llevan de uno a tres números
+            "-o" | "-s" | "-t" => arguments[index + 1..]
+                .iter()
+                .take(3)
+                .take_while(|v| v.parse::<f32>().is_ok())
+                .count(),
+            _ => break,
+        };
+        index += 1 + values;
+    }
+    let file = arguments.get(index..).filter(|rest| !rest.is_empty())?.join(" ");
+    let path = directory.join(file).to_string_lossy().into_owned();
+    if Path::new(&path).is_file() {
+        Some(path)
+    } else {
+        eprintln!("La textura '{}' no existe; se ignora", path);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Carpeta temporal de un test; se borra al salir de él, aunque falle.
+    struct TempDir(std::path::PathBuf);
+
+    impl std::ops::Deref for TempDir {
+        type Target = Path;
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Escribe los archivos en una carpeta temporal propia del test y la devuelve.
+    fn write_files(test: &str, files: &[(&str, &str)]) -> TempDir {
+        let directory = std::env::temp_dir().join(format!("raytracer-obj-{}-{}", test, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for (name, contents) in files {
+            std::fs::write(directory.join(name), contents).unwrap();
+        }
+        TempDir(directory)
+    }
+
+    #[test]
+    fn fans_polygons_and_resolves_negative_indices() {
+        let obj = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vn 0 0 1
+# un quad con índices relativos y solo normales
+f -4//-1 -3//-1 -2//-1 -1//1
+";
+        let directory = write_files("fan", &[("quad.obj", obj)]);
+        let model = load_obj(directory.join("quad.obj").to_str().unwrap()).unwrap();
+
+        assert_eq!(model.meshes.len(), 1);
+        let mesh = &model.meshes[0];
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.normals, vec![Vector3::new(0.0, 0.0, 1.0)]);
+        let faces: Vec<[usize; 3]> = mesh.faces.iter().map(|face| face.positions).collect();
+        assert_eq!(faces, vec![[0, 1, 2], [0, 2, 3]]);
+        assert!(mesh.faces.iter().all(|face| face.normals == Some([0, 0, 0]) && face.uvs.is_none()));
+    }
+
+    #[test]
+    fn rejects_indices_out_of_range() {
+        assert!(parse_face_vertex("-4", 3, 0, 0).is_err());
+        assert!(parse_face_vertex("4", 3, 0, 0).is_err());
+        assert!(parse_face_vertex("0", 3, 0, 0).is_err());
+        let vertex = parse_face_vertex("2/-1/", 3, 2, 0).unwrap();
+        assert_eq!((vertex.position, vertex.uv, vertex.normal), (1, Some(1), None));
+    }
+
+    #[test]
+    fn reads_mtl_opacity_emission_and_illum() {
+        let mtl = "\
+newmtl cristal
+Kd 0.8 0.9 1.0
+Ks 0.5 0.5 0.5
+d 0.25
+illum 7
+
+newmtl tr
+Tr 0.4
+
+newmtl lampara
+Ke 2 1.5 1
+";
+        let obj = "mtllib piezas.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl lampara\nf 1 2 3\n";
+        let directory = write_files("mtl", &[("piezas.mtl", mtl), ("pieza.obj", obj)]);
+        let library = load_mtl(directory.join("piezas.mtl").to_str().unwrap()).unwrap();
+
+        let glass = library.get("cristal").unwrap();
+        assert_eq!(glass.diffuse, Vector3::new(0.8, 0.9, 1.0));
+        assert!((glass.transparency - 0.75).abs() < 1e-6);
+        assert_eq!(glass.albedo, [1.0, 0.5]);
+        assert!((glass.reflectivity - 0.125).abs() < 1e-6);
+        assert!((library.get("tr").unwrap().transparency - 0.4).abs() < 1e-6);
+
+        let model = load_obj(directory.join("pieza.obj").to_str().unwrap()).unwrap();
+        assert_eq!(model.meshes[0].materials[0].emission, Vector3::new(2.0, 1.5, 1.0));
+    }
+
+    #[test]
+    fn keeps_hashes_and_spaces_inside_names_and_paths() {
+        let mtl = "\
+newmtl piedra#2 # el nombre lleva almohadilla
+map_Kd -o 0.5 0.5 -bm 2 piedra gris#2.png # textura con espacios
+map_Bump -bm 0.5 relieve gris.png
+";
+        let obj = "mtllib piezas.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl piedra#2\nf 1 2 3 # triángulo\n";
+        let directory = write_files(
+            "hash",
+            &[("piezas.mtl", mtl), ("pieza.obj", obj), ("piedra gris#2.png", ""), ("relieve gris.png", "")],
+        );
+
+        let model = load_obj(directory.join("pieza.obj").to_str().unwrap()).unwrap();
+        let material = &model.meshes[0].materials[0];
+        let texture = directory.join("piedra gris#2.png").to_string_lossy().into_owned();
+        let height_map = directory.join("relieve gris.png").to_string_lossy().into_owned();
+        assert_eq!(material.texture, Some(texture));
+        assert_eq!(material.height_map, Some(height_map));
+        assert!((material.height_scale - 0.01).abs() < 1e-6);
+    }
+}
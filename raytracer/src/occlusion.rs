@@ -0,0 +1,155 @@
+// occlusion.rs
+use raylib::prelude::*;
+use std::collections::HashSet;
+
+use crate::cube::Cube;
+use crate::material::Material;
+use crate::ray_intersect::RayIntersect;
+use crate::sampling::cosine_sample_hemisphere;
+
+/// Cómo se calcula la oclusión ambiental que oscurece la luz ambiente.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AoMode {
+    Off,
+    /// Rayos repartidos por el hemisferio (según el coseno); cuenta los que chocan antes de `max_distance`.
+    Hemisphere { samples: u32, max_distance: f32 },
+    /// Aproximación rápida para rejillas de bloques: mira los vecinos de cada cara, como Minecraft.
+    /// Las superficies que no son caras de bloques de la rejilla usan el hemisferio.
+    VoxelNeighbours,
+}
+
+/// Visibilidad de cada esquina de una cara según cuántos de sus tres vecinos (dos lados y la
+/// esquina) están ocupados: índice 0 = esquina encerrada, 3 = ningún vecino.
+const VOXEL_AO_LEVELS: [f32; 4] = [0.35, 0.55, 0.78, 1.0];
+
+/// Rayos y alcance del hemisferio para lo que no es una cara de la rejilla en modo `VoxelNeighbours`.
+const VOXEL_FALLBACK_SAMPLES: u32 = 4;
+const VOXEL_FALLBACK_DISTANCE: f32 = 1.0;
+
+/// Margen con el que se considera que una normal está alineada a un eje y que un punto está
+/// sobre el plano de una cara de la rejilla.
+const LATTICE_EPSILON: f32 = 1e-3;
+
+/// Celdas ocupadas por bloques opacos, en coordenadas enteras (el centro de cada cubo unitario).
+#[derive(Debug, Default)]
+pub struct VoxelGrid {
+    cells: HashSet<(i32, i32, i32)>,
+}
+
+impl VoxelGrid {
+    /// Marca las celdas cuyo centro queda dentro del cubo, si es opaco; los transparentes
+    /// (vidrio, agua) no ocluyen.
+    pub fn add_cube(&mut self, cube: &Cube) {
+        self.add_box(cube.min_bounds, cube.max_bounds, &cube.material);
+    }
+
+    /// Igual que `add_cube` para una caja dada por sus esquinas (p. ej. un bloque instanciado).
+    pub fn add_box(&mut self, min: Vector3, max: Vector3, material: &Material) {
+        if material.transparency > 0.0 {
+            return;
+        }
+        for x in (min.x + 0.5).ceil() as i32..=(max.x - 0.5).floor() as i32 {
+            for y in (min.y + 0.5).ceil() as i32..=(max.y - 0.5).floor() as i32 {
+                for z in (min.z + 0.5).ceil() as i32..=(max.z - 0.5).floor() as i32 {
+                    self.cells.insert((x, y, z));
+                }
+            }
+        }
+    }
+
+    pub fn is_occupied(&self, cell: (i32, i32, i32)) -> bool {
+        self.cells.contains(&cell)
+    }
+
+    /// Visibilidad en [0, 1] de un punto sobre la cara de un bloque de la rejilla: se calcula en
+    /// las cuatro esquinas de la cara y se interpola bilinealmente. Devuelve `None` si el punto no
+    /// está en una cara de un bloque ocupado (esferas, mallas, objetos girados...), donde los
+    /// vecinos de la rejilla no dicen nada.
+    pub fn occlusion(&self, point: Vector3, normal: Vector3) -> Option<f32> {
+        let largest = normal.x.abs().max(normal.y.abs()).max(normal.z.abs());
+        if largest < 1.0 - LATTICE_EPSILON {
+            return None;
+        }
+        let along_normal = point.dot(normal);
+        if (along_normal - 0.5 - (along_normal - 0.5).round()).abs() > LATTICE_EPSILON {
+            return None;
+        }
+        let behind = point - normal * 0.5;
+        if !self.is_occupied((behind.x.round() as i32, behind.y.round() as i32, behind.z.round() as i32)) {
+            return None;
+        }
+
+        // Celda vacía delante de la cara
+        let front = point + normal * 0.5;
+        let cell = (front.x.round() as i32, front.y.round() as i32, front.z.round() as i32);
+
+        // Ejes de la cara y posición del punto dentro de ella, en [0, 1]
+        let (axis_u, axis_v) = if normal.x.abs() > 0.5 {
+            ((0, 0, 1), (0, 1, 0))
+        } else if normal.y.abs() > 0.5 {
+            ((1, 0, 0), (0, 0, 1))
+        } else {
+            ((1, 0, 0), (0, 1, 0))
+        };
+        let along = |axis: (i32, i32, i32), p: Vector3| p.x * axis.0 as f32 + p.y * axis.1 as f32 + p.z * axis.2 as f32;
+        let fu = (along(axis_u, point) + 0.5 - along(axis_u, front).round()).clamp(0.0, 1.0);
+        let fv = (along(axis_v, point) + 0.5 - along(axis_v, front).round()).clamp(0.0, 1.0);
+
+        let offset = |c: (i32, i32, i32), axis: (i32, i32, i32), sign: i32| (c.0 + axis.0 * sign, c.1 + axis.1 * sign, c.2 + axis.2 * sign);
+        let corner = |su: i32, sv: i32| {
+            let side_u = self.is_occupied(offset(cell, axis_u, su));
+            let side_v = self.is_occupied(offset(cell, axis_v, sv));
+            let diagonal = self.is_occupied(offset(offset(cell, axis_u, su), axis_v, sv));
+            let level = if side_u && side_v { 0 } else { 3 - side_u as usize - side_v as usize - diagonal as usize };
+            VOXEL_AO_LEVELS[level]
+        };
+
+        let bottom = corner(-1, -1) * (1.0 - fu) + corner(1, -1) * fu;
+        let top = corner(-1, 1) * (1.0 - fu) + corner(1, 1) * fu;
+        Some(bottom * (1.0 - fv) + top * fv)
+    }
+}
+
+/// Fracción en [0, 1] de rayos del hemisferio que no chocan con nada a menos de `max_distance`,
+/// con la escena en el instante `time` del fotograma.
+pub fn hemisphere_occlusion(
+    point: Vector3,
+    normal: Vector3,
+    time: f32,
+    objects: &[Box<dyn RayIntersect>],
+    samples: u32,
+    max_distance: f32,
+) -> f32 {
+    let origin = point + normal * 1e-3;
+    let samples = samples.max(1);
+    let mut unoccluded = 0;
+    for _ in 0..samples {
+        let direction = cosine_sample_hemisphere(normal);
+        let blocked = objects.iter().any(|object| {
+            let hit = object.ray_intersect(&origin, &direction, time);
+            hit.is_intersecting && hit.distance < max_distance
+        });
+        if !blocked {
+            unoccluded += 1;
+        }
+    }
+    unoccluded as f32 / samples as f32
+}
+
+/// Oclusión ambiental de un punto según el modo elegido (1.0 = sin oclusión).
+pub fn ambient_occlusion(
+    mode: AoMode,
+    point: Vector3,
+    normal: Vector3,
+    time: f32,
+    objects: &[Box<dyn RayIntersect>],
+    voxels: &VoxelGrid,
+) -> f32 {
+    match mode {
+        AoMode::Off => 1.0,
+        AoMode::Hemisphere { samples, max_distance } => hemisphere_occlusion(point, normal, time, objects, samples, max_distance),
+        AoMode::VoxelNeighbours => voxels
+            .occlusion(point, normal)
+            .unwrap_or_else(|| hemisphere_occlusion(point, normal, time, objects, VOXEL_FALLBACK_SAMPLES, VOXEL_FALLBACK_DISTANCE)),
+    }
+}
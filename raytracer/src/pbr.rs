@@ -0,0 +1,130 @@
+// pbr.rs
+use raylib::prelude::*;
+use std::f32::consts::PI;
+
+/// Parámetros del modelo físico metallic-roughness (como en glTF 2.0).
+/// El color base puede venir de `Material::texture` y la emisión de `Material::emission`.
+#[derive(Debug, Clone)]
+pub struct PbrMaterial {
+    pub base_color: Vector3, // Albedo para dieléctricos, color del reflejo para metales
+    pub metallic: f32,       // 0.0 dieléctrico, 1.0 metal
+    pub roughness: f32,      // 0.0 espejo, 1.0 completamente mate
+    pub ior: f32,            // índice de refracción, determina F0 de los dieléctricos
+    pub transmission: f32,   // fracción de luz que atraviesa la superficie (vidrio, agua)
+    pub metallic_roughness_texture: Option<String>, // canal G = rugosidad, canal B = metalicidad
+}
+
+impl PbrMaterial {
+    pub fn new(base_color: Vector3, metallic: f32, roughness: f32) -> Self {
+        PbrMaterial {
+            base_color,
+            metallic,
+            roughness,
+            ior: 1.5,
+            transmission: 0.0,
+            metallic_roughness_texture: None,
+        }
+    }
+}
+
+/// Reflectancia a incidencia normal: la de un dieléctrico con ese IOR mezclada con el color base según `metallic`.
+pub fn base_reflectance(base_color: Vector3, metallic: f32, ior: f32) -> Vector3 {
+    let r = (ior - 1.0) / (ior + 1.0);
+    let dielectric = r * r;
+    Vector3::new(dielectric, dielectric, dielectric) * (1.0 - metallic) + base_color * metallic
+}
+
+pub fn fresnel_schlick(f0: Vector3, cos_theta: f32) -> Vector3 {
+    let factor = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    f0 + (Vector3::one() - f0) * factor
+}
+
+/// Distribución normal de microfacetas GGX / Trowbridge-Reitz.
+pub fn distribution_ggx(n_dot_h: f32, alpha: f32) -> f32 {
+    let a2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    a2 / (PI * d * d)
+}
+
+/// Término de visibilidad de Smith correlacionado en altura: G / (4 · n·l · n·v).
+pub fn visibility_smith_ggx(n_dot_v: f32, n_dot_l: f32, alpha: f32) -> f32 {
+    let a2 = alpha * alpha;
+    let ggx_v = n_dot_l * (n_dot_v * n_dot_v * (1.0 - a2) + a2).sqrt();
+    let ggx_l = n_dot_v * (n_dot_l * n_dot_l * (1.0 - a2) + a2).sqrt();
+    0.5 / (ggx_v + ggx_l).max(1e-6)
+}
+
+/// Convierte la rugosidad perceptual en el parámetro alpha de GGX.
+pub fn roughness_to_alpha(roughness: f32) -> f32 {
+    let roughness = roughness.clamp(0.045, 1.0);
+    roughness * roughness
+}
+
+/// Parámetros PBR ya evaluados en un punto concreto (con texturas aplicadas).
+#[derive(Debug, Clone, Copy)]
+pub struct PbrSurface {
+    pub base_color: Vector3,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub transmission: f32,
+    pub f0: Vector3,
+}
+
+impl PbrSurface {
+    /// `base_color` ya incluye la textura de color; `metallic_roughness` es el texel (R, G, B)
+    /// de la textura metallic-roughness, o blanco si no hay.
+    pub fn new(material: &PbrMaterial, base_color: Vector3, metallic_roughness: Vector3) -> Self {
+        let metallic = (material.metallic * metallic_roughness.z).clamp(0.0, 1.0);
+        let roughness = (material.roughness * metallic_roughness.y).clamp(0.0, 1.0);
+        PbrSurface {
+            base_color,
+            metallic,
+            roughness,
+            transmission: material.transmission.clamp(0.0, 1.0),
+            f0: base_reflectance(base_color, metallic, material.ior),
+        }
+    }
+
+    /// Peso de la componente difusa (lo que no se refleja ni se transmite).
+    pub fn diffuse_weight(&self, fresnel: Vector3) -> Vector3 {
+        (Vector3::one() - fresnel) * ((1.0 - self.metallic) * (1.0 - self.transmission))
+    }
+
+    /// Luz reflejada hacia `view` por una luz de intensidad unitaria en `light_direction`.
+    /// Se multiplica por π para que, igual que en el modelo clásico, una superficie blanca
+    /// mate de frente a una luz de intensidad 1 devuelva 1.
+    pub fn direct_lighting(&self, normal: Vector3, view: Vector3, light_direction: Vector3) -> Vector3 {
+        let n_dot_l = normal.dot(light_direction);
+        let n_dot_v = normal.dot(view).max(1e-4);
+        if n_dot_l <= 0.0 {
+            return Vector3::zero();
+        }
+        let half = (view + light_direction).normalized();
+        let n_dot_h = normal.dot(half).max(0.0);
+        let v_dot_h = view.dot(half).max(0.0);
+        let alpha = roughness_to_alpha(self.roughness);
+
+        let fresnel = fresnel_schlick(self.f0, v_dot_h);
+        let specular = fresnel * (distribution_ggx(n_dot_h, alpha) * visibility_smith_ggx(n_dot_v, n_dot_l, alpha));
+        let diffuse = self.diffuse_weight(fresnel) * self.base_color / PI;
+
+        (diffuse + specular) * (n_dot_l * PI)
+    }
+}
+
+/// Integral precalculada del BRDF especular sobre el hemisferio (aproximación analítica de Karis),
+/// para ponderar la luz del entorno: devuelve F0 · A + B.
+pub fn environment_brdf(f0: Vector3, roughness: f32, n_dot_v: f32) -> Vector3 {
+    let c0 = [-1.0, -0.0275, -0.572, 0.022];
+    let c1 = [1.0, 0.0425, 1.04, -0.04];
+    let r = [
+        roughness * c0[0] + c1[0],
+        roughness * c0[1] + c1[1],
+        roughness * c0[2] + c1[2],
+        roughness * c0[3] + c1[3],
+    ];
+    let a004 = (r[0] * r[0]).min((-9.28 * n_dot_v).exp2()) * r[0] + r[1];
+    let scale = -1.04 * a004 + r[2];
+    let bias = 1.04 * a004 + r[3];
+    f0 * scale + Vector3::new(bias, bias, bias)
+}
@@ -0,0 +1,80 @@
+// plane.rs
+use raylib::prelude::Vector3;
+use crate::ray_intersect::{Intersect, RayIntersect, Span, wrap_uv};
+use crate::material::Material;
+use crate::sampling::orthonormal_basis;
+
+/// Plano infinito que pasa por `point`. Se ve por las dos caras.
+/// En CSG es el semiespacio sólido del lado contrario a la normal.
+pub struct Plane {
+    pub point: Vector3,
+    pub normal: Vector3,
+    pub material: Material,
+    pub texture_scale: f32, // Unidades del mundo que ocupa cada repetición de la textura
+}
+
+impl Plane {
+    pub fn new(point: Vector3, normal: Vector3, material: Material) -> Self {
+        Plane {
+            point,
+            normal: normal.normalized(),
+            material,
+            texture_scale: 1.0,
+        }
+    }
+}
+
+impl RayIntersect for Plane {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Intersect {
+        let denominator = self.normal.dot(*ray_direction);
+        if denominator.abs() < 1e-8 {
+            return Intersect::empty();
+        }
+        let distance = self.normal.dot(self.point - *ray_origin) / denominator;
+        if distance < 0.001 {
+            return Intersect::empty();
+        }
+
+        let point = *ray_origin + *ray_direction * distance;
+        // La normal apunta siempre hacia el lado desde el que llega el rayo
+        let normal = if denominator > 0.0 { -self.normal } else { self.normal };
+
+        // La textura se repite sobre una base fija del plano
+        let (tangent, bitangent) = orthonormal_basis(self.normal);
+        let local = point - self.point;
+        let u = wrap_uv(local.dot(tangent) / self.texture_scale);
+        let v = wrap_uv(local.dot(bitangent) / self.texture_scale);
+
+        Intersect::new(self.material.clone(), distance, normal, point, u, v)
+            .with_tangent_frame(tangent * self.texture_scale, bitangent * self.texture_scale)
+    }
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Vec<Span> {
+        let denominator = self.normal.dot(*ray_direction);
+        let height = self.normal.dot(*ray_origin - self.point);
+        if denominator.abs() < 1e-8 {
+            // Paralelo: el rayo está entero dentro o entero fuera
+            return if height < 0.0 {
+                vec![Span { enter: Intersect::at_infinity(f32::NEG_INFINITY), exit: Intersect::at_infinity(f32::INFINITY) }]
+            } else {
+                Vec::new()
+            };
+        }
+
+        let distance = -height / denominator;
+        let point = *ray_origin + *ray_direction * distance;
+        let (tangent, bitangent) = orthonormal_basis(self.normal);
+        let local = point - self.point;
+        let u = wrap_uv(local.dot(tangent) / self.texture_scale);
+        let v = wrap_uv(local.dot(bitangent) / self.texture_scale);
+        // La cara del semiespacio mira siempre hacia fuera
+        let surface = Intersect::new(self.material.clone(), distance, self.normal, point, u, v)
+            .with_tangent_frame(tangent * self.texture_scale, bitangent * self.texture_scale);
+
+        let span = if denominator < 0.0 {
+            Span { enter: surface, exit: Intersect::at_infinity(f32::INFINITY) }
+        } else {
+            Span { enter: Intersect::at_infinity(f32::NEG_INFINITY), exit: surface }
+        };
+        vec![span]
+    }
+}
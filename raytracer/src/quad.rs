@@ -0,0 +1,64 @@
+// quad.rs
+use raylib::prelude::Vector3;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::material::Material;
+use crate::aabb::Aabb;
+use crate::area_light::{AreaLight, EmissivePatch};
+
+/// Paralelogramo `corner + edge_u * u + edge_v * v` con u, v en [0, 1] (un rectángulo si los
+/// lados son perpendiculares). La textura cubre el quad una vez. Se ve por las dos caras;
+/// su normal "delantera" es `edge_u × edge_v`.
+pub struct Quad {
+    pub corner: Vector3,
+    pub edge_u: Vector3,
+    pub edge_v: Vector3,
+    pub material: Material,
+}
+
+impl Quad {
+    pub fn new(corner: Vector3, edge_u: Vector3, edge_v: Vector3, material: Material) -> Self {
+        Quad { corner, edge_u, edge_v, material }
+    }
+
+    pub fn normal(&self) -> Vector3 {
+        self.edge_u.cross(self.edge_v).normalized()
+    }
+}
+
+impl RayIntersect for Quad {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Intersect {
+        let n = self.edge_u.cross(self.edge_v);
+        let denominator = n.dot(*ray_direction);
+        if denominator.abs() < 1e-8 {
+            return Intersect::empty();
+        }
+        let distance = n.dot(self.corner - *ray_origin) / denominator;
+        if distance < 0.001 {
+            return Intersect::empty();
+        }
+
+        // Coordenadas del punto en la base (edge_u, edge_v)
+        let point = *ray_origin + *ray_direction * distance;
+        let w = n / n.dot(n);
+        let local = point - self.corner;
+        let u = w.dot(local.cross(self.edge_v));
+        let v = w.dot(self.edge_u.cross(local));
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return Intersect::empty();
+        }
+
+        let normal = n.normalized();
+        let normal = if denominator > 0.0 { -normal } else { normal };
+        Intersect::new(self.material.clone(), distance, normal, point, u, v)
+            .with_tangent_frame(self.edge_u, self.edge_v)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let c = self.corner;
+        Some(Aabb::from_points(&[c, c + self.edge_u, c + self.edge_v, c + self.edge_u + self.edge_v]))
+    }
+
+    fn emissive_patches(&self) -> Vec<EmissivePatch> {
+        AreaLight::from_quad(self).map(|light| light.patches).unwrap_or_default()
+    }
+}
@@ -1,6 +1,9 @@
 // ray_intersect.rs
 use raylib::prelude::{Color, Vector3};
+use crate::aabb::Aabb;
+use crate::area_light::EmissivePatch;
 use crate::material::Material;
+use crate::sampling::orthonormal_basis;
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct Intersect {
@@ -11,9 +14,14 @@ pub struct Intersect {
     pub point: Vector3,
     pub u: f32,
     pub v: f32,
+    pub tangent: Vector3,   // dP/du: dirección en la que crece u sobre la superficie
+    pub bitangent: Vector3, // dP/dv: dirección en la que crece v sobre la superficie
 }
 impl Intersect {
+    /// Crea una intersección con una base tangente arbitraria alrededor de la normal.
+    /// Las primitivas con UVs deben indicar la suya con `with_tangent_frame`.
     pub fn new(material: Material, distance: f32, normal: Vector3, point: Vector3, u: f32, v: f32) -> Self {
+        let (tangent, bitangent) = orthonormal_basis(normal);
         Intersect {
             material,
             distance,
@@ -22,30 +30,109 @@ impl Intersect {
             point,
             u,
             v,
+            tangent,
+            bitangent,
         }
     }
+
+    /// Sustituye la base tangente por las derivadas de la parametrización UV de la primitiva.
+    pub fn with_tangent_frame(mut self, tangent: Vector3, bitangent: Vector3) -> Self {
+        self.tangent = tangent;
+        self.bitangent = bitangent;
+        self
+    }
     pub fn empty() -> Self {
         Intersect {
-            material: Material {
-                diffuse: Vector3::zero(),
-                albedo: [0.0, 0.0],
-                specular: 0.0,
-                reflectivity: 0.0,
-                transparency: 0.0,
-                refractive_index: 0.0,
-                texture: None,
-                normal_map_id: None,
-                emission: Vector3::zero(), // <-- CAMPO AÑADIDO
-            },
+            material: Material::black(),
             distance: 0.0,
             is_intersecting: false,
             normal: Vector3::zero(),
             point: Vector3::zero(),
             u: 0.0,
             v: 0.0,
+            tangent: Vector3::zero(),
+            bitangent: Vector3::zero(),
         }
     }
+
+    /// Extremo de un tramo que no se cierra (el rayo empieza dentro o el sólido no tiene fin).
+    pub fn at_infinity(distance: f32) -> Self {
+        Intersect { distance, ..Intersect::empty() }
+    }
+}
+
+/// Tramo del rayo dentro de un sólido: dónde entra y dónde sale. Las distancias pueden ser
+/// negativas (detrás del origen) o infinitas; las normales de los extremos apuntan hacia fuera.
+#[derive(Debug, Clone)]
+pub struct Span {
+    pub enter: Intersect,
+    pub exit: Intersect,
+}
+
+/// Máximo de cruces que se buscan al recorrer un rayo con `walk_intervals`.
+const MAX_CROSSINGS: usize = 32;
+
+/// Las primitivas deben poder compartirse entre los hilos de rayon. Cada rayo lleva su instante
+/// `time` dentro del fotograma (0 = inicio, 1 = final), con el que se colocan los objetos que se
+/// mueven; los demás lo ignoran.
+pub trait RayIntersect: Send + Sync {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Intersect;
+
+    /// Todos los tramos del rayo dentro del objeto, ordenados (para CSG). Por defecto se
+    /// recorre el rayo de impacto en impacto y se decide por la normal si entra o sale, lo que
+    /// solo tiene sentido para superficies cerradas con normales hacia fuera.
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Vec<Span> {
+        walk_intervals(self, ray_origin, ray_direction, time)
+    }
+
+    /// Caja que envuelve la primitiva; `None` si no está acotada (un plano infinito).
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+
+    /// Superficies que emiten luz y que `Scene` muestrea como luces de área. Por defecto
+    /// ninguna: la emisión de las primitivas que no la indican solo se ve, no ilumina.
+    fn emissive_patches(&self) -> Vec<EmissivePatch> {
+        Vec::new()
+    }
+}
+
+/// Lleva una coordenada de textura a [0, 1] repitiendo la textura (los valores ya dentro no cambian).
+pub fn wrap_uv(value: f32) -> f32 {
+    if (0.0..=1.0).contains(&value) { value } else { value.rem_euclid(1.0) }
+}
+
+/// Tramos de un objeto cualquiera buscando impactos sucesivos a partir del origen.
+/// Si el primer impacto es una salida, el origen está dentro y el tramo empieza en -∞.
+pub fn walk_intervals<T: RayIntersect + ?Sized>(object: &T, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut enter: Option<Intersect> = None;
+    let mut travelled = 0.0;
+    for _ in 0..MAX_CROSSINGS {
+        let origin = *ray_origin + *ray_direction * travelled;
+        let mut hit = object.ray_intersect(&origin, ray_direction, time);
+        if !hit.is_intersecting {
+            break;
+        }
+        hit.distance += travelled;
+        travelled = hit.distance;
+        if hit.normal.dot(*ray_direction) < 0.0 {
+            enter = Some(hit);
+        } else {
+            let enter = enter.take().unwrap_or_else(|| Intersect::at_infinity(f32::NEG_INFINITY));
+            spans.push(Span { enter, exit: hit });
+        }
+    }
+    if let Some(enter) = enter {
+        spans.push(Span { enter, exit: Intersect::at_infinity(f32::INFINITY) });
+    }
+    spans
+}
+
+/// Empareja las distancias ordenadas en las que un rayo cruza un sólido cerrado
+/// (entrada, salida, entrada, …) en tramos.
+pub fn spans_from_hits(hits: &[f32], hit_at: impl Fn(f32) -> Intersect) -> Vec<Span> {
+    hits.chunks_exact(2)
+        .map(|pair| Span { enter: hit_at(pair[0]), exit: hit_at(pair[1]) })
+        .collect()
 }
-pub trait RayIntersect {
-    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect;
-}
\ No newline at end of file
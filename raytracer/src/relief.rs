@@ -0,0 +1,87 @@
+// relief.rs
+use raylib::prelude::*;
+
+use crate::ray_intersect::Intersect;
+use crate::textures::TextureManager;
+
+/// Número de capas del parallax occlusion mapping mirando de frente y en ángulo rasante.
+const MIN_PARALLAX_LAYERS: f32 = 8.0;
+const MAX_PARALLAX_LAYERS: f32 = 32.0;
+
+/// Base tangente ortonormal (tangente, bitangente) alrededor de `normal`, derivada de la
+/// parametrización UV de la intersección y conservando su orientación.
+pub fn shading_frame(intersect: &Intersect, normal: Vector3) -> (Vector3, Vector3) {
+    let tangent = (intersect.tangent - normal * normal.dot(intersect.tangent)).normalized();
+    let handedness = if normal.cross(tangent).dot(intersect.bitangent) < 0.0 { -1.0 } else { 1.0 };
+    (tangent, normal.cross(tangent) * handedness)
+}
+
+/// Profundidad en [0, 1] bajo la superficie según el mapa de alturas.
+fn depth_at(texture_manager: &TextureManager, path: &str, u: f32, v: f32, height_scale: f32) -> f32 {
+    let height = texture_manager.sample_height(path, u, v);
+    if height_scale >= 0.0 { 1.0 - height } else { height }
+}
+
+/// Desplaza las UVs de la intersección recorriendo el relieve a lo largo del rayo de vista
+/// (parallax occlusion mapping). `view_direction` apunta desde la superficie hacia el observador.
+pub fn parallax_occlusion_uv(
+    texture_manager: &TextureManager,
+    path: &str,
+    intersect: &Intersect,
+    view_direction: Vector3,
+    height_scale: f32,
+) -> (f32, f32) {
+    let normal = intersect.normal;
+    let (tangent, bitangent) = shading_frame(intersect, normal);
+    let view = Vector3::new(view_direction.dot(tangent), view_direction.dot(bitangent), view_direction.dot(normal));
+    if view.z <= 1e-3 || height_scale == 0.0 {
+        return (intersect.u, intersect.v);
+    }
+
+    // Más capas en ángulos rasantes, donde el desplazamiento es mayor.
+    let layers = MAX_PARALLAX_LAYERS + (MIN_PARALLAX_LAYERS - MAX_PARALLAX_LAYERS) * view.z;
+    let layer_depth = 1.0 / layers;
+    let shift = height_scale.abs() / view.z / layers;
+    let (du, dv) = (view.x * shift, view.y * shift);
+
+    let (mut u, mut v) = (intersect.u, intersect.v);
+    let mut current_layer = 0.0;
+    let mut current_depth = depth_at(texture_manager, path, u, v, height_scale);
+    while current_layer < current_depth && current_layer < 1.0 {
+        u -= du;
+        v -= dv;
+        current_layer += layer_depth;
+        current_depth = depth_at(texture_manager, path, u, v, height_scale);
+    }
+
+    // Interpolación lineal entre la última capa por encima y la primera por debajo del relieve.
+    let (previous_u, previous_v) = (u + du, v + dv);
+    let after = current_depth - current_layer;
+    let before = depth_at(texture_manager, path, previous_u, previous_v, height_scale) - (current_layer - layer_depth);
+    let weight = if (after - before).abs() > 1e-6 { (after / (after - before)).clamp(0.0, 1.0) } else { 0.0 };
+    let u = previous_u * weight + u * (1.0 - weight);
+    let v = previous_v * weight + v * (1.0 - weight);
+    (u.rem_euclid(1.0), v.rem_euclid(1.0))
+}
+
+/// Perturba `normal` con el gradiente del mapa de alturas en (u, v) (bump mapping).
+pub fn bump_normal(
+    texture_manager: &TextureManager,
+    path: &str,
+    intersect: &Intersect,
+    normal: Vector3,
+    height_scale: f32,
+) -> Vector3 {
+    let Some((width, height)) = texture_manager.texture_size(path) else {
+        return normal;
+    };
+    let (u, v) = (intersect.u, intersect.v);
+    let step_u = 1.0 / width as f32;
+    let step_v = 1.0 / height as f32;
+    // Diferencias centradas: pendiente de la altura respecto a u y v
+    let dh_du = (texture_manager.sample_height(path, u + step_u, v) - texture_manager.sample_height(path, u - step_u, v)) / (2.0 * step_u);
+    let dh_dv = (texture_manager.sample_height(path, u, v + step_v) - texture_manager.sample_height(path, u, v - step_v)) / (2.0 * step_v);
+
+    let (tangent, bitangent) = shading_frame(intersect, normal);
+    (normal - tangent * (dh_du * height_scale) - bitangent * (dh_dv * height_scale)).normalized()
+}
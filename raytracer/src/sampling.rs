@@ -0,0 +1,141 @@
+// sampling.rs
+use raylib::prelude::*;
+use std::cell::Cell;
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Cada hilo de rayon obtiene su propio generador con una semilla distinta.
+static NEXT_SEED: AtomicU64 = AtomicU64::new(0x9E37_79B9_7F4A_7C15);
+
+thread_local! {
+    static RNG_STATE: Cell<u64> = Cell::new(splitmix64(NEXT_SEED.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)));
+}
+
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Número aleatorio uniforme en [0, 1) del generador del hilo actual (xorshift64*).
+pub fn random_f32() -> f32 {
+    RNG_STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        let bits = x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 40;
+        bits as f32 / (1u64 << 24) as f32
+    })
+}
+
+/// Construye una base ortonormal (tangente, bitangente) alrededor de `normal` sin singularidades.
+pub fn orthonormal_basis(normal: Vector3) -> (Vector3, Vector3) {
+    let sign = if normal.z >= 0.0 { 1.0 } else { -1.0 };
+    let a = -1.0 / (sign + normal.z);
+    let b = normal.x * normal.y * a;
+    let tangent = Vector3::new(1.0 + sign * normal.x * normal.x * a, sign * b, -sign * normal.x);
+    let bitangent = Vector3::new(b, sign + normal.y * normal.y * a, -normal.y);
+    (tangent, bitangent)
+}
+
+/// Dirección en el hemisferio de `normal` con densidad proporcional al coseno.
+pub fn cosine_sample_hemisphere(normal: Vector3) -> Vector3 {
+    let r1 = random_f32();
+    let r2 = random_f32();
+    let r = r1.sqrt();
+    let phi = 2.0 * PI * r2;
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    (tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + normal * (1.0 - r1).max(0.0).sqrt()).normalized()
+}
+
+/// Dirección uniforme dentro de un cono de semiángulo `cos_max` alrededor de `axis`.
+pub fn uniform_sample_cone(axis: Vector3, cos_max: f32) -> Vector3 {
+    let cos_theta = 1.0 - random_f32() * (1.0 - cos_max);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * random_f32();
+    let (tangent, bitangent) = orthonormal_basis(axis);
+    (tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + axis * cos_theta).normalized()
+}
+
+/// Normal de microfaceta muestreada de la distribución GGX de parámetro `alpha` alrededor de `normal`.
+pub fn sample_ggx_microfacet(normal: Vector3, alpha: f32) -> Vector3 {
+    let r1 = random_f32();
+    let r2 = random_f32();
+    let tan_theta = alpha * (r1 / (1.0 - r1).max(1e-6)).sqrt();
+    let cos_theta = 1.0 / (1.0 + tan_theta * tan_theta).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * r2;
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    (tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + normal * cos_theta).normalized()
+}
+
+/// Punto uniforme en el disco unidad (x, y).
+pub fn uniform_sample_disk() -> (f32, f32) {
+    let r = random_f32().sqrt();
+    let phi = 2.0 * PI * random_f32();
+    (r * phi.cos(), r * phi.sin())
+}
+
+/// Punto uniforme en el polígono regular de `sides` lados inscrito en el círculo unidad, con un
+/// vértice girado `rotation` radianes desde el eje X: se elige uno de sus triángulos (todos de la
+/// misma área) y un punto uniforme dentro de él.
+pub fn uniform_sample_polygon(sides: u32, rotation: f32) -> (f32, f32) {
+    let sides = sides.max(3);
+    let index = ((random_f32() * sides as f32) as u32).min(sides - 1);
+    let angle = |i: u32| rotation + 2.0 * PI * i as f32 / sides as f32;
+    let (a, b) = (angle(index), angle(index + 1));
+    // Coordenadas baricéntricas uniformes respecto al centro y los dos vértices
+    let (r1, r2) = (random_f32().sqrt(), random_f32());
+    let (wa, wb) = (r1 * (1.0 - r2), r1 * r2);
+    (wa * a.cos() + wb * b.cos(), wa * a.sin() + wb * b.sin())
+}
+
+/// Distribución discreta 1D para muestreo por importancia (tabla de distribución acumulada).
+pub struct Distribution1D {
+    cdf: Vec<f32>,
+    pub total: f32,
+}
+
+impl Distribution1D {
+    pub fn new(weights: &[f32]) -> Self {
+        let mut cdf = Vec::with_capacity(weights.len() + 1);
+        let mut total = 0.0;
+        cdf.push(0.0);
+        for w in weights {
+            total += w.max(0.0);
+            cdf.push(total);
+        }
+        if total > 0.0 {
+            for c in &mut cdf {
+                *c /= total;
+            }
+        } else {
+            // Sin pesos útiles se recurre a una distribución uniforme.
+            let n = weights.len().max(1) as f32;
+            for (i, c) in cdf.iter_mut().enumerate() {
+                *c = i as f32 / n;
+            }
+        }
+        Distribution1D { cdf, total }
+    }
+
+    pub fn count(&self) -> usize {
+        self.cdf.len() - 1
+    }
+
+    /// Devuelve (índice elegido, posición continua dentro de la celda en [0, 1), probabilidad discreta).
+    pub fn sample(&self, r: f32) -> (usize, f32, f32) {
+        let index = self.cdf.partition_point(|&c| c <= r).clamp(1, self.count()) - 1;
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0.0 { ((r - self.cdf[index]) / width).clamp(0.0, 0.999_999) } else { 0.5 };
+        (index, offset, width)
+    }
+
+    /// Probabilidad discreta de la celda `index`.
+    pub fn probability(&self, index: usize) -> f32 {
+        self.cdf[index + 1] - self.cdf[index]
+    }
+}
@@ -0,0 +1,87 @@
+// scene.rs
+use raylib::prelude::Vector3;
+use std::sync::Arc;
+
+use crate::area_light::AreaLight;
+use crate::cube::Cube;
+use crate::instance::{Blas, Instance, Tlas};
+use crate::light::Light;
+use crate::occlusion::VoxelGrid;
+use crate::quad::Quad;
+use crate::ray_intersect::RayIntersect;
+use crate::transform::Transform;
+
+/// Todo lo que se renderiza: la geometría y las luces, junto con las estructuras que
+/// se derivan de ella (luces de área de los objetos emisivos y rejilla de bloques para la AO).
+/// Mientras se construye, los objetos sueltos van en `objects` y las copias de geometría
+/// compartida en `instances`; `build_tlas` los reúne en la jerarquía de dos niveles que se traza.
+#[derive(Default)]
+pub struct Scene {
+    pub objects: Vec<Box<dyn RayIntersect>>,
+    pub instances: Vec<Instance>,
+    pub lights: Vec<Light>,
+    pub area_lights: Vec<AreaLight>,
+    pub voxels: VoxelGrid,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Scene::default()
+    }
+
+    /// Añade cualquier primitiva. Las superficies emisivas que indica (`emissive_patches`: cubos,
+    /// quads, triángulos, mallas y sus instancias) se convierten en luz de área; la emisión de
+    /// las demás (planos infinitos, esferas, SDF...) se ve pero no ilumina a los demás objetos.
+    pub fn add(&mut self, object: impl RayIntersect + 'static) {
+        if let Some(area_light) = AreaLight::from_patches(object.emissive_patches()) {
+            self.area_lights.push(area_light);
+        }
+        self.objects.push(Box::new(object));
+    }
+
+    /// Añade un bloque: si emite luz se convierte en luz de área y, si es opaco, ocluye en la rejilla.
+    pub fn add_cube(&mut self, cube: Cube) {
+        self.voxels.add_cube(&cube);
+        self.add(cube);
+    }
+
+    /// Añade una instancia de una geometría compartida; sus emisores también son luces de área.
+    pub fn add_instance(&mut self, instance: Instance) {
+        if let Some(area_light) = AreaLight::from_patches(instance.emissive_patches()) {
+            self.area_lights.push(area_light);
+        }
+        self.instances.push(instance);
+    }
+
+    /// Añade un bloque como instancia de `block` (un cubo unidad centrado en el origen que
+    /// comparten todos los bloques del mismo tipo), con su centro y su tamaño en el mundo.
+    pub fn add_block(&mut self, block: &Arc<Cube>, center: Vector3, size: f32) {
+        let transform = Transform::scale(Vector3::new(size, size, size)).translated(center);
+        self.voxels.add_box(transform.point(block.min_bounds), transform.point(block.max_bounds), &block.material);
+        self.add_instance(Instance::new(block.clone(), transform));
+    }
+
+    /// Prepara la escena para trazarla: las instancias y una instancia más con todos los objetos
+    /// sueltos con caja (en su propia BLAS) pasan a una única TLAS. Solo los objetos sin caja
+    /// (planos infinitos) se siguen probando uno a uno. Se llama una vez, con la escena completa.
+    pub fn build_tlas(&mut self) {
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = std::mem::take(&mut self.objects).into_iter().partition(|object| object.bounding_box().is_some());
+        let mut instances = std::mem::take(&mut self.instances);
+        if !bounded.is_empty() {
+            instances.push(Instance::new(Arc::new(Blas::new("objects", bounded)), Transform::identity()));
+        }
+        self.objects = unbounded;
+        if !instances.is_empty() {
+            self.objects.push(Box::new(Tlas::new(instances)));
+        }
+    }
+
+    /// Añade un quad; si emite luz (p. ej. un panel luminoso) se convierte en luz de área.
+    pub fn add_quad(&mut self, quad: Quad) {
+        self.add(quad);
+    }
+
+    pub fn add_light(&mut self, light: Light) {
+        self.lights.push(light);
+    }
+}
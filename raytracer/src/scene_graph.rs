@@ -0,0 +1,214 @@
+// scene_graph.rs
+use std::sync::Arc;
+
+use crate::instance::Instance;
+use crate::light::{Light, Spot};
+use crate::material::Material;
+use crate::motion::MotionTransform;
+use crate::ray_intersect::RayIntersect;
+use crate::scene::Scene;
+use crate::transform::Transform;
+
+/// Identificador de un nodo dentro de su `SceneGraph`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// Nodo del grafo: una transformación relativa a su padre y, opcionalmente, geometría y luces
+/// en su propio espacio. Un nodo sin geometría es un grupo.
+pub struct SceneNode {
+    pub name: String,
+    pub transform: Transform,
+    /// Movimiento relativo al padre durante el fotograma; si existe, sustituye a `transform`
+    /// (las luces del nodo se quedan donde están al principio del fotograma).
+    pub motion: Option<MotionTransform>,
+    /// Un nodo oculto no se dibuja, y sus hijos tampoco.
+    pub visible: bool,
+    pub geometry: Option<Arc<dyn RayIntersect>>,
+    /// Material que sustituye al de la geometría en este nodo y en los hijos que no tengan otro.
+    pub material: Option<Arc<Material>>,
+    pub lights: Vec<Light>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+/// Escena jerárquica con nodos con nombre. Mover u ocultar un grupo afecta a todo lo que
+/// cuelga de él; `flatten` la convierte en las estructuras que usa el render (instancias en una
+/// TLAS y luces en coordenadas del mundo).
+pub struct SceneGraph {
+    nodes: Vec<SceneNode>,
+}
+
+impl Default for SceneGraph {
+    fn default() -> Self {
+        SceneGraph::new()
+    }
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        SceneGraph {
+            nodes: vec![SceneNode {
+                name: "root".to_string(),
+                transform: Transform::identity(),
+                motion: None,
+                visible: true,
+                geometry: None,
+                material: None,
+                lights: Vec::new(),
+                parent: None,
+                children: Vec::new(),
+            }],
+        }
+    }
+
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    fn add_node(&mut self, parent: NodeId, name: &str, transform: Transform) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(SceneNode {
+            name: name.to_string(),
+            transform,
+            motion: None,
+            visible: true,
+            geometry: None,
+            material: None,
+            lights: Vec::new(),
+            parent: Some(parent),
+            children: Vec::new(),
+        });
+        self.nodes[parent.0].children.push(id);
+        id
+    }
+
+    /// Añade un grupo vacío para colgar otros nodos.
+    pub fn add_group(&mut self, parent: NodeId, name: &str, transform: Transform) -> NodeId {
+        self.add_node(parent, name, transform)
+    }
+
+    /// Añade una primitiva que solo usa este nodo.
+    pub fn add_object(&mut self, parent: NodeId, name: &str, object: impl RayIntersect + 'static, transform: Transform) -> NodeId {
+        self.add_instance(parent, name, Arc::new(object), transform)
+    }
+
+    /// Añade una geometría que pueden compartir varios nodos.
+    pub fn add_instance(&mut self, parent: NodeId, name: &str, geometry: Arc<dyn RayIntersect>, transform: Transform) -> NodeId {
+        let id = self.add_node(parent, name, transform);
+        self.nodes[id.0].geometry = Some(geometry);
+        id
+    }
+
+    /// Añade una luz; su posición es relativa al padre.
+    pub fn add_light(&mut self, parent: NodeId, name: &str, light: Light) -> NodeId {
+        let id = self.add_node(parent, name, Transform::identity());
+        self.nodes[id.0].lights.push(light);
+        id
+    }
+
+    pub fn node(&self, id: NodeId) -> &SceneNode {
+        &self.nodes[id.0]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut SceneNode {
+        &mut self.nodes[id.0]
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].parent
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id.0].children
+    }
+
+    /// Primer nodo (en orden de creación) con ese nombre.
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|node| node.name == name).map(NodeId)
+    }
+
+    /// Busca siguiendo una ruta de nombres separados por `/` desde la raíz, p. ej. `"casa/puerta"`.
+    pub fn find_path(&self, path: &str) -> Option<NodeId> {
+        path.split('/').filter(|name| !name.is_empty()).try_fold(self.root(), |parent, name| {
+            self.children(parent).iter().copied().find(|child| self.nodes[child.0].name == name)
+        })
+    }
+
+    pub fn set_visible(&mut self, id: NodeId, visible: bool) {
+        self.nodes[id.0].visible = visible;
+    }
+
+    pub fn set_transform(&mut self, id: NodeId, transform: Transform) {
+        self.nodes[id.0].transform = transform;
+    }
+
+    /// Transformación del nodo al mundo (la suya seguida de la de cada antepasado).
+    pub fn world_transform(&self, id: NodeId) -> Transform {
+        let mut transform = self.nodes[id.0].transform;
+        let mut current = self.nodes[id.0].parent;
+        while let Some(parent) = current {
+            transform = transform.then(&self.nodes[parent.0].transform);
+            current = self.nodes[parent.0].parent;
+        }
+        transform
+    }
+
+    /// Si el nodo y todos sus antepasados están visibles.
+    pub fn is_visible(&self, id: NodeId) -> bool {
+        let mut current = Some(id);
+        while let Some(node) = current {
+            if !self.nodes[node.0].visible {
+                return false;
+            }
+            current = self.nodes[node.0].parent;
+        }
+        true
+    }
+
+    /// Construye una escena nueva con el contenido visible del grafo.
+    pub fn flatten(&self) -> Scene {
+        let mut scene = Scene::new();
+        self.flatten_into(&mut scene);
+        scene.build_tlas();
+        scene
+    }
+
+    /// Añade a `scene` una instancia por nodo visible con geometría y sus luces.
+    /// Los nodos que se mueven (o cuelgan de uno que se mueve) dan instancias con movimiento.
+    pub fn flatten_into(&self, scene: &mut Scene) {
+        let mut stack = vec![(self.root(), Transform::identity(), None::<MotionTransform>, None::<Arc<Material>>)];
+        while let Some((id, parent_transform, parent_motion, inherited_material)) = stack.pop() {
+            let node = &self.nodes[id.0];
+            if !node.visible {
+                continue;
+            }
+            let motion = match (&node.motion, &parent_motion) {
+                (None, None) => None,
+                (local, parent) => {
+                    let local = local.clone().unwrap_or_else(|| MotionTransform::fixed(node.transform));
+                    Some(local.then(&parent.clone().unwrap_or_else(|| MotionTransform::fixed(parent_transform))))
+                }
+            };
+            let world = match &motion {
+                Some(motion) => motion.start(),
+                None => node.transform.then(&parent_transform),
+            };
+            let material = node.material.clone().or(inherited_material);
+            if let Some(geometry) = &node.geometry {
+                let mut instance = Instance::new(geometry.clone(), world);
+                if let Some(material) = &material {
+                    instance = instance.with_material(material.clone());
+                }
+                if let Some(motion) = &motion {
+                    instance = instance.with_motion(motion.clone());
+                }
+                scene.add_instance(instance);
+            }
+            for light in &node.lights {
+                let spot = light.spot.map(|spot| Spot { direction: world.direction(spot.direction), ..spot });
+                scene.add_light(Light { position: world.point(light.position), spot, ..*light });
+            }
+            stack.extend(node.children.iter().rev().map(|child| (*child, world, motion.clone(), material.clone())));
+        }
+    }
+}
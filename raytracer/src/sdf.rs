@@ -0,0 +1,249 @@
+// sdf.rs
+use raylib::prelude::Vector3;
+
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect, wrap_uv};
+
+/// Pasos máximos de la marcha por esferas antes de dar el rayo por perdido.
+const MAX_STEPS: usize = 256;
+/// Distancia a la superficie (relativa a lo recorrido) a la que se considera que el rayo ha llegado.
+const HIT_EPSILON: f32 = 1e-4;
+/// Hasta dónde se marcha si la forma no está acotada (repetición infinita).
+const MAX_DISTANCE: f32 = 100.0;
+/// Paso de las diferencias finitas para la normal.
+const GRADIENT_STEP: f32 = 1e-4;
+
+/// Función de distancia con signo: negativa dentro, positiva fuera, y nunca mayor que la distancia
+/// real a la superficie (así la marcha por esferas no se la salta).
+#[derive(Debug, Clone)]
+pub enum Sdf {
+    Sphere { center: Vector3, radius: f32 },
+    Box { center: Vector3, half_size: Vector3 },
+    /// Caja con las aristas redondeadas con radio `radius` (sin salirse de `half_size`).
+    RoundedBox { center: Vector3, half_size: Vector3, radius: f32 },
+    /// Toro horizontal (alrededor del eje Y).
+    Torus { center: Vector3, major_radius: f32, minor_radius: f32 },
+    Union(Box<Sdf>, Box<Sdf>),
+    /// Unión que funde las dos formas en una zona de ancho `smoothness`.
+    SmoothUnion { a: Box<Sdf>, b: Box<Sdf>, smoothness: f32 },
+    /// Copias de `shape` cada `period` (una componente a 0 no repite en ese eje), hasta `count`
+    /// copias a cada lado de la original (`f32::INFINITY` repite sin límite).
+    Repeat { shape: Box<Sdf>, origin: Vector3, period: Vector3, count: Vector3 },
+}
+
+impl Sdf {
+    pub fn sphere(center: Vector3, radius: f32) -> Self {
+        Sdf::Sphere { center, radius }
+    }
+
+    pub fn cuboid(center: Vector3, size: Vector3) -> Self {
+        Sdf::Box { center, half_size: size / 2.0 }
+    }
+
+    pub fn rounded_cuboid(center: Vector3, size: Vector3, radius: f32) -> Self {
+        let half_size = size / 2.0;
+        let radius = radius.min(half_size.x).min(half_size.y).min(half_size.z);
+        Sdf::RoundedBox { center, half_size, radius }
+    }
+
+    pub fn torus(center: Vector3, major_radius: f32, minor_radius: f32) -> Self {
+        Sdf::Torus { center, major_radius, minor_radius }
+    }
+
+    pub fn union(self, other: Sdf) -> Self {
+        Sdf::Union(Box::new(self), Box::new(other))
+    }
+
+    pub fn smooth_union(self, other: Sdf, smoothness: f32) -> Self {
+        Sdf::SmoothUnion { a: Box::new(self), b: Box::new(other), smoothness }
+    }
+
+    /// Repite la forma sin límite con el periodo dado.
+    pub fn repeat(self, period: Vector3) -> Self {
+        let count = Vector3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+        self.repeat_limited(period, count)
+    }
+
+    /// Repite la forma `count` veces a cada lado en cada eje.
+    pub fn repeat_limited(self, period: Vector3, count: Vector3) -> Self {
+        // Las celdas se centran en la forma original y no en el origen del mundo
+        let origin = self.bounds().map(|bounds| bounds.centroid()).unwrap_or(Vector3::zero());
+        Sdf::Repeat { shape: Box::new(self), origin, period, count }
+    }
+
+    /// Distancia con signo desde `p` a la superficie.
+    pub fn distance(&self, p: Vector3) -> f32 {
+        match self {
+            Sdf::Sphere { center, radius } => (p - *center).length() - radius,
+            Sdf::Box { center, half_size } => box_distance(p - *center, *half_size),
+            Sdf::RoundedBox { center, half_size, radius } => {
+                let inner = *half_size - Vector3::new(*radius, *radius, *radius);
+                box_distance(p - *center, inner) - radius
+            }
+            Sdf::Torus { center, major_radius, minor_radius } => {
+                let local = p - *center;
+                let ring = (local.x * local.x + local.z * local.z).sqrt() - major_radius;
+                (ring * ring + local.y * local.y).sqrt() - minor_radius
+            }
+            Sdf::Union(a, b) => a.distance(p).min(b.distance(p)),
+            Sdf::SmoothUnion { a, b, smoothness } => {
+                // Mínimo suave polinómico
+                let (da, db) = (a.distance(p), b.distance(p));
+                let k = smoothness.max(1e-6);
+                let h = (0.5 + 0.5 * (db - da) / k).clamp(0.0, 1.0);
+                db + (da - db) * h - k * h * (1.0 - h)
+            }
+            Sdf::Repeat { shape, origin, period, count } => {
+                let cell = |x: f32, period: f32, count: f32| {
+                    if period > 0.0 { x - period * (x / period).round().clamp(-count, count) } else { x }
+                };
+                let local = p - *origin;
+                let local = Vector3::new(
+                    cell(local.x, period.x, count.x),
+                    cell(local.y, period.y, count.y),
+                    cell(local.z, period.z, count.z),
+                );
+                shape.distance(*origin + local)
+            }
+        }
+    }
+
+    /// Caja que envuelve la superficie; `None` si la repetición no tiene límite.
+    pub fn bounds(&self) -> Option<Aabb> {
+        match self {
+            Sdf::Sphere { center, radius } => {
+                let r = Vector3::new(*radius, *radius, *radius);
+                Some(Aabb::new(*center - r, *center + r))
+            }
+            Sdf::Box { center, half_size } | Sdf::RoundedBox { center, half_size, .. } => {
+                Some(Aabb::new(*center - *half_size, *center + *half_size))
+            }
+            Sdf::Torus { center, major_radius, minor_radius } => {
+                let outer = major_radius + minor_radius;
+                let extent = Vector3::new(outer, *minor_radius, outer);
+                Some(Aabb::new(*center - extent, *center + extent))
+            }
+            Sdf::Union(a, b) => Some(a.bounds()?.union(&b.bounds()?)),
+            Sdf::SmoothUnion { a, b, smoothness } => {
+                // El mínimo suave puede hinchar la unión hasta un cuarto de `smoothness`
+                let bounds = a.bounds()?.union(&b.bounds()?);
+                let grow = Vector3::new(1.0, 1.0, 1.0) * (smoothness * 0.25);
+                Some(Aabb::new(bounds.min - grow, bounds.max + grow))
+            }
+            Sdf::Repeat { shape, period, count, .. } => {
+                let spread = |period: f32, count: f32| if period > 0.0 { period * count } else { 0.0 };
+                let spread = Vector3::new(spread(period.x, count.x), spread(period.y, count.y), spread(period.z, count.z));
+                if !(spread.x.is_finite() && spread.y.is_finite() && spread.z.is_finite()) {
+                    return None;
+                }
+                let bounds = shape.bounds()?;
+                Some(Aabb::new(bounds.min - spread, bounds.max + spread))
+            }
+        }
+    }
+}
+
+/// Distancia con signo a una caja centrada en el origen.
+fn box_distance(p: Vector3, half_size: Vector3) -> f32 {
+    let q = Vector3::new(p.x.abs(), p.y.abs(), p.z.abs()) - half_size;
+    let outside = Vector3::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).length();
+    outside + q.x.max(q.y).max(q.z).min(0.0)
+}
+
+/// Objeto definido por una función de distancia con signo, dibujado por marcha de esferas.
+/// La normal es el gradiente de la función y las UV se proyectan desde el eje dominante de la
+/// normal (como las caras de un bloque), repitiendo la textura cada `texture_scale` unidades.
+pub struct SdfObject {
+    pub sdf: Sdf,
+    pub material: Material,
+    pub texture_scale: f32,
+}
+
+impl SdfObject {
+    pub fn new(sdf: Sdf, material: Material) -> Self {
+        SdfObject {
+            sdf,
+            material,
+            texture_scale: 1.0,
+        }
+    }
+
+    /// Gradiente normalizado por diferencias finitas en un tetraedro (cuatro evaluaciones).
+    fn normal(&self, p: Vector3) -> Vector3 {
+        let h = GRADIENT_STEP;
+        let k1 = Vector3::new(1.0, -1.0, -1.0);
+        let k2 = Vector3::new(-1.0, -1.0, 1.0);
+        let k3 = Vector3::new(-1.0, 1.0, -1.0);
+        let k4 = Vector3::new(1.0, 1.0, 1.0);
+        let gradient = k1 * self.sdf.distance(p + k1 * h)
+            + k2 * self.sdf.distance(p + k2 * h)
+            + k3 * self.sdf.distance(p + k3 * h)
+            + k4 * self.sdf.distance(p + k4 * h);
+        gradient.normalized()
+    }
+
+    /// UV y base tangente proyectando sobre el plano perpendicular al eje dominante de la normal,
+    /// con tangente × bitangente del lado de la normal.
+    fn surface(&self, point: Vector3, normal: Vector3) -> (f32, f32, Vector3, Vector3) {
+        let p = point / self.texture_scale;
+        let (tangent, bitangent) = if normal.x.abs() >= normal.y.abs() && normal.x.abs() >= normal.z.abs() {
+            (Vector3::new(0.0, 0.0, -normal.x.signum()), Vector3::new(0.0, 1.0, 0.0))
+        } else if normal.y.abs() >= normal.z.abs() {
+            (Vector3::new(-normal.y.signum(), 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0))
+        } else {
+            (Vector3::new(normal.z.signum(), 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0))
+        };
+        let u = wrap_uv(p.dot(tangent));
+        let v = wrap_uv(p.dot(bitangent));
+        // En las zonas curvas la base se proyecta sobre el plano tangente
+        let tangent = tangent - normal * normal.dot(tangent);
+        let bitangent = bitangent - normal * normal.dot(bitangent);
+        (u, v, tangent * self.texture_scale, bitangent * self.texture_scale)
+    }
+}
+
+impl RayIntersect for SdfObject {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Intersect {
+        let length = ray_direction.length();
+        if length < 1e-12 {
+            return Intersect::empty();
+        }
+        let direction = *ray_direction / length;
+
+        // Solo se marcha por el tramo del rayo dentro de la caja envolvente
+        let (mut t, end) = match self.sdf.bounds() {
+            Some(bounds) => {
+                let inv_direction = Vector3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+                let Some(enter) = bounds.hit(ray_origin, &inv_direction, f32::INFINITY) else {
+                    return Intersect::empty();
+                };
+                (enter, enter + bounds.extent().length())
+            }
+            None => (0.0, MAX_DISTANCE),
+        };
+
+        // Desde dentro se marcha con la distancia cambiada de signo hasta salir
+        let min_distance = 0.001 * length;
+        let side = if self.sdf.distance(*ray_origin + direction * t.max(min_distance)) < 0.0 { -1.0 } else { 1.0 };
+        for _ in 0..MAX_STEPS {
+            let point = *ray_origin + direction * t;
+            let distance = self.sdf.distance(point) * side;
+            if distance < HIT_EPSILON * t.max(1.0) && t > min_distance {
+                let normal = self.normal(point);
+                let (u, v, tangent, bitangent) = self.surface(point, normal);
+                return Intersect::new(self.material.clone(), t / length, normal, point, u, v)
+                    .with_tangent_frame(tangent, bitangent);
+            }
+            t += distance.max(HIT_EPSILON);
+            if t > end {
+                break;
+            }
+        }
+        Intersect::empty()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.sdf.bounds()
+    }
+}
@@ -0,0 +1,44 @@
+// settings.rs
+use raylib::prelude::*;
+
+use crate::ibl::IblMode;
+use crate::motion::Shutter;
+use crate::occlusion::AoMode;
+
+/// Parámetros de calidad y de iluminación que se pasan a `render` y `cast_ray`.
+#[derive(Clone, Copy, Debug)]
+pub struct RenderSettings {
+    pub ibl_mode: IblMode,  // Cómo ilumina el entorno la escena
+    pub ibl_strength: f32,  // Multiplicador de la luz que aporta el entorno
+    pub glossy_samples: u32, // Rayos por reflejo/refracción rugosa en el primer impacto (los rebotes usan 1)
+    pub area_light_samples: u32, // Puntos muestreados en cada luz de área en el primer impacto (los rebotes usan 1)
+    pub exposure: f32,      // Multiplicador de la radiancia antes de pasarla a color de pantalla
+    pub ambient_light: Vector3, // Luz ambiente uniforme, para que las zonas sin luz directa no queden negras
+    pub ao_mode: AoMode,    // Oclusión ambiental que oscurece la luz ambiente y la del entorno
+    pub shutter: Shutter,   // Parte del fotograma con el obturador abierto; si dura algo, hay desenfoque de movimiento
+    pub time_samples: u32,  // Instantes por píxel con el obturador abierto
+    pub lens_samples: u32,  // Puntos de la lente por píxel si la cámara tiene apertura
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            ibl_mode: IblMode::Precomputed,
+            ibl_strength: 0.6,
+            glossy_samples: 4,
+            area_light_samples: 4,
+            exposure: 1.0,
+            ambient_light: Vector3::new(0.08, 0.08, 0.08),
+            ao_mode: AoMode::Off,
+            shutter: Shutter::default(),
+            time_samples: 8,
+            lens_samples: 8,
+        }
+    }
+}
+
+/// Exposición para escenas en unidades físicas a partir del valor de exposición EV100
+/// (p. ej. 15 a pleno sol, 7 en un interior iluminado): una luminancia de 1.2 · 2^EV100 queda en blanco.
+pub fn exposure_from_ev100(ev100: f32) -> f32 {
+    1.0 / (1.2 * 2.0_f32.powf(ev100))
+}
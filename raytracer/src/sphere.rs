@@ -1,7 +1,8 @@
 // sphere.rs
 use raylib::prelude::Vector3;
-use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::ray_intersect::{Intersect, RayIntersect, Span};
 use crate::material::{Material};
+use crate::sampling::orthonormal_basis;
 use std::f32::consts::PI;
 
 pub struct Sphere {
@@ -17,10 +18,30 @@ impl Sphere {
         let v = 0.5 + normlaized.y.asin() / PI;
         (u, v)
     }
+
+    /// Tangente a lo largo de la longitud (u) y bitangente a lo largo de la latitud (v).
+    fn get_tangent_frame(&self, normal: &Vector3) -> (Vector3, Vector3) {
+        let tangent = Vector3::new(normal.z, 0.0, -normal.x);
+        if tangent.length() < 1e-6 {
+            // En los polos la longitud no está definida: cualquier base sirve.
+            return orthonormal_basis(*normal);
+        }
+        let tangent = tangent.normalized();
+        (tangent, normal.cross(tangent))
+    }
+
+    /// Intersección en el punto del rayo a distancia `t`, que está sobre la esfera.
+    fn hit_at(&self, ray_origin: &Vector3, ray_direction: &Vector3, t: f32) -> Intersect {
+        let point = *ray_origin + *ray_direction * t;
+        let normal = (point - self.center).normalized();
+        let (u, v) = self.get_uv(&point);
+        let (tangent, bitangent) = self.get_tangent_frame(&normal);
+        Intersect::new(self.material.clone(), t, normal, point, u, v).with_tangent_frame(tangent, bitangent)
+    }
 }
 
 impl RayIntersect for Sphere {
-    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Intersect {
         let oc = *ray_origin - self.center;
 
         let a = ray_direction.dot(*ray_direction);
@@ -35,6 +56,7 @@ impl RayIntersect for Sphere {
             let normal = (point - self.center).normalized();
             let (u, v) = self.get_uv(&point);
             if t > 0.0 {
+                let (tangent, bitangent) = self.get_tangent_frame(&normal);
                 return Intersect::new(
                     self.material.clone(),
                     t,
@@ -42,10 +64,25 @@ impl RayIntersect for Sphere {
                     point,
                     u,
                     v
-                );
+                ).with_tangent_frame(tangent, bitangent);
             }
         }
 
         Intersect::empty()
     }
-}
\ No newline at end of file
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Vec<Span> {
+        let oc = *ray_origin - self.center;
+        let a = ray_direction.dot(*ray_direction);
+        let b = 2.0 * oc.dot(*ray_direction);
+        let c = oc.dot(oc) - self.radius * self.radius;
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant <= 0.0 {
+            return Vec::new();
+        }
+        let root = discriminant.sqrt();
+        vec![Span {
+            enter: self.hit_at(ray_origin, ray_direction, (-b - root) / (2.0 * a)),
+            exit: self.hit_at(ray_origin, ray_direction, (-b + root) / (2.0 * a)),
+        }]
+    }
+}
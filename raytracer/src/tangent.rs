@@ -0,0 +1,78 @@
+// tangent.rs
+use raylib::prelude::*;
+
+use crate::sampling::orthonormal_basis;
+
+/// Tangente y bitangente de un triángulo a partir de sus posiciones y coordenadas UV,
+/// es decir, las derivadas dP/du y dP/dv de su parametrización (sin normalizar).
+/// Devuelve `None` si las UVs son degeneradas.
+pub fn triangle_tangents(
+    positions: [Vector3; 3],
+    uvs: [(f32, f32); 3],
+) -> Option<(Vector3, Vector3)> {
+    let edge1 = positions[1] - positions[0];
+    let edge2 = positions[2] - positions[0];
+    let du1 = uvs[1].0 - uvs[0].0;
+    let dv1 = uvs[1].1 - uvs[0].1;
+    let du2 = uvs[2].0 - uvs[0].0;
+    let dv2 = uvs[2].1 - uvs[0].1;
+
+    let determinant = du1 * dv2 - du2 * dv1;
+    if determinant.abs() < 1e-12 {
+        return None;
+    }
+    let r = 1.0 / determinant;
+    let tangent = (edge1 * dv2 - edge2 * dv1) * r;
+    let bitangent = (edge2 * du1 - edge1 * du2) * r;
+    Some((tangent, bitangent))
+}
+
+/// Tangentes por vértice al estilo MikkTSpace: se acumulan las tangentes de los triángulos
+/// que comparten cada vértice, ponderadas por el ángulo de la esquina, se ortogonalizan
+/// contra la normal del vértice (Gram-Schmidt) y se guarda el signo de la bitangente en `w`.
+///
+/// `indices` contiene tríos de índices a `positions`, `normals` y `uvs` (mismas longitudes).
+/// El resultado es `[tx, ty, tz, w]`; la bitangente se reconstruye como `w * normal × tangente`.
+pub fn compute_vertex_tangents(
+    positions: &[Vector3],
+    normals: &[Vector3],
+    uvs: &[(f32, f32)],
+    indices: &[[usize; 3]],
+) -> Vec<[f32; 4]> {
+    let mut tangents = vec![Vector3::zero(); positions.len()];
+    let mut bitangents = vec![Vector3::zero(); positions.len()];
+
+    for triangle in indices {
+        let p = [positions[triangle[0]], positions[triangle[1]], positions[triangle[2]]];
+        let uv = [uvs[triangle[0]], uvs[triangle[1]], uvs[triangle[2]]];
+        let Some((tangent, bitangent)) = triangle_tangents(p, uv) else {
+            continue;
+        };
+        for corner in 0..3 {
+            let a = p[(corner + 1) % 3] - p[corner];
+            let b = p[(corner + 2) % 3] - p[corner];
+            let length = a.length() * b.length();
+            if length <= 0.0 {
+                continue;
+            }
+            let angle = (a.dot(b) / length).clamp(-1.0, 1.0).acos();
+            tangents[triangle[corner]] += tangent * angle;
+            bitangents[triangle[corner]] += bitangent * angle;
+        }
+    }
+
+    normals
+        .iter()
+        .enumerate()
+        .map(|(i, &normal)| {
+            let tangent = tangents[i] - normal * normal.dot(tangents[i]);
+            let tangent = if tangent.length() > 1e-8 {
+                tangent.normalized()
+            } else {
+                orthonormal_basis(normal).0
+            };
+            let w = if normal.cross(tangent).dot(bitangents[i]) < 0.0 { -1.0 } else { 1.0 };
+            [tangent.x, tangent.y, tangent.z, w]
+        })
+        .collect()
+}
@@ -2,6 +2,10 @@
 use raylib::prelude::*;
 use std::collections::HashMap;
 
+use crate::environment::EnvironmentMap;
+use crate::ibl::ImageBasedLighting;
+use crate::light::Light;
+
 struct CpuTexture {
     width: i32,
     height: i32,
@@ -32,9 +36,10 @@ impl CpuTexture {
 }
 
 pub struct TextureManager {
-    cpu_textures: HashMap<String, CpuTexture>,
-    textures: HashMap<String, Texture2D>, // Store GPU textures for rendering
+    cpu_textures: HashMap<String, CpuTexture>, // Todo se muestrea en la CPU: no hace falta ventana ni GPU
     skybox_textures: Option<SkyboxTextures>,
+    environment: Option<EnvironmentMap>, // HDRI equirectangular, tiene prioridad sobre el skybox
+    ibl: Option<ImageBasedLighting>,     // Iluminación precalculada del entorno actual
 }
 
 #[derive(Clone)]
@@ -52,46 +57,116 @@ impl TextureManager {
         Self::default()
     }
 
-    pub fn load_texture(
-        &mut self,
-        rl: &mut RaylibHandle,
-        thread: &RaylibThread,
-        path: &str,
-    ) {
-        if self.textures.contains_key(path) {
-            return;
+    /// Lee una imagen y guarda sus píxeles para muestrearlos. Solo usa la CPU, así que funciona
+    /// antes de abrir la ventana (o sin abrirla, al renderizar secuencias).
+    pub fn load_texture(&mut self, path: &str) -> Result<(), String> {
+        if self.cpu_textures.contains_key(path) {
+            return Ok(());
         }
 
         let image = Image::load_image(path)
-            .unwrap_or_else(|_| panic!("Failed to load image {}", path));
-
-        let texture = rl
-            .load_texture_from_image(thread, &image)
-            .unwrap_or_else(|_| panic!("Failed to load texture {}", path));
-
-        let cpu_texture = CpuTexture::from_image(&image);
-
-        self.cpu_textures.insert(path.to_string(), cpu_texture);
-        self.textures.insert(path.to_string(), texture);
-    }
-
-    pub fn load_skybox(
-        &mut self,
-        rl: &mut RaylibHandle,
-        thread: &RaylibThread,
-        skybox: SkyboxTextures,
-    ) {
-        self.load_texture(rl, thread, &skybox.front);
-        self.load_texture(rl, thread, &skybox.back);
-        self.load_texture(rl, thread, &skybox.left);
-        self.load_texture(rl, thread, &skybox.right);
-        self.load_texture(rl, thread, &skybox.top);
-        self.load_texture(rl, thread, &skybox.bottom);
+            .map_err(|e| format!("No se pudo cargar la textura '{}': {}", path, e))?;
+
+        self.cpu_textures.insert(path.to_string(), CpuTexture::from_image(&image));
+        Ok(())
+    }
+
+    /// Carga una imagen ya leída en memoria (por ejemplo incrustada en un GLB) y la registra
+    /// con `key` como si fuera su ruta. `file_type` es la extensión con punto (".png").
+    /// Los bytes pueden venir de un archivo ajeno: si no son una imagen válida se devuelve un error.
+    pub fn load_texture_from_memory(&mut self, key: &str, file_type: &str, bytes: &[u8]) -> Result<(), String> {
+        if self.cpu_textures.contains_key(key) {
+            return Ok(());
+        }
+
+        let image = Image::load_image_from_mem(file_type, bytes)
+            .map_err(|e| format!("No se pudo cargar la textura '{}': {}", key, e))?;
+
+        self.cpu_textures.insert(key.to_string(), CpuTexture::from_image(&image));
+        Ok(())
+    }
+
+    /// Carga las seis caras; si falla alguna el skybox no se usa.
+    pub fn load_skybox(&mut self, skybox: SkyboxTextures) -> Result<(), String> {
+        self.load_texture(&skybox.front)?;
+        self.load_texture(&skybox.back)?;
+        self.load_texture(&skybox.left)?;
+        self.load_texture(&skybox.right)?;
+        self.load_texture(&skybox.top)?;
+        self.load_texture(&skybox.bottom)?;
         self.skybox_textures = Some(skybox);
+        Ok(())
+    }
+
+    /// Carga un HDRI equirectangular (`.hdr`) que sustituye al skybox de seis caras.
+    pub fn load_environment(&mut self, path: &str, rotation: f32, intensity: f32) -> Result<(), String> {
+        self.environment = Some(EnvironmentMap::load_hdr(path, rotation, intensity)?);
+        Ok(())
+    }
+
+    pub fn environment(&self) -> Option<&EnvironmentMap> {
+        self.environment.as_ref()
+    }
+
+    /// Gira el HDRI alrededor del eje Y (en radianes). No hace nada si no hay HDRI.
+    /// La IBL gira con él sin recalcularse.
+    pub fn rotate_environment(&mut self, delta: f32) {
+        if let Some(environment) = &mut self.environment {
+            environment.rotation = (environment.rotation + delta).rem_euclid(2.0 * std::f32::consts::PI);
+            if let Some(ibl) = &mut self.ibl {
+                ibl.rotation = environment.rotation;
+            }
+        }
+    }
+
+    /// Multiplica la intensidad del HDRI (y la de su IBL) por `factor`.
+    pub fn scale_environment_intensity(&mut self, factor: f32) {
+        if let Some(environment) = &mut self.environment {
+            environment.intensity = (environment.intensity * factor).max(0.0);
+            if let Some(ibl) = &mut self.ibl {
+                ibl.intensity = environment.intensity;
+            }
+        }
+    }
+
+    /// Precalcula la iluminación basada en imagen a partir del fondo actual (HDRI, skybox o cielo procedural).
+    /// Hay que llamarla de nuevo cada vez que cambie el fondo. Con HDRI se precalcula sin girar, con
+    /// intensidad 1 y sin el sol, que ya ilumina como luz directa (`environment_light`).
+    pub fn update_ibl(&mut self) {
+        let ibl = match &self.environment {
+            Some(environment) => {
+                let mut ibl = ImageBasedLighting::build(|direction| environment.sample_unrotated_without_sun(direction));
+                ibl.rotation = environment.rotation;
+                ibl.intensity = environment.intensity;
+                ibl
+            }
+            None => ImageBasedLighting::build(|direction| self.sample_skybox(direction)),
+        };
+        self.ibl = Some(ibl);
+    }
+
+    pub fn ibl(&self) -> Option<&ImageBasedLighting> {
+        self.ibl.as_ref()
+    }
+
+    /// Luz direccional que aporta el sol del HDRI al punto dado, si lo hay.
+    pub fn environment_light(&self, point: Vector3) -> Option<Light> {
+        self.environment.as_ref().and_then(|environment| environment.sun_light(point))
+    }
+
+    /// Radiancia del fondo que ilumina la escena de forma indirecta: sin el sol del HDRI, que ya
+    /// se suma como luz directa. Sin HDRI es la del skybox.
+    pub fn environment_radiance(&self, direction: Vector3) -> Vector3 {
+        match &self.environment {
+            Some(environment) => environment.sample_without_sun(direction),
+            None => self.sample_skybox(direction),
+        }
     }
 
     pub fn sample_skybox(&self, direction: Vector3) -> Vector3 {
-        if let Some(ref skybox) = self.skybox_textures {
+        if let Some(ref environment) = self.environment {
+            environment.sample(direction)
+        } else if let Some(ref skybox) = self.skybox_textures {
             // Mapear la dirección a las caras del cubo
             let abs_x = direction.x.abs();
             let abs_y = direction.y.abs();
@@ -198,11 +273,45 @@ impl TextureManager {
         }
     }
 
-    pub fn get_texture(
-        &self,
-        path: &str,
-    ) -> Option<&Texture2D> {
-        self.textures.get(path)
+    /// Color de una textura en coordenadas (u, v) en [0, 1], sin filtrar.
+    pub fn sample_texture(&self, path: &str, u: f32, v: f32) -> Vector3 {
+        match self.cpu_textures.get(path) {
+            Some(cpu_texture) => {
+                let tx = (u * cpu_texture.width as f32) as u32;
+                let ty = (v * cpu_texture.height as f32) as u32;
+                self.get_pixel_color(path, tx, ty)
+            }
+            None => Vector3::one(),
+        }
+    }
+
+    /// Altura en [0, 1] (luminancia) de un mapa de alturas con filtrado bilineal y UVs repetidas.
+    pub fn sample_height(&self, path: &str, u: f32, v: f32) -> f32 {
+        let Some(cpu_texture) = self.cpu_textures.get(path) else {
+            return 0.0;
+        };
+        let width = cpu_texture.width as i64;
+        let height = cpu_texture.height as i64;
+        let x = u * width as f32 - 0.5;
+        let y = v * height as f32 - 0.5;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+        let texel = |x: i64, y: i64| {
+            let index = (y.rem_euclid(height) * width + x.rem_euclid(width)) as usize;
+            let c = cpu_texture.pixels[index];
+            0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+        };
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = texel(x0, y0) * (1.0 - fx) + texel(x0 + 1, y0) * fx;
+        let bottom = texel(x0, y0 + 1) * (1.0 - fx) + texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    /// Tamaño en píxeles de una textura cargada.
+    pub fn texture_size(&self, path: &str) -> Option<(i32, i32)> {
+        self.cpu_textures.get(path).map(|t| (t.width, t.height))
     }
 
     pub fn get_normal_from_map(
@@ -241,8 +350,9 @@ impl Default for TextureManager {
     fn default() -> Self {
         TextureManager {
             cpu_textures: HashMap::new(),
-            textures: HashMap::new(),
             skybox_textures: None,
+            environment: None,
+            ibl: None,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,231 @@
+// torus.rs
+use raylib::prelude::Vector3;
+use std::f32::consts::PI;
+
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect, Span, spans_from_hits, wrap_uv};
+use crate::sampling::orthonormal_basis;
+
+/// Toro (donut) alrededor de `axis`: un tubo de radio `minor_radius` cuyo centro recorre una
+/// circunferencia de radio `major_radius`. Es un sólido con normales hacia fuera.
+/// UV: u alrededor del eje principal y v alrededor del tubo.
+pub struct Torus {
+    pub center: Vector3,
+    pub axis: Vector3,
+    pub major_radius: f32,
+    pub minor_radius: f32,
+    pub material: Material,
+}
+
+impl Torus {
+    pub fn new(center: Vector3, axis: Vector3, major_radius: f32, minor_radius: f32, material: Material) -> Self {
+        Torus {
+            center,
+            axis: axis.normalized(),
+            major_radius,
+            minor_radius,
+            material,
+        }
+    }
+
+    /// Distancias (ordenadas, de cualquier signo) a las que el rayo cruza la superficie.
+    /// Las raíces de la ecuación de cuarto grado se calculan en f64 desde el punto en el que el
+    /// rayo entra en la esfera envolvente, para no perder precisión con orígenes lejanos.
+    pub fn hits(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Vec<f32> {
+        let direction_length = ray_direction.length();
+        if direction_length < 1e-12 {
+            return Vec::new();
+        }
+        let direction = *ray_direction / direction_length;
+        let (axis_x, axis_z) = orthonormal_basis(self.axis);
+        let local = |v: Vector3| [v.dot(axis_x) as f64, v.dot(self.axis) as f64, v.dot(axis_z) as f64];
+        let mut o = local(*ray_origin - self.center);
+        let d = local(direction);
+
+        // Acercar el origen a la esfera envolvente (radio R + r)
+        let bound = (self.major_radius + self.minor_radius) as f64;
+        let b = o[0] * d[0] + o[1] * d[1] + o[2] * d[2];
+        let c = o[0] * o[0] + o[1] * o[1] + o[2] * o[2] - bound * bound;
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return Vec::new();
+        }
+        let shift = (-b - discriminant.sqrt()).max(0.0);
+        for i in 0..3 {
+            o[i] += d[i] * shift;
+        }
+
+        // (|p|² + R² - r²)² = 4R²(x² + z²) con p = o + t·d y |d| = 1
+        let major2 = (self.major_radius as f64).powi(2);
+        let minor2 = (self.minor_radius as f64).powi(2);
+        let m = o[0] * o[0] + o[1] * o[1] + o[2] * o[2];
+        let n = o[0] * d[0] + o[1] * d[1] + o[2] * d[2];
+        let k = m + major2 - minor2;
+        let planar_dd = d[0] * d[0] + d[2] * d[2];
+        let planar_od = o[0] * d[0] + o[2] * d[2];
+        let planar_oo = o[0] * o[0] + o[2] * o[2];
+        let coefficients = [
+            k * k - 4.0 * major2 * planar_oo,
+            4.0 * n * k - 8.0 * major2 * planar_od,
+            4.0 * n * n + 2.0 * k - 4.0 * major2 * planar_dd,
+            4.0 * n,
+            1.0,
+        ];
+
+        let mut hits: Vec<f32> = solve_quartic(coefficients)
+            .into_iter()
+            .map(|t| ((t + shift) / direction_length as f64) as f32)
+            .collect();
+        hits.sort_by(|a, b| a.total_cmp(b));
+        hits
+    }
+
+    /// Normal hacia fuera, UV y base tangente en un punto de la superficie.
+    fn surface(&self, point: Vector3) -> (Vector3, f32, f32, Vector3, Vector3) {
+        let local = point - self.center;
+        let y = local.dot(self.axis);
+        let radial_vector = local - self.axis * y;
+        let radial_distance = radial_vector.length();
+        let (axis_x, axis_z) = orthonormal_basis(self.axis);
+        let radial = if radial_distance > 1e-6 { radial_vector / radial_distance } else { axis_x };
+
+        // La normal sale del centro del tubo más cercano
+        let tube_center = self.center + radial * self.major_radius;
+        let normal = (point - tube_center).normalized();
+
+        let u = wrap_uv(radial.dot(axis_z).atan2(radial.dot(axis_x)) / (2.0 * PI));
+        let tube_angle = y.atan2(radial_distance - self.major_radius);
+        let v = wrap_uv(tube_angle / (2.0 * PI));
+
+        let around = self.axis.cross(radial);
+        let around_tube = normal.cross(around);
+        (
+            normal,
+            u,
+            v,
+            around * (2.0 * PI * radial_distance.max(1e-3)),
+            around_tube * (2.0 * PI * self.minor_radius),
+        )
+    }
+
+    /// Intersección en el punto del rayo a `distance`, que está sobre la superficie.
+    fn hit_at(&self, ray_origin: &Vector3, ray_direction: &Vector3, distance: f32) -> Intersect {
+        let point = *ray_origin + *ray_direction * distance;
+        let (normal, u, v, tangent, bitangent) = self.surface(point);
+        Intersect::new(self.material.clone(), distance, normal, point, u, v).with_tangent_frame(tangent, bitangent)
+    }
+}
+
+impl RayIntersect for Torus {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Intersect {
+        match self.hits(ray_origin, ray_direction).into_iter().find(|t| *t > 0.001) {
+            Some(distance) => self.hit_at(ray_origin, ray_direction, distance),
+            None => Intersect::empty(),
+        }
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Vec<Span> {
+        spans_from_hits(&self.hits(ray_origin, ray_direction), |t| self.hit_at(ray_origin, ray_direction, t))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let tube = Vector3::new(self.minor_radius, self.minor_radius, self.minor_radius);
+        let ring = Aabb::from_disk(self.center, self.axis, self.major_radius);
+        Some(Aabb::new(ring.min - tube, ring.max + tube))
+    }
+}
+
+/// Raíces reales de c[0] + c[1]·t + c[2]·t² con c[2] ≠ 0.
+fn solve_quadratic(c: [f64; 3]) -> Vec<f64> {
+    let p = c[1] / (2.0 * c[2]);
+    let q = c[0] / c[2];
+    let discriminant = p * p - q;
+    if discriminant.abs() < 1e-12 {
+        vec![-p]
+    } else if discriminant < 0.0 {
+        Vec::new()
+    } else {
+        let root = discriminant.sqrt();
+        vec![-p - root, -p + root]
+    }
+}
+
+/// Raíces reales de c[0] + c[1]·t + c[2]·t² + c[3]·t³ con c[3] ≠ 0 (método de Cardano).
+fn solve_cubic(c: [f64; 4]) -> Vec<f64> {
+    let a = c[2] / c[3];
+    let b = c[1] / c[3];
+    let d = c[0] / c[3];
+
+    // Cambio t = x - a/3 para quitar el término cuadrático: x³ + 3p·x + 2q = 0
+    let a2 = a * a;
+    let p = (-a2 / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * a2 - a * b / 3.0 + d) / 2.0;
+    let p3 = p * p * p;
+    let discriminant = q * q + p3;
+
+    let roots = if discriminant.abs() < 1e-12 {
+        if q.abs() < 1e-12 {
+            vec![0.0]
+        } else {
+            let u = (-q).cbrt();
+            vec![2.0 * u, -u]
+        }
+    } else if discriminant < 0.0 {
+        // Tres raíces reales (forma trigonométrica)
+        let phi = (-q / (-p3).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        vec![t * phi.cos(), -t * (phi + std::f64::consts::PI / 3.0).cos(), -t * (phi - std::f64::consts::PI / 3.0).cos()]
+    } else {
+        let root = discriminant.sqrt();
+        vec![(root - q).cbrt() - (root + q).cbrt()]
+    };
+    roots.into_iter().map(|x| x - a / 3.0).collect()
+}
+
+/// Raíces reales de c[0] + c[1]·t + … + c[4]·t⁴ con c[4] ≠ 0 (método de Ferrari),
+/// refinadas con unas iteraciones de Newton.
+fn solve_quartic(c: [f64; 5]) -> Vec<f64> {
+    let a = c[3] / c[4];
+    let b = c[2] / c[4];
+    let d = c[1] / c[4];
+    let e = c[0] / c[4];
+
+    // Cambio t = x - a/4: x⁴ + p·x² + q·x + r = 0
+    let a2 = a * a;
+    let p = -3.0 / 8.0 * a2 + b;
+    let q = a2 * a / 8.0 - a * b / 2.0 + d;
+    let r = -3.0 / 256.0 * a2 * a2 + a2 * b / 16.0 - a * d / 4.0 + e;
+
+    let mut roots = if r.abs() < 1e-12 {
+        // x · (x³ + p·x + q) = 0
+        let mut roots = solve_cubic([q, p, 0.0, 1.0]);
+        roots.push(0.0);
+        roots
+    } else {
+        // Una raíz z de la resolvente permite factorizar en dos cuadráticas
+        let z = solve_cubic([r * p / 2.0 - q * q / 8.0, -r, -p / 2.0, 1.0])[0];
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let u = if u.abs() < 1e-12 { 0.0 } else if u > 0.0 { u.sqrt() } else { return Vec::new() };
+        let v = if v.abs() < 1e-12 { 0.0 } else if v > 0.0 { v.sqrt() } else { return Vec::new() };
+        let sign = if q < 0.0 { -1.0 } else { 1.0 };
+        let mut roots = solve_quadratic([z - u, sign * v, 1.0]);
+        roots.extend(solve_quadratic([z + u, -sign * v, 1.0]));
+        roots
+    };
+
+    let polynomial = |t: f64| (((c[4] * t + c[3]) * t + c[2]) * t + c[1]) * t + c[0];
+    let derivative = |t: f64| ((4.0 * c[4] * t + 3.0 * c[3]) * t + 2.0 * c[2]) * t + c[1];
+    for root in roots.iter_mut() {
+        *root -= a / 4.0;
+        for _ in 0..3 {
+            let slope = derivative(*root);
+            if slope.abs() < 1e-12 {
+                break;
+            }
+            *root -= polynomial(*root) / slope;
+        }
+    }
+    roots
+}
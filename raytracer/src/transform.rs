@@ -0,0 +1,173 @@
+// transform.rs
+use raylib::prelude::{Matrix, Vector3};
+
+use crate::aabb::Aabb;
+use crate::area_light::EmissivePatch;
+use crate::ray_intersect::{Intersect, RayIntersect, Span};
+
+/// Transformación afín (matriz 4x4) con su inversa ya calculada.
+/// Las matrices siguen el convenio de raylib: `a * b` aplica primero `a` y después `b`.
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    pub matrix: Matrix,
+    pub inverse: Matrix,
+}
+
+impl Transform {
+    pub fn new(matrix: Matrix) -> Self {
+        Transform { matrix, inverse: matrix.inverted() }
+    }
+
+    pub fn identity() -> Self {
+        Transform { matrix: Matrix::identity(), inverse: Matrix::identity() }
+    }
+
+    pub fn translation(offset: Vector3) -> Self {
+        Transform {
+            matrix: Matrix::translate(offset.x, offset.y, offset.z),
+            inverse: Matrix::translate(-offset.x, -offset.y, -offset.z),
+        }
+    }
+
+    /// Giro de `angle` radianes alrededor de `axis` (regla de la mano derecha).
+    pub fn rotation(axis: Vector3, angle: f32) -> Self {
+        Transform {
+            matrix: Matrix::rotate(axis, angle),
+            inverse: Matrix::rotate(axis, -angle),
+        }
+    }
+
+    /// Escala por eje; admite factores distintos en cada eje (no nulos).
+    pub fn scale(factors: Vector3) -> Self {
+        Transform {
+            matrix: Matrix::scale(factors.x, factors.y, factors.z),
+            inverse: Matrix::scale(1.0 / factors.x, 1.0 / factors.y, 1.0 / factors.z),
+        }
+    }
+
+    /// Aplica primero `self` y después `next`.
+    pub fn then(&self, next: &Transform) -> Self {
+        Transform {
+            matrix: self.matrix * next.matrix,
+            inverse: next.inverse * self.inverse,
+        }
+    }
+
+    pub fn translated(&self, offset: Vector3) -> Self {
+        self.then(&Transform::translation(offset))
+    }
+
+    pub fn rotated(&self, axis: Vector3, angle: f32) -> Self {
+        self.then(&Transform::rotation(axis, angle))
+    }
+
+    pub fn scaled(&self, factors: Vector3) -> Self {
+        self.then(&Transform::scale(factors))
+    }
+
+    pub fn inverted(&self) -> Self {
+        Transform { matrix: self.inverse, inverse: self.matrix }
+    }
+
+    pub fn point(&self, point: Vector3) -> Vector3 {
+        point.transform_with(self.matrix)
+    }
+
+    pub fn direction(&self, direction: Vector3) -> Vector3 {
+        transform_direction(&self.matrix, direction)
+    }
+
+    /// Las normales se transforman con la inversa traspuesta para seguir siendo
+    /// perpendiculares a la superficie con escalas no uniformes.
+    pub fn normal(&self, normal: Vector3) -> Vector3 {
+        let m = &self.inverse;
+        Vector3::new(
+            m.m0 * normal.x + m.m1 * normal.y + m.m2 * normal.z,
+            m.m4 * normal.x + m.m5 * normal.y + m.m6 * normal.z,
+            m.m8 * normal.x + m.m9 * normal.y + m.m10 * normal.z,
+        )
+        .normalized()
+    }
+
+    /// Caja que envuelve a `bounds` una vez transformada (por sus ocho esquinas).
+    pub fn bounds(&self, bounds: &Aabb) -> Aabb {
+        let corners: Vec<Vector3> = (0..8)
+            .map(|i| {
+                let x = if i & 1 == 0 { bounds.min.x } else { bounds.max.x };
+                let y = if i & 2 == 0 { bounds.min.y } else { bounds.max.y };
+                let z = if i & 4 == 0 { bounds.min.z } else { bounds.max.z };
+                self.point(Vector3::new(x, y, z))
+            })
+            .collect();
+        Aabb::from_points(&corners)
+    }
+
+    /// Rayo del mundo expresado en el espacio del objeto (origen y dirección sin normalizar).
+    pub fn local_ray(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> (Vector3, Vector3) {
+        (ray_origin.transform_with(self.inverse), transform_direction(&self.inverse, *ray_direction))
+    }
+
+    /// Lleva una intersección calculada en el espacio del objeto al del mundo.
+    /// La distancia no cambia porque el rayo local no se normaliza.
+    pub fn intersect_to_world(&self, mut hit: Intersect, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        if !hit.is_intersecting {
+            return hit;
+        }
+        hit.point = *ray_origin + *ray_direction * hit.distance;
+        hit.normal = self.normal(hit.normal);
+        hit.tangent = self.direction(hit.tangent);
+        hit.bitangent = self.direction(hit.bitangent);
+        hit
+    }
+}
+
+/// Aplica la parte lineal de la matriz (sin traslación) a una dirección.
+pub fn transform_direction(m: &Matrix, v: Vector3) -> Vector3 {
+    Vector3::new(
+        m.m0 * v.x + m.m4 * v.y + m.m8 * v.z,
+        m.m1 * v.x + m.m5 * v.y + m.m9 * v.z,
+        m.m2 * v.x + m.m6 * v.y + m.m10 * v.z,
+    )
+}
+
+/// Cualquier primitiva colocada con una transformación: los rayos se llevan al espacio del
+/// objeto con la inversa y los impactos vuelven al mundo. Permite cubos girados, carteles
+/// inclinados o esferas estiradas en elipsoides.
+pub struct Transformed<T> {
+    pub object: T,
+    pub transform: Transform,
+}
+
+impl<T: RayIntersect> Transformed<T> {
+    pub fn new(object: T, transform: Transform) -> Self {
+        Transformed { object, transform }
+    }
+}
+
+impl<T: RayIntersect> RayIntersect for Transformed<T> {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Intersect {
+        let (origin, direction) = self.transform.local_ray(ray_origin, ray_direction);
+        let hit = self.object.ray_intersect(&origin, &direction, time);
+        self.transform.intersect_to_world(hit, ray_origin, ray_direction)
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Vec<Span> {
+        let (origin, direction) = self.transform.local_ray(ray_origin, ray_direction);
+        self.object
+            .ray_intervals(&origin, &direction, time)
+            .into_iter()
+            .map(|span| Span {
+                enter: self.transform.intersect_to_world(span.enter, ray_origin, ray_direction),
+                exit: self.transform.intersect_to_world(span.exit, ray_origin, ray_direction),
+            })
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.object.bounding_box().map(|bounds| self.transform.bounds(&bounds))
+    }
+
+    fn emissive_patches(&self) -> Vec<EmissivePatch> {
+        self.object.emissive_patches().iter().map(|patch| patch.transformed(&self.transform)).collect()
+    }
+}
@@ -0,0 +1,106 @@
+// triangle.rs
+use raylib::prelude::Vector3;
+use crate::ray_intersect::{Intersect, RayIntersect, wrap_uv};
+use crate::material::Material;
+use crate::aabb::Aabb;
+use crate::area_light::{AreaLight, EmissivePatch};
+use crate::sampling::orthonormal_basis;
+use crate::tangent::triangle_tangents;
+
+/// Triángulo con normales y coordenadas UV opcionales por vértice. Sin normales por vértice
+/// se usa la normal geométrica `(v1 - v0) × (v2 - v0)` (vértices en sentido antihorario).
+pub struct Triangle {
+    pub vertices: [Vector3; 3],
+    pub normals: Option<[Vector3; 3]>,
+    pub uvs: [(f32, f32); 3],
+    pub material: Material,
+}
+
+impl Triangle {
+    /// Triángulo plano cuyas UVs son las coordenadas baricéntricas.
+    pub fn new(v0: Vector3, v1: Vector3, v2: Vector3, material: Material) -> Self {
+        Triangle {
+            vertices: [v0, v1, v2],
+            normals: None,
+            uvs: [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)],
+            material,
+        }
+    }
+
+    pub fn with_normals(mut self, normals: [Vector3; 3]) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: [(f32, f32); 3]) -> Self {
+        self.uvs = uvs;
+        self
+    }
+
+    pub fn geometric_normal(&self) -> Vector3 {
+        (self.vertices[1] - self.vertices[0]).cross(self.vertices[2] - self.vertices[0]).normalized()
+    }
+}
+
+/// Algoritmo de Möller–Trumbore: resuelve directamente la distancia y las coordenadas
+/// baricéntricas (b1, b2) del punto de impacto. Devuelve `(distancia, b1, b2)`.
+pub fn intersect_triangle(
+    ray_origin: &Vector3,
+    ray_direction: &Vector3,
+    vertices: [Vector3; 3],
+) -> Option<(f32, f32, f32)> {
+    let [v0, v1, v2] = vertices;
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let p = ray_direction.cross(edge2);
+    let determinant = edge1.dot(p);
+    if determinant.abs() < 1e-10 {
+        return None; // Rayo paralelo al triángulo
+    }
+    let inv_determinant = 1.0 / determinant;
+
+    let t = *ray_origin - v0;
+    let b1 = t.dot(p) * inv_determinant;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let q = t.cross(edge1);
+    let b2 = ray_direction.dot(q) * inv_determinant;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    let distance = edge2.dot(q) * inv_determinant;
+    if distance < 0.001 {
+        return None;
+    }
+    Some((distance, b1, b2))
+}
+
+impl RayIntersect for Triangle {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Intersect {
+        let Some((distance, b1, b2)) = intersect_triangle(ray_origin, ray_direction, self.vertices) else {
+            return Intersect::empty();
+        };
+
+        let b0 = 1.0 - b1 - b2;
+        let point = *ray_origin + *ray_direction * distance;
+        let normal = match self.normals {
+            Some([n0, n1, n2]) => (n0 * b0 + n1 * b1 + n2 * b2).normalized(),
+            None => self.geometric_normal(),
+        };
+        let u = self.uvs[0].0 * b0 + self.uvs[1].0 * b1 + self.uvs[2].0 * b2;
+        let v = self.uvs[0].1 * b0 + self.uvs[1].1 * b1 + self.uvs[2].1 * b2;
+        let (tangent, bitangent) = triangle_tangents(self.vertices, self.uvs).unwrap_or_else(|| orthonormal_basis(normal));
+
+        Intersect::new(self.material.clone(), distance, normal, point, wrap_uv(u), wrap_uv(v))
+            .with_tangent_frame(tangent, bitangent)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_points(&self.vertices))
+    }
+
+    fn emissive_patches(&self) -> Vec<EmissivePatch> {
+        AreaLight::from_triangle(self).map(|light| light.patches).unwrap_or_default()
+    }
+}